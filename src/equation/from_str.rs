@@ -1,24 +1,26 @@
-use nom::bytes::complete::tag;
-use nom::error::Error;
-use nom::IResult;
-use nom::sequence::tuple;
+use std::str::FromStr;
 use crate::equation::Equation;
-use crate::expression::from_str::{expression, ws};
+use crate::expression::error::ParseError;
+use crate::expression::from_str::{ParseOptions, ParseResult, Parser, TokenKind};
 
-impl Equation{
-    pub fn from_str(input: &str) -> Result<Equation, nom::Err<Error<&str>>> {
-        equation(input).map(|(_, equation)| equation)
+impl FromStr for Equation{
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Equation, Self::Err> {
+        Equation::parse_with(input, ParseOptions::default())
     }
 }
 
-fn equation(input: &str) -> IResult<&str, Equation> {
-    let (input, (left, _, right)) = tuple((
-        expression,
-        ws(tag("=")),
-        expression
-    ))(input)?;
-
-    return Ok((input, Equation{left, right}));
+impl Equation {
+    /// Parses the input with the given settings, `from_str` uses the defaults
+    pub fn parse_with(input: &str, options: ParseOptions) -> ParseResult<Equation> {
+        let mut parser = Parser::new(input, options)?;
+        let left = parser.expression()?;
+        parser.expect(TokenKind::Operator('='))?;
+        let right = parser.expression()?;
+        parser.end()?;
+        Ok(Equation{left, right})
+    }
 }
 
 #[cfg(test)]
@@ -32,5 +34,10 @@ mod tests {
         let equation = Equation::from_str("x^2 + 2*x + 1 = 0").unwrap();
         assert_eq!(equation.left, Expression::from_str("x^2 + 2*x + 1").unwrap());
         assert_eq!(equation.right, Expression::from_str("0").unwrap());
+        let error = Equation::from_str("x = 1 = 2").unwrap_err();
+        assert_eq!((error.offset, error.expected.as_slice()), (6, ["an operator".to_string()].as_slice()));
+        let error = Equation::from_str("x + 1").unwrap_err();
+        assert_eq!((error.offset, error.expected.as_slice()), (5, ["\"=\"".to_string()].as_slice()));
+        assert_eq!(Equation::from_str("E = m c^2").unwrap().right, Expression::from_str("m * c^2").unwrap());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,145 @@
+use std::fmt::Display;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+use crate::expression::error::ParseError;
+use crate::expression::from_str::{ParseOptions, ParseResult, Parser};
+use crate::expression::{Comparison, Condition, Expression};
+use crate::neg;
+
+/// The comparisons that can stand between the sides of an inequality
+const INEQUALITIES: [Comparison; 5] = [
+    Comparison::Less,
+    Comparison::LessEqual,
+    Comparison::Greater,
+    Comparison::GreaterEqual,
+    Comparison::NotEqual,
+];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Inequality {
+    pub left: Expression,
+    pub comparison: Comparison,
+    pub right: Expression,
+}
+
+impl Inequality {
+    pub fn new(left: Expression, comparison: Comparison, right: Expression) -> Self {
+        Self { left, comparison, right }
+    }
+
+    /// The same inequality read from right to left, like `b > a` for `a < b`
+    pub fn reversed(&self) -> Inequality {
+        Inequality::new(self.right.clone(), self.comparison.reversed(), self.left.clone())
+    }
+
+    /// Negates both sides, which flips the direction
+    pub fn negate(&self) -> Inequality {
+        Inequality::new(neg!(self.left.clone()), self.comparison.reversed(), neg!(self.right.clone()))
+    }
+
+    /// Multiplies both sides by `factor`, flipping the direction when it is negative.
+    /// Returns `None` when the factor is zero or its sign depends on variables.
+    pub fn multiply(&self, factor: &Expression) -> Option<Inequality> {
+        let comparison = self.scaled_comparison(factor)?;
+        Some(Inequality::new(self.left.clone() * factor.clone(), comparison, self.right.clone() * factor.clone()))
+    }
+
+    /// Divides both sides by `divisor`, flipping the direction when it is negative.
+    /// Returns `None` when the divisor is zero or its sign depends on variables.
+    pub fn divide(&self, divisor: &Expression) -> Option<Inequality> {
+        let comparison = self.scaled_comparison(divisor)?;
+        Some(Inequality::new(self.left.clone() / divisor.clone(), comparison, self.right.clone() / divisor.clone()))
+    }
+
+    fn scaled_comparison(&self, factor: &Expression) -> Option<Comparison> {
+        match factor.solve(None).ok()? {
+            value if value > 0.0 => Some(self.comparison),
+            value if value < 0.0 => Some(self.comparison.reversed()),
+            _ => None,
+        }
+    }
+
+    /// Parses the input with the given settings, `from_str` uses the defaults
+    pub fn parse_with(input: &str, options: ParseOptions) -> ParseResult<Inequality> {
+        let mut parser = Parser::new(input, options)?;
+        let left = parser.expression()?;
+        let comparison = parser.comparator(&INEQUALITIES)?;
+        let right = parser.expression()?;
+        parser.end()?;
+        Ok(Inequality::new(left, comparison, right))
+    }
+}
+
+impl FromStr for Inequality {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Inequality, Self::Err> {
+        Inequality::parse_with(input, ParseOptions::default())
+    }
+}
+
+impl Add<Expression> for Inequality {
+    type Output = Inequality;
+
+    fn add(self, rhs: Expression) -> Self::Output {
+        Inequality::new(self.left + rhs.clone(), self.comparison, self.right + rhs)
+    }
+}
+
+impl Sub<Expression> for Inequality {
+    type Output = Inequality;
+
+    fn sub(self, rhs: Expression) -> Self::Output {
+        Inequality::new(self.left - rhs.clone(), self.comparison, self.right - rhs)
+    }
+}
+
+impl From<Inequality> for Condition {
+    fn from(inequality: Inequality) -> Self {
+        Condition::Compare(inequality.left, inequality.comparison, inequality.right)
+    }
+}
+
+impl Display for Inequality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.left, self.comparison, self.right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::equation::inequality::Inequality;
+    use crate::expression::{Comparison, Expression};
+    use crate::{num, var};
+
+    #[test]
+    fn test_parse() {
+        let inequality = Inequality::from_str("x^2 - 4 > 0").unwrap();
+        assert_eq!(inequality.left, Expression::from_str("x^2 - 4").unwrap());
+        assert_eq!(inequality.comparison, Comparison::Greater);
+        assert_eq!(Inequality::from_str("2x ≤ 1").unwrap().comparison, Comparison::LessEqual);
+        assert_eq!(Inequality::from_str("x != 1").unwrap().to_string(), "x != 1");
+
+        let error = Inequality::from_str("x = 1").unwrap_err();
+        assert_eq!(error.offset, 2);
+        assert_eq!(error.expected, ["\"<\"", "\"<=\"", "\">\"", "\">=\"", "\"!=\""]);
+        assert!(Inequality::from_str("x < 1 < 2").is_err());
+    }
+
+    #[test]
+    fn test_manipulate() {
+        let inequality = Inequality::from_str("x < 3").unwrap();
+        assert_eq!(inequality.reversed().to_string(), "3 > x");
+        assert_eq!(inequality.multiply(&num!(-2)).unwrap().comparison, Comparison::Greater);
+        assert_eq!(inequality.divide(&num!(2)).unwrap().comparison, Comparison::Less);
+        assert_eq!(inequality.multiply(&num!(0)), None);
+        assert_eq!(inequality.multiply(&var!("a")), None);
+        assert_eq!(inequality.negate().comparison, Comparison::Greater);
+        assert_eq!((inequality.clone() - num!(3)).right, num!(3) - num!(3));
+
+        let flipped = Inequality::from_str("x >= 1").unwrap().multiply(&num!(-1)).unwrap();
+        assert_eq!(flipped.comparison, Comparison::LessEqual);
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::{Display, Formatter};
+
+use crate::expression::Expression;
+
+/// One end of an interval
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    /// Unbounded, -∞ at the start and ∞ at the end
+    Infinite,
+    /// The value itself is excluded
+    Open(Expression),
+    /// The value itself is included
+    Closed(Expression),
+}
+
+impl Endpoint {
+    fn value(&self) -> Option<f64> {
+        match self {
+            Endpoint::Infinite => None,
+            Endpoint::Open(value) | Endpoint::Closed(value) => value.solve(None).ok(),
+        }
+    }
+}
+
+/// A connected set of real numbers, like (-∞, 2] or the single point {0}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub start: Endpoint,
+    pub end: Endpoint,
+}
+
+impl Interval {
+    pub fn new(start: Endpoint, end: Endpoint) -> Self {
+        Self { start, end }
+    }
+
+    /// All real numbers
+    pub fn all() -> Self {
+        Self::new(Endpoint::Infinite, Endpoint::Infinite)
+    }
+
+    pub fn point(value: Expression) -> Self {
+        Self::new(Endpoint::Closed(value.clone()), Endpoint::Closed(value))
+    }
+
+    /// Whether the interval contains `x`, which is false when an endpoint cannot be evaluated
+    pub fn contains(&self, x: f64) -> bool {
+        let above_start = match (&self.start, self.start.value()) {
+            (Endpoint::Infinite, _) => true,
+            (Endpoint::Open(_), Some(start)) => x > start,
+            (Endpoint::Closed(_), Some(start)) => x >= start,
+            (_, None) => false,
+        };
+        let below_end = match (&self.end, self.end.value()) {
+            (Endpoint::Infinite, _) => true,
+            (Endpoint::Open(_), Some(end)) => x < end,
+            (Endpoint::Closed(_), Some(end)) => x <= end,
+            (_, None) => false,
+        };
+        above_start && below_end
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let (Endpoint::Closed(start), Endpoint::Closed(end)) = (&self.start, &self.end) {
+            if start == end {
+                return write!(f, "{{{start}}}");
+            }
+        }
+        match &self.start {
+            Endpoint::Infinite => write!(f, "(-∞")?,
+            Endpoint::Open(start) => write!(f, "({start}")?,
+            Endpoint::Closed(start) => write!(f, "[{start}")?,
+        }
+        match &self.end {
+            Endpoint::Infinite => write!(f, ", ∞)"),
+            Endpoint::Open(end) => write!(f, ", {end})"),
+            Endpoint::Closed(end) => write!(f, ", {end}]"),
+        }
+    }
+}
+
+/// A union of disjoint intervals in ascending order, which is empty when nothing is included
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalSet(pub Vec<Interval>);
+
+impl IntervalSet {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.0.iter().any(|interval| interval.contains(x))
+    }
+}
+
+impl Display for IntervalSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "∅");
+        }
+        let intervals: Vec<String> = self.0.iter().map(|interval| interval.to_string()).collect();
+        write!(f, "{}", intervals.join(" ∪ "))
+    }
+}
@@ -1,19 +1,23 @@
 use crate::equation::Equation;
+use crate::expression::error::IsolateError;
+use crate::var;
 
 impl Equation{
-    pub fn isolate_variable(&self, variable: &str) -> Equation {
+    /// Rearranges the equation into the form `variable = ...`, on the principal branch of every inverse it applies
+    pub fn isolate_variable(&self, variable: &str) -> Result<Equation, IsolateError> {
         let left = self.left.contains_variable(variable);
         let right = self.right.contains_variable(variable);
 
-        // if left && right {
-        //     unimplemented!("Variable appears on both sides of the equation")
-        // } else if left {
-        //     return Equation{left: self.left.isolate_variable(variable), right: self.right.clone()};
-        // } else if right {
-        //     return Equation{left: self.right.separate_variable(variable), right: self.left.clone()};
-        // } else {
-        //     panic!("Variable does not appear on either side of the equation");
-        // }
-        todo!();
+        let (containing, other) = match (left, right) {
+            (true, true) => return Err(IsolateError::VariableOnBothSides(variable.to_string())),
+            (false, false) => return Err(IsolateError::VariableAbsent(variable.to_string())),
+            (true, false) => (&self.left, &self.right),
+            (false, true) => (&self.right, &self.left),
+        };
+
+        Ok(Equation{
+            left: var!(variable),
+            right: containing.isolate_variable(variable, other.clone())?,
+        })
     }
-}
\ No newline at end of file
+}
@@ -1,12 +1,19 @@
 pub mod from_str;
+pub mod inequality;
+pub mod interval;
 mod isolate_variable;
+pub mod solution_set;
+mod solve_for;
+mod solve_inequality;
+mod substitute;
 
 use std::fmt::Display;
 use crate::expression::Expression;
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct Equation {
-    left: Expression,
-    right: Expression
+    pub left: Expression,
+    pub right: Expression
 }
 
 impl Equation{
@@ -25,9 +32,13 @@ impl Display for Equation{
 
 #[cfg(test)]
 mod tests{
+    use std::collections::HashMap;
     use std::str::FromStr;
     use crate::equation::Equation;
     use crate::expression::Expression;
+    use crate::equation::solution_set::{Root, SolutionSet};
+    use crate::expression::error::IsolateError;
+    use crate::{num, var};
 
     #[test]
     fn test_from_str() {
@@ -36,8 +47,38 @@ mod tests{
         assert_eq!(equation.right, Expression::from_str("0").unwrap());
     }
 
+    #[test]
+    fn test_solve_for() {
+        let equation = Equation::from_str("x^2 + 2*x + 1 = 0").unwrap();
+        assert_eq!(equation.solve_for("x"), SolutionSet::Roots(vec![Root::real(num!(-1), 2)]));
+    }
+
     #[test]
     fn example(){
         let equation = Equation::from_str("P = O + V * t").unwrap();
+        let isolated = equation.isolate_variable("t").unwrap();
+        assert_eq!(isolated.left, var!("t"));
+        let variables = HashMap::from([
+            ("P".to_string(), 10.0),
+            ("O".to_string(), 4.0),
+            ("V".to_string(), 2.0),
+        ]);
+        assert_eq!(isolated.right.solve(Some(&variables)).unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_substitute(){
+        let equation = Equation::from_str("P = O + V * t").unwrap();
+        let velocity = Expression::from_str("d / t").unwrap();
+        let substituted = equation.substitute("V", &velocity);
+        assert_eq!(substituted.left, var!("P"));
+        assert_eq!(substituted.right.simplify(), (var!("O") + var!("d")).simplify());
+    }
+
+    #[test]
+    fn test_isolate_errors(){
+        let equation = Equation::from_str("x * a = x + 2").unwrap();
+        assert_eq!(equation.isolate_variable("x").err(), Some(IsolateError::VariableOnBothSides("x".to_string())));
+        assert_eq!(equation.isolate_variable("y").err(), Some(IsolateError::VariableAbsent("y".to_string())));
     }
 }
\ No newline at end of file
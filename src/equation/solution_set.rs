@@ -0,0 +1,112 @@
+use std::fmt::{Display, Formatter};
+
+use crate::expression::Expression;
+use crate::num;
+use crate::expression::display::parenthesize_if_of_type;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolutionSet {
+    /// Every value of the variable solves the equation, like for x = x
+    All,
+    /// All roots of the equation, which is empty when nothing solves it
+    Roots(Vec<Root>),
+    /// No closed form could be found, for example for polynomials above degree four
+    Unsolved,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Root {
+    /// The root itself, or its real part when the root is complex
+    pub value: Expression,
+    /// Imaginary part, only present for roots known to be complex
+    pub imaginary: Option<Expression>,
+    /// How many times the root is repeated
+    pub multiplicity: usize,
+}
+
+impl Root {
+    pub fn real(value: Expression, multiplicity: usize) -> Self {
+        Self { value, imaginary: None, multiplicity }
+    }
+
+    pub fn complex(value: Expression, imaginary: Expression, multiplicity: usize) -> Self {
+        Self { value, imaginary: Some(imaginary), multiplicity }
+    }
+
+    pub fn is_complex(&self) -> bool {
+        self.imaginary.is_some()
+    }
+}
+
+impl SolutionSet {
+    pub fn roots(&self) -> &[Root] {
+        match self {
+            SolutionSet::Roots(roots) => roots,
+            _ => &[],
+        }
+    }
+}
+
+impl Display for Root {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use Expression::*;
+        match &self.imaginary {
+            None => write!(f, "{}", self.value)?,
+            Some(imaginary) => {
+                let (negative, magnitude) = split_sign(imaginary);
+                let magnitude = parenthesize_if_of_type!(magnitude, Add(..) | Multiply(..) | Negate(..) | Invert(..));
+                match (self.value == num!(0), negative) {
+                    (true, false) => write!(f, "{magnitude}i")?,
+                    (true, true) => write!(f, "-{magnitude}i")?,
+                    (false, negative) => write!(
+                        f,
+                        "{} {} {magnitude}i",
+                        parenthesize_if_of_type!(self.value, Add(..)),
+                        if negative { "-" } else { "+" }
+                    )?,
+                }
+            }
+        }
+        if self.multiplicity > 1 {
+            write!(f, " (multiplicity {})", self.multiplicity)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits a leading minus sign off an expression, so that an imaginary part of -2 prints as `- 2i` rather than `+ -2i`
+fn split_sign(expression: &Expression) -> (bool, Expression) {
+    use Expression::*;
+    match expression {
+        Negate(negate) => (true, *negate.0.clone()),
+        Number(number) if number.is_negative() => (true, Number(number.abs())),
+        Multiply(multiply) if multiply.0.iter().any(|factor| matches!(factor, Number(number) if number.is_negative())) => {
+            let factors: Vec<Expression> = multiply.0.iter()
+                .map(|factor| match factor {
+                    Number(number) => Number(number.abs()),
+                    factor => factor.clone(),
+                })
+                .filter(|factor| *factor != num!(1))
+                .collect();
+            match factors.len() {
+                1 => (true, factors[0].clone()),
+                _ => (true, Multiply(crate::expression::Multiply(factors))),
+            }
+        }
+        expression => (false, expression.clone()),
+    }
+}
+
+impl Display for SolutionSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolutionSet::All => write!(f, "all values"),
+            SolutionSet::Unsolved => write!(f, "unsolved"),
+            SolutionSet::Roots(roots) if roots.is_empty() => write!(f, "no solutions"),
+            SolutionSet::Roots(roots) => {
+                let roots: Vec<String> = roots.iter().map(|root| root.to_string()).collect();
+                write!(f, "{}", roots.join(", "))
+            }
+        }
+    }
+}
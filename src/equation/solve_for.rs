@@ -0,0 +1,403 @@
+use crate::equation::Equation;
+use crate::equation::solution_set::{Root, SolutionSet};
+use crate::expression::Expression;
+use crate::expression::constant::Constant::Pi;
+use crate::utils::integer_polynomial;
+use crate::{acos, cos, neg, num, pow, sqrt};
+
+/// Numeric values smaller than this fraction of the size of the terms they are computed from
+/// are treated as zero when choosing between root formulas
+pub(super) const TOLERANCE: f64 = 1e-9;
+
+/// Whether `value` is zero up to rounding, where `scale` is the size of the terms it is computed from
+pub(super) fn negligible(value: f64, scale: f64) -> bool {
+    value.abs() <= TOLERANCE * scale
+}
+
+impl Equation {
+    /// Finds all roots of an equation that is polynomial in `variable`, in closed form up to degree four
+    pub fn solve_for(&self, variable: &str) -> SolutionSet {
+        let Some(polynomial) = (self.left.clone() - self.right.clone()).as_polynomial(variable) else {
+            return SolutionSet::Unsolved;
+        };
+        if polynomial.is_zero() {
+            return SolutionSet::All;
+        }
+        if polynomial.degree() == 0 {
+            return match numeric(&polynomial.coefficient(0)) {
+                Some(_) => SolutionSet::Roots(vec![]),
+                None => SolutionSet::Unsolved,
+            };
+        }
+        let scale = root_scale(polynomial.coefficients()).unwrap_or(0.0);
+        match polynomial_roots(polynomial.coefficients().to_vec()) {
+            Some(roots) => SolutionSet::Roots(merge_repeated(roots, scale)),
+            None => SolutionSet::Unsolved,
+        }
+    }
+}
+
+/// Bound on the size of the roots of the polynomial with the given numeric coefficients, ordered from the constant term upwards,
+/// which sets the scale that rounding errors in the root formulas are measured against. `None` for symbolic coefficients,
+/// where only exact zeros count as zero.
+fn root_scale(coefficients: &[Expression]) -> Option<f64> {
+    let (leading, rest) = coefficients.split_last()?;
+    let leading = numeric(leading)?;
+    rest.iter().enumerate().try_fold(0f64, |scale, (i, coefficient)| {
+        let degree = (rest.len() - i) as f64;
+        Some(scale.max((numeric(coefficient)? / leading).abs().powf(1.0 / degree)))
+    })
+}
+
+/// Roots of the polynomial with the given coefficients, ordered from the constant term upwards,
+/// or `None` when a cubic or quartic has symbolic coefficients that decide which root formula applies
+fn polynomial_roots(mut coefficients: Vec<Expression>) -> Option<Vec<Root>> {
+    let mut roots = Vec::new();
+    while coefficients.len() > 1 && coefficients[0] == num!(0) {
+        coefficients.remove(0);
+        roots.push(Root::real(num!(0), 1));
+    }
+    if let Some(mut integers) = coefficients.iter().map(integer).collect::<Option<Vec<i128>>>() {
+        roots.extend(integer_polynomial::extract_rational_roots(&mut integers)
+            .into_iter()
+            .map(|(p, q)| Root::real(num!(p, q), 1)));
+        coefficients = integers.into_iter().map(|coefficient| num!(coefficient)).collect();
+    }
+
+    let leading = coefficients.last()?.clone();
+    let monic: Vec<Expression> = coefficients.iter()
+        .map(|coefficient| (coefficient.clone() / leading.clone()).simplify())
+        .collect();
+    let scale = root_scale(&monic).unwrap_or(0.0);
+    roots.extend(match monic[..] {
+        [_] => vec![],
+        [ref c, _] => vec![Root::real(neg!(c.clone()).simplify(), 1)],
+        [ref c, ref b, _] => quadratic(b, c),
+        [ref d, ref c, ref b, _] => cubic(b, c, d, scale)?,
+        [ref e, ref d, ref c, ref b, _] => quartic(b, c, d, e, scale)?,
+        _ => return None,
+    });
+    Some(roots)
+}
+
+/// Roots of x^2 + b * x + c = 0
+fn quadratic(b: &Expression, c: &Expression) -> Vec<Root> {
+    let vertex = (neg!(b.clone()) / num!(2)).simplify();
+    let discriminant = (pow!(b.clone(), num!(2)) - num!(4) * c.clone()).simplify();
+    let scale = numeric(b).zip(numeric(c)).map_or(0.0, |(b, c)| b * b + 4.0 * c.abs());
+    match numeric(&discriminant) {
+        Some(value) if negligible(value, scale) => vec![Root::real(vertex, 2)],
+        Some(value) if value < 0.0 => {
+            let imaginary = (sqrt!(neg!(discriminant)) / num!(2)).simplify();
+            vec![
+                Root::complex(vertex.clone(), imaginary.clone(), 1),
+                Root::complex(vertex, neg!(imaginary).simplify(), 1),
+            ]
+        }
+        _ => {
+            let offset = sqrt!(discriminant) / num!(2);
+            vec![
+                Root::real((vertex.clone() + offset.clone()).simplify(), 1),
+                Root::real((vertex - offset).simplify(), 1),
+            ]
+        }
+    }
+}
+
+/// Roots of x^3 + a * x^2 + b * x + c = 0, through the depressed cubic t^3 + p * t + q with x = t - a / 3.
+/// `scale` bounds the size of the roots.
+fn cubic(a: &Expression, b: &Expression, c: &Expression, scale: f64) -> Option<Vec<Root>> {
+    let shift = (neg!(a.clone()) / num!(3)).simplify();
+    let p = (b.clone() - pow!(a.clone(), num!(2)) / num!(3)).simplify();
+    let q = (num!(2) * pow!(a.clone(), num!(3)) / num!(27) - a.clone() * b.clone() / num!(3) + c.clone()).simplify();
+    Some(shift_roots(depressed_cubic(&p, &q, scale)?, &shift))
+}
+
+/// Roots of t^3 + p * t + q = 0, or `None` when p or q is symbolic, since the sign of the discriminant
+/// decides which roots are real and which root formula applies
+fn depressed_cubic(p: &Expression, q: &Expression, scale: f64) -> Option<Vec<Root>> {
+    let (p_value, q_value) = (numeric(p)?, numeric(q)?);
+    if negligible(p_value, scale.powi(2)) && negligible(q_value, scale.powi(3)) {
+        return Some(vec![Root::real(num!(0), 3)]);
+    }
+    let discriminant = -(4.0 * p_value.powi(3) + 27.0 * q_value.powi(2));
+    if negligible(discriminant, 4.0 * p_value.abs().powi(3) + 27.0 * q_value.powi(2)) {
+        return Some(vec![
+            Root::real((num!(-3) * q.clone() / (num!(2) * p.clone())).simplify(), 2),
+            Root::real((num!(3) * q.clone() / p.clone()).simplify(), 1),
+        ]);
+    }
+    if discriminant > 0.0 {
+        // Three real roots, where Cardano would need complex cube roots, so use the trigonometric form
+        let amplitude = num!(2) * sqrt!(neg!(p.clone()) / num!(3));
+        let angle = acos!(num!(3) * q.clone() / (num!(2) * p.clone()) * sqrt!(num!(-3) / p.clone())) / num!(3);
+        return Some((0..3)
+            .map(|k| {
+                let root = amplitude.clone() * cos!(angle.clone() - num!(2 * k) * Expression::Constant(Pi) / num!(3));
+                Root::real(root.simplify(), 1)
+            })
+            .collect());
+    }
+    // Cardano's formula: the real root u + v and the complex pair -(u + v) / 2 ± i * sqrt(3) / 2 * (u - v)
+    let root = sqrt!(pow!(q.clone(), num!(2)) / num!(4) + pow!(p.clone(), num!(3)) / num!(27));
+    let u = real_cube_root(neg!(q.clone()) / num!(2) + root.clone());
+    let v = real_cube_root(neg!(q.clone()) / num!(2) - root);
+    let real_part = (neg!(u.clone() + v.clone()) / num!(2)).simplify();
+    let imaginary = (sqrt!(num!(3)) / num!(2) * (u.clone() - v.clone())).simplify();
+    Some(vec![
+        Root::real((u + v).simplify(), 1),
+        Root::complex(real_part.clone(), imaginary.clone(), 1),
+        Root::complex(real_part, neg!(imaginary).simplify(), 1),
+    ])
+}
+
+/// Cube root that stays real for negative numbers, where a power of 1/3 would not
+fn real_cube_root(radicand: Expression) -> Expression {
+    let radicand = radicand.simplify();
+    match numeric(&radicand) {
+        Some(value) if value < 0.0 => neg!(pow!(neg!(radicand), num!(1, 3))),
+        _ => pow!(radicand, num!(1, 3)),
+    }
+}
+
+/// Roots of x^4 + a * x^3 + b * x^2 + c * x + d = 0,
+/// through the depressed quartic y^4 + p * y^2 + q * y + r with x = y - a / 4. `scale` bounds the size of the roots.
+fn quartic(a: &Expression, b: &Expression, c: &Expression, d: &Expression, scale: f64) -> Option<Vec<Root>> {
+    let shift = (neg!(a.clone()) / num!(4)).simplify();
+    let p = (b.clone() - num!(3) * pow!(a.clone(), num!(2)) / num!(8)).simplify();
+    let q = (pow!(a.clone(), num!(3)) / num!(8) - a.clone() * b.clone() / num!(2) + c.clone()).simplify();
+    let r = (
+        num!(-3) * pow!(a.clone(), num!(4)) / num!(256)
+            + pow!(a.clone(), num!(2)) * b.clone() / num!(16)
+            - a.clone() * c.clone() / num!(4)
+            + d.clone()
+    ).simplify();
+    Some(shift_roots(depressed_quartic(&p, &q, &r, scale)?, &shift))
+}
+
+/// Roots of y^4 + p * y^2 + q * y + r = 0 using Ferrari's method
+fn depressed_quartic(p: &Expression, q: &Expression, r: &Expression, scale: f64) -> Option<Vec<Root>> {
+    if negligible(numeric(q)?, scale.powi(3)) {
+        return Some(biquadratic(p, r, scale));
+    }
+
+    // Completing the square turns the quartic into two quadratics once m solves the resolvent cubic
+    // m^3 + p * m^2 + (p^2 / 4 - r) * m - q^2 / 8 = 0, which has a positive root whenever q != 0
+    let resolvent = polynomial_roots(vec![
+        (neg!(pow!(q.clone(), num!(2))) / num!(8)).simplify(),
+        (pow!(p.clone(), num!(2)) / num!(4) - r.clone()).simplify(),
+        p.clone(),
+        num!(1),
+    ])?;
+    let m = resolvent.into_iter()
+        .filter(|root| !root.is_complex())
+        .map(|root| root.value)
+        .max_by(|a, b| numeric(a).partial_cmp(&numeric(b)).unwrap_or(std::cmp::Ordering::Equal))?;
+
+    let s = sqrt!(num!(2) * m.clone());
+    let base = p.clone() / num!(2) + m;
+    let correction = q.clone() / (num!(2) * s.clone());
+    let mut roots = quadratic(&neg!(s.clone()).simplify(), &(base.clone() + correction.clone()).simplify());
+    roots.extend(quadratic(&s.simplify(), &(base - correction).simplify()));
+    Some(roots)
+}
+
+/// Roots of y^4 + p * y^2 + r = 0, a quadratic in y^2, where `scale` bounds the size of y
+fn biquadratic(p: &Expression, r: &Expression, scale: f64) -> Vec<Root> {
+    let squares = quadratic(p, r);
+    if squares.iter().any(Root::is_complex) {
+        // y^4 + p * y^2 + r = (y^2 + s * y + t) * (y^2 - s * y + t) with t = sqrt(r) and s = sqrt(2 * t - p)
+        let t = sqrt!(r.clone()).simplify();
+        let s = sqrt!(num!(2) * t.clone() - p.clone()).simplify();
+        let mut roots = quadratic(&s, &t);
+        roots.extend(quadratic(&neg!(s).simplify(), &t));
+        return roots;
+    }
+    squares.into_iter()
+        .flat_map(|square| {
+            let multiplicity = square.multiplicity;
+            match numeric(&square.value) {
+                Some(value) if negligible(value, scale.powi(2)) => vec![Root::real(num!(0), 2 * multiplicity)],
+                Some(value) if value < 0.0 => {
+                    let imaginary = sqrt!(neg!(square.value)).simplify();
+                    vec![
+                        Root::complex(num!(0), imaginary.clone(), multiplicity),
+                        Root::complex(num!(0), neg!(imaginary).simplify(), multiplicity),
+                    ]
+                }
+                _ => {
+                    let root = sqrt!(square.value).simplify();
+                    vec![
+                        Root::real(root.clone(), multiplicity),
+                        Root::real(neg!(root).simplify(), multiplicity),
+                    ]
+                }
+            }
+        })
+        .collect()
+}
+
+fn shift_roots(roots: Vec<Root>, shift: &Expression) -> Vec<Root> {
+    roots.into_iter()
+        .map(|root| Root { value: (root.value + shift.clone()).simplify(), ..root })
+        .collect()
+}
+
+/// Combines roots that are equal, either structurally or numerically up to rounding relative to `scale`,
+/// adding up their multiplicities
+fn merge_repeated(roots: Vec<Root>, scale: f64) -> Vec<Root> {
+    let mut merged: Vec<Root> = Vec::new();
+    for root in roots {
+        match merged.iter_mut().find(|other| same_root(other, &root, scale)) {
+            Some(other) => other.multiplicity += root.multiplicity,
+            None => merged.push(root),
+        }
+    }
+    merged
+}
+
+fn same_root(a: &Root, b: &Root, scale: f64) -> bool {
+    if a.value == b.value && a.imaginary == b.imaginary {
+        return true;
+    }
+    let parts = |root: &Root| Some((
+        numeric(&root.value)?,
+        match &root.imaginary {
+            Some(imaginary) => numeric(imaginary)?,
+            None => 0.0,
+        },
+    ));
+    match (parts(a), parts(b)) {
+        (Some((a_real, a_imaginary)), Some((b_real, b_imaginary))) => {
+            negligible(a_real - b_real, scale) && negligible(a_imaginary - b_imaginary, scale)
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn numeric(expression: &Expression) -> Option<f64> {
+    expression.solve(None).ok().filter(|value| value.is_finite())
+}
+
+fn integer(expression: &Expression) -> Option<i128> {
+    numeric(expression)
+        .filter(|value| value.fract() == 0.0 && value.abs() < 1e15)
+        .map(|value| value as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use crate::var;
+    use super::*;
+
+    fn solve(input: &str) -> SolutionSet {
+        Equation::from_str(input).unwrap().solve_for("x")
+    }
+
+    /// Checks that every root makes the polynomial vanish, using complex arithmetic,
+    /// and that the multiplicities add up to its degree
+    fn assert_roots(input: &str, degree: usize, complex: usize) {
+        let equation = Equation::from_str(input).unwrap();
+        let polynomial = (equation.left - equation.right).as_polynomial("x").unwrap();
+        let solutions = solve(input);
+        let roots = solutions.roots();
+        assert_eq!(roots.iter().map(|root| root.multiplicity).sum::<usize>(), degree, "{input}: {solutions}");
+        assert_eq!(roots.iter().filter(|root| root.is_complex()).count(), complex, "{input}: {solutions}");
+        for root in roots {
+            let real = numeric(&root.value).unwrap();
+            let imaginary = root.imaginary.as_ref().map_or(0.0, |imaginary| numeric(imaginary).unwrap());
+            let (mut value_real, mut value_imaginary) = (0.0, 0.0);
+            for coefficient in polynomial.coefficients().iter().rev() {
+                (value_real, value_imaginary) = (
+                    value_real * real - value_imaginary * imaginary + numeric(coefficient).unwrap(),
+                    value_real * imaginary + value_imaginary * real,
+                );
+            }
+            assert!(value_real.hypot(value_imaginary) < 1e-6, "{input}: {root} is not a root");
+        }
+    }
+
+    #[test]
+    fn test_linear_and_quadratic() {
+        assert_eq!(solve("2 * x + 3 = 0"), SolutionSet::Roots(vec![Root::real(num!(-1.5), 1)]));
+        assert_roots("x ^ 2 = 2", 2, 0);
+        assert_eq!(
+            solve("x ^ 2 + 1 = 0"),
+            SolutionSet::Roots(vec![Root::complex(num!(0), num!(1), 1), Root::complex(num!(0), num!(-1), 1)])
+        );
+        assert_roots("x ^ 2 + x + 1 = 0", 2, 2);
+        assert_eq!(solve("x ^ 2 + 4 = 0").to_string(), "2i, -2i");
+        assert_eq!(solve("x ^ 2 - 2 * x + 5 = 0").to_string(), "1 + 2i, 1 - 2i");
+    }
+
+    #[test]
+    fn test_symbolic_quadratic() {
+        let SolutionSet::Roots(roots) = solve("a * x ^ 2 + b * x + c = 0") else { panic!() };
+        assert_eq!(roots.len(), 2);
+        let variables = HashMap::from([("a", 2.0), ("b", -7.0), ("c", 3.0)].map(|(k, v)| (k.to_string(), v)));
+        let mut values: Vec<f64> = roots.iter().map(|root| root.value.solve(Some(&variables)).unwrap()).collect();
+        values.sort_by(f64::total_cmp);
+        assert_eq!(values, vec![0.5, 3.0]);
+    }
+
+    #[test]
+    fn test_cubic() {
+        assert_eq!(
+            solve("x ^ 3 - 6 * x ^ 2 + 11 * x - 6 = 0"),
+            SolutionSet::Roots(vec![Root::real(num!(1), 1), Root::real(num!(2), 1), Root::real(num!(3), 1)])
+        );
+        assert_roots("x ^ 3 - 3 * x + 1 = 0", 3, 0);
+        assert_roots("x ^ 3 + x + 1 = 0", 3, 2);
+        assert_roots("2 * x ^ 3 - 4 = 0", 3, 2);
+        assert_eq!(solve("x ^ 3 = 0"), SolutionSet::Roots(vec![Root::real(num!(0), 3)]));
+        // Cube roots of numbers that are not perfect cubes stay exact
+        assert_eq!(solve("x ^ 3 - 2 = 0").roots()[0], Root::real(pow!(num!(2), num!(1, 3)), 1));
+        assert_eq!(
+            solve("x ^ 3 - 2 = 0").to_string(),
+            "2 ^ (1/3), -1/2 * 2 ^ (1/3) + (1/2 * 2 ^ (1/3) * sqrt(3))i, -1/2 * 2 ^ (1/3) - (1/2 * 2 ^ (1/3) * sqrt(3))i"
+        );
+    }
+
+    #[test]
+    fn test_quartic() {
+        assert_roots("x ^ 4 - 5 * x ^ 2 + 4 = 0", 4, 0);
+        assert_roots("x ^ 4 + 1 = 0", 4, 4);
+        assert_roots("x ^ 4 - 2 = 0", 4, 2);
+        assert_roots("x ^ 4 + x + 1 = 0", 4, 4);
+        assert_roots("x ^ 4 - 4 * x ^ 3 + x ^ 2 + 3 * x - 0.5 = 0", 4, 0);
+        assert_eq!(
+            Equation { left: pow!(var!("x") - num!(1), num!(4)), right: num!(0) }.solve_for("x"),
+            SolutionSet::Roots(vec![Root::real(num!(1), 4)])
+        );
+    }
+
+    #[test]
+    fn test_scale() {
+        let SolutionSet::Roots(roots) = solve("x ^ 2 - 1e-10 = 0") else { panic!() };
+        assert_eq!(roots.iter().filter_map(|root| numeric(&root.value)).map(|value| (value * 1e5).round()).collect::<Vec<_>>(), vec![1.0, -1.0]);
+        let SolutionSet::Roots(roots) = solve("x ^ 3 - 7e-8 * x + 6e-12 = 0") else { panic!() };
+        let mut values: Vec<f64> = roots.iter().filter_map(|root| numeric(&root.value)).map(|value| (value * 1e4).round()).collect();
+        values.sort_by(f64::total_cmp);
+        assert_eq!(values, vec![-3.0, 1.0, 2.0]);
+        assert_roots("x ^ 3 - 3e6 * x ^ 2 + 3e12 * x - 1.000001e18 = 0", 3, 2);
+    }
+
+    #[test]
+    fn test_symbolic_cubic() {
+        // Which roots are real depends on the sign of a
+        assert_eq!(solve("x ^ 3 + a * x + 1 = 0"), SolutionSet::Unsolved);
+        assert_eq!(solve("x ^ 4 + a * x + 1 = 0"), SolutionSet::Unsolved);
+        assert_eq!(solve("x ^ 4 - a * x ^ 2 = 0").roots().len(), 3);
+    }
+
+    #[test]
+    fn test_special_cases() {
+        assert_eq!(solve("x = x"), SolutionSet::All);
+        assert_eq!(solve("x + 1 = x"), SolutionSet::Roots(vec![]));
+        assert_eq!(solve("sin(x) = 0"), SolutionSet::Unsolved);
+        assert_eq!(solve("x ^ 5 + x + 1 = 0"), SolutionSet::Unsolved);
+        assert_eq!(solve("x ^ 1000000000 = 1"), SolutionSet::Unsolved);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::equation::Equation;
+use crate::equation::inequality::Inequality;
+use crate::equation::interval::{Endpoint, Interval, IntervalSet};
+use crate::equation::solution_set::SolutionSet;
+use crate::equation::solve_for::{negligible, numeric};
+use crate::expression::Expression;
+use crate::expression::polynomial::Polynomial;
+use crate::num;
+
+/// A point where the sign of a rational function can change
+struct CriticalPoint {
+    value: Expression,
+    numeric: f64,
+    /// Whether the denominator vanishes there, so that the function is undefined
+    pole: bool,
+}
+
+impl Inequality {
+    /// Finds the values of `variable` that satisfy a polynomial or rational inequality with numeric coefficients.
+    /// Returns `None` for other inequalities and when the numerator or denominator has no closed form roots.
+    pub fn solve_for(&self, variable: &str) -> Option<IntervalSet> {
+        let difference = (self.left.clone() - self.right.clone()).simplify();
+        let (numerator, denominator) = difference.as_rational_function(variable)?;
+        if denominator.is_zero() {
+            return None;
+        }
+        let numerator_values = numeric_coefficients(&numerator)?;
+        let denominator_values = numeric_coefficients(&denominator)?;
+        let satisfied = |x: f64| {
+            self.comparison.holds(horner(&numerator_values, x) / horner(&denominator_values, x), 0.0)
+        };
+
+        let mut points: Vec<CriticalPoint> = real_roots(&numerator, variable, false)?;
+        points.extend(real_roots(&denominator, variable, true)?);
+        points.sort_by(|a, b| a.numeric.total_cmp(&b.numeric));
+        let mut merged: Vec<CriticalPoint> = Vec::new();
+        for point in points {
+            match merged.last_mut() {
+                Some(last) if negligible(last.numeric - point.numeric, last.numeric.abs().max(point.numeric.abs())) => {
+                    last.pole |= point.pole
+                }
+                _ => merged.push(point),
+            }
+        }
+
+        // The real line is split into the critical points and the open segments around them, in ascending order
+        let mut pieces = Vec::new();
+        let mut lower: Option<&CriticalPoint> = None;
+        for point in merged.iter().map(Some).chain([None]) {
+            let sample = match (lower, point) {
+                (None, None) => 0.0,
+                (None, Some(upper)) => upper.numeric - 1.0,
+                (Some(lower), None) => lower.numeric + 1.0,
+                (Some(lower), Some(upper)) => (lower.numeric + upper.numeric) / 2.0,
+            };
+            let start = lower.map_or(Endpoint::Infinite, |lower| Endpoint::Open(lower.value.clone()));
+            let end = point.map_or(Endpoint::Infinite, |upper| Endpoint::Open(upper.value.clone()));
+            pieces.push((satisfied(sample), start, end));
+            if let Some(point) = point {
+                let included = !point.pole && self.comparison.holds(0.0, 0.0);
+                pieces.push((included, Endpoint::Closed(point.value.clone()), Endpoint::Closed(point.value.clone())));
+            }
+            lower = point;
+        }
+
+        let mut intervals = Vec::new();
+        let mut current: Option<(Endpoint, Endpoint)> = None;
+        for (included, start, end) in pieces {
+            match (&mut current, included) {
+                (Some((_, current_end)), true) => *current_end = end,
+                (None, true) => current = Some((start, end)),
+                (_, false) => intervals.extend(current.take().map(|(start, end)| Interval::new(start, end))),
+            }
+        }
+        intervals.extend(current.map(|(start, end)| Interval::new(start, end)));
+        Some(IntervalSet(intervals))
+    }
+}
+
+fn numeric_coefficients(polynomial: &Polynomial) -> Option<Vec<f64>> {
+    polynomial.coefficients().iter().map(numeric).collect()
+}
+
+/// Value of the polynomial with the given coefficients at x, ordered from the constant term upwards
+fn horner(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |value, coefficient| value * x + coefficient)
+}
+
+/// Distinct real roots of a non-zero polynomial, while the zero polynomial has none that change its sign
+fn real_roots(polynomial: &Polynomial, variable: &str, pole: bool) -> Option<Vec<CriticalPoint>> {
+    if polynomial.is_zero() {
+        return Some(vec![]);
+    }
+    let SolutionSet::Roots(roots) = Equation { left: polynomial.to_expression(), right: num!(0) }.solve_for(variable) else {
+        return None;
+    };
+    roots.into_iter()
+        .filter(|root| !root.is_complex())
+        .map(|root| Some(CriticalPoint { numeric: numeric(&root.value)?, value: root.value, pole }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+
+    fn solve(input: &str) -> Option<String> {
+        Inequality::from_str(input).unwrap().solve_for("x").map(|set| set.to_string())
+    }
+
+    #[test]
+    fn test_polynomial() {
+        assert_eq!(solve("x^2 - 4 > 0").as_deref(), Some("(-∞, -2) ∪ (2, ∞)"));
+        assert_eq!(solve("x^2 <= 4").as_deref(), Some("[-2, 2]"));
+        assert_eq!(solve("2x + 1 >= x - 3").as_deref(), Some("[-4, ∞)"));
+        assert_eq!(solve("x^2 + 1 < 0").as_deref(), Some("∅"));
+        assert_eq!(solve("x^2 >= 0").as_deref(), Some("(-∞, ∞)"));
+        assert_eq!(solve("x^2 <= 0").as_deref(), Some("{0}"));
+        assert_eq!(solve("(x - 1)^2 > 0").as_deref(), Some("(-∞, 1) ∪ (1, ∞)"));
+        assert_eq!(solve("x != 3").as_deref(), Some("(-∞, 3) ∪ (3, ∞)"));
+        assert_eq!(solve("x^3 - x < 0").as_deref(), Some("(-∞, -1) ∪ (0, 1)"));
+        assert_eq!(solve("x^2 < 2").as_deref(), Some("(-sqrt(2), sqrt(2))"));
+    }
+
+    #[test]
+    fn test_rational() {
+        assert_eq!(solve("(x - 1) / (x + 2) <= 0").as_deref(), Some("(-2, 1]"));
+        assert_eq!(solve("1 / x > 0").as_deref(), Some("(0, ∞)"));
+        assert_eq!(solve("x / (x^2 - 1) >= 0").as_deref(), Some("(-1, 0] ∪ (1, ∞)"));
+        assert_eq!(solve("1/x + 1/(x - 1) < 0").as_deref(), Some("(-∞, 0) ∪ (1/2, 1)"));
+    }
+
+    #[test]
+    fn test_unsolved() {
+        assert_eq!(solve("sin(x) > 0"), None);
+        assert_eq!(solve("a x > 1"), None);
+        assert_eq!(solve("x^5 - x + 1 > 0"), None);
+        assert_eq!(solve("x^1000000000 > 1"), None);
+
+        let set = Inequality::from_str("x^2 - 4 > 0").unwrap().solve_for("x").unwrap();
+        assert!(set.contains(-3.0) && !set.contains(0.0) && !set.contains(2.0));
+    }
+}
@@ -0,0 +1,12 @@
+use crate::equation::Equation;
+use crate::expression::Expression;
+
+impl Equation {
+    /// Replaces every occurrence of `variable` on both sides with `with`
+    pub fn substitute(&self, variable: &str, with: &Expression) -> Equation {
+        Equation {
+            left: self.left.substitute(variable, with),
+            right: self.right.substitute(variable, with),
+        }
+    }
+}
@@ -1,36 +1,61 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 
 use itertools::Itertools;
 use rustc_hash::FxHashMap;
 
-use crate::{mul, num};
-use crate::expression::{add, Expression, multiply, negate, number, Operand};
-use crate::expression::display::parenthesize_if_of_type;
+use crate::{cos, mul, neg, num, pow, sinh};
+use crate::expression::{add, Expression, multiply, negate, number, ordering, Operand};
+use crate::expression::display::{write_with_precedence, Precedence};
+use crate::expression::context::Context;
 use crate::expression::error::ExpressionError;
 use crate::utils::insert_or_add::InsertOrAdd;
 
-#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+#[derive(Debug, Clone)]
 pub struct Add(pub Vec<Expression>);
 
+/// Operands are compared in canonical order, so that the order they were written in does not matter
+impl PartialEq for Add {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Add {}
+
+impl Ord for Add {
+    fn cmp(&self, other: &Self) -> Ordering {
+        ordering::sorted(&self.0).cmp(&ordering::sorted(&other.0))
+    }
+}
+
+impl PartialOrd for Add {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Add {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ordering::sorted(&self.0).hash(state)
+    }
+}
+
 impl Operand for Add {
-    fn solve(&self, variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError> {
+    fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError> {
         let results: Result<Vec<f64>, _> = self.0.iter()
-            .map(|child| child.solve(variables))
+            .map(|child| child.evaluate(context))
             .collect();
         Ok(results?.iter().sum())
     }
 
-    fn operand_count(&self) -> usize {
-        2
-    }
-
     fn children(&self) -> Vec<&Expression> {
         self.0.iter().collect()
     }
 
     fn simplify(&self) -> Expression {
-        let mut number_sum: f64 = 0.0;
+        let mut number_sum = number::Number::integer(0);
         let mut new_children: Vec<Expression> = Vec::new();
         use Expression::*;
         let children: Vec<Expression> = self.0.iter()
@@ -41,35 +66,48 @@ impl Operand for Add {
                 }})
             .collect();
 
-        let mut additions: FxHashMap<Expression, f64> = FxHashMap::default();
+        let mut additions: FxHashMap<Expression, number::Number> = FxHashMap::default();
 
         for child in children {
             match &child {
-                Number(number::Number(num)) => number_sum += num,
-                Negate(negate::Negate(negate)) => additions.insert_or_add(*negate.clone(), -1.0),
+                Number(num) => number_sum += num.clone(),
+                Negate(negate::Negate(negate)) => additions.insert_or_add(*negate.clone(), number::Number::from(-1)),
                 Multiply(multiply::Multiply(children)) => {
                     let numeric_multiple = children.iter().find(|child| matches!(child, Number(..)));
-                    if let Some(Number(number::Number(num))) = numeric_multiple {
+                    if let Some(Number(num)) = numeric_multiple {
                         let remainder = children.iter().filter(|child| !matches!(child, Number(..))).cloned().collect_vec();
-                        additions.insert_or_add(Multiply(multiply::Multiply(remainder)).simplify(), *num);
+                        additions.insert_or_add(Multiply(multiply::Multiply(remainder)).simplify(), num.clone());
                     } else {
-                        additions.insert_or_add(child, 1.0);
+                        additions.insert_or_add(child, number::Number::from(1));
                     }
                 }
-                _ => additions.insert_or_add(child, 1.0)
+                _ => additions.insert_or_add(child, number::Number::from(1))
             }
         }
+        pythagorean_identities(&mut additions, &mut number_sum);
         for (addition, count) in additions.into_iter(){
-            if count == 1.{
+            if count == 1.0 {
                 new_children.push(addition);
-            } else if count != 0.{
-                new_children.push(mul!(num!(count), addition))
+            } else if count == -1.0 {
+                new_children.push(neg!(addition));
+            } else if count != 0.0 {
+                match addition {
+                    Multiply(multiply::Multiply(children)) => new_children.push(
+                        Multiply(multiply::Multiply(std::iter::once(num!(count)).chain(children).collect()))
+                    ),
+                    addition => new_children.push(mul!(num!(count), addition)),
+                }
             }
         }
 
         if number_sum != 0.0 {
             new_children.push(num!(number_sum));
         }
+        new_children.sort_by(ordering::compare_terms);
+
+        if new_children.is_empty() {
+            return num!(0);
+        }
 
         // If there is only one value, there is no addition
         if new_children.len() == 1{
@@ -80,28 +118,52 @@ impl Operand for Add {
     }
 }
 
+/// Replaces sin(a)^2 + cos(a)^2 and cosh(a)^2 - sinh(a)^2 with 1, as far as the coefficients of the squares allow
+fn pythagorean_identities(additions: &mut FxHashMap<Expression, number::Number>, number_sum: &mut number::Number) {
+    use Expression::*;
+    let square = |expression: Expression| pow!(expression, num!(2));
+    let pairs: Vec<(Expression, Expression, bool)> = additions.keys()
+        .filter_map(|term| match term {
+            Power(base, exponent) if **exponent == num!(2) => match &**base {
+                Sin(a) => Some((term.clone(), square(cos!(*a.clone())), false)),
+                Cosh(a) => Some((term.clone(), square(sinh!(*a.clone())), true)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    for (first, second, opposite) in pairs {
+        let (Some(a), Some(b)) = (additions.get(&first).cloned(), additions.get(&second).cloned()) else {
+            continue;
+        };
+        // The identity applies to terms k * first + k * second, or k * first - k * second for the hyperbolic one
+        let b = if opposite { -b } else { b };
+        if a.is_negative() != b.is_negative() {
+            continue;
+        }
+        let k = if a.abs().value() <= b.abs().value() { a } else { b };
+        *additions.get_mut(&first).unwrap() -= k.clone();
+        *additions.get_mut(&second).unwrap() -= if opposite { -k.clone() } else { k.clone() };
+        *number_sum += k;
+    }
+}
 
 impl Display for Add {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        use Expression::*;
-        if self.0.is_empty() {
+        let Some((first, rest)) = self.0.split_first() else {
             return write!(f, "0");
-        }
-        write!(f, "{}", self.0[0])?;
-
-        if self.0.len() == 1 {
-            return Ok(());
-        }
-
-        for child in &self.0[1..] {
-            if let Negate(negate) = child {
-                write!(f, " - {}", parenthesize_if_of_type!(*negate.0, Add(..)))?;
-            } else if let Number(number) = *child{
-                if number < 0.0{
-                    write!(f, " - {}", -number)?;
-                    continue;
+        };
+        write_with_precedence(f, first, Precedence::Sum)?;
+        for child in rest {
+            match child.negated() {
+                Some(positive) => {
+                    write!(f, " - ")?;
+                    write_with_precedence(f, &positive, Precedence::Product)?;
+                }
+                None => {
+                    write!(f, " + ")?;
+                    write_with_precedence(f, child, Precedence::Product)?;
                 }
-                write!(f, " + {}", child)?;
             }
         }
         Ok(())
@@ -110,9 +172,8 @@ impl Display for Add {
 
 #[cfg(test)]
 pub mod test {
-    use crate::{add, neg, num, var};
-
-    use super::*;
+    use crate::{add, cos, cosh, mul, neg, num, pow, sin, sinh, var};
+    use crate::expression::Expression;
 
     #[test]
     fn test_display() {
@@ -128,6 +189,10 @@ pub mod test {
 
     #[test]
     fn test_simplification(){
+        let third = num!(1) / num!(3);
+        assert_eq!((third.clone() + third.clone() + third).simplify(), num!(1));
+        assert_eq!((num!(0.1) + num!(0.2)).simplify(), num!(3, 10));
+
         let expression = add!(add!(num!(1), var!("a")), var!("b"), num!(-2));
         assert_eq!(expression.simplify(), add!(var!("a"), var!("b"), num!(-1)));
 
@@ -137,4 +202,20 @@ pub mod test {
         let expression = num!(1) * var!("a") + var!("b") + num!(-2) + num!(3) + num!(5) * var!("a");
         assert_eq!(expression.simplify(), num!(6) * var!("a") + var!("b") + num!(1));
     }
+
+    #[test]
+    fn test_pythagorean_identities() {
+        let square = |expression| pow!(expression, num!(2));
+        let x = || var!("x");
+        assert_eq!((square(cosh!(x())) - square(sinh!(x()))).simplify(), num!(1));
+        assert_eq!((square(sin!(x())) + square(cos!(x()))).simplify(), num!(1));
+        assert_eq!((num!(2) * square(sin!(x())) + num!(2) * square(cos!(x())) + var!("y")).simplify(), (var!("y") + num!(2)).simplify());
+        assert_eq!(
+            (num!(3) * square(cosh!(x())) - square(sinh!(x()))).simplify(),
+            (mul!(num!(2), square(cosh!(x()))) + num!(1)).simplify()
+        );
+        // Different arguments or matching signs are left alone
+        assert!(matches!((square(cosh!(x())) - square(sinh!(var!("y")))).simplify(), Expression::Add(_)));
+        assert!(matches!((square(cosh!(x())) + square(sinh!(x()))).simplify(), Expression::Add(_)));
+    }
 }
\ No newline at end of file
@@ -1,19 +1,136 @@
 use core::fmt;
+use std::f64::consts::{PI, SQRT_2, TAU};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
+use crate::expression::number::Number;
+use crate::units::dimension::Dimension;
+use crate::units::unit::Unit;
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
 pub enum Constant {
     Pi,
     E,
+    /// 2π, the circumference of the unit circle
+    Tau,
+    /// The golden ratio (1 + √5) / 2
+    GoldenRatio,
+    Sqrt2,
+    /// The Euler–Mascheroni constant γ
+    EulerGamma,
+    /// Speed of light in vacuum c, in m/s
+    SpeedOfLight,
+    /// Newtonian constant of gravitation G, in m^3 kg^-1 s^-2
+    Gravitation,
+    /// Planck constant h, in J s
+    Planck,
+    /// Reduced Planck constant ħ = h / 2π, in J s
+    ReducedPlanck,
+    /// Boltzmann constant k_B, in J/K
+    Boltzmann,
+    /// Avogadro constant N_A, in mol^-1
+    Avogadro,
+    /// Elementary charge, in C
+    ElementaryCharge,
+    /// Vacuum electric permittivity ε_0, in F/m
+    VacuumPermittivity,
+    /// Vacuum magnetic permeability μ_0, in N/A^2
+    VacuumPermeability,
+    /// A constant defined by the user in a `Context`
+    Named(Arc<NamedConstant>),
+}
+
+/// A user-defined constant, with its value in the coherent SI unit of its dimension
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
+pub struct NamedConstant {
+    pub name: String,
+    pub value: Number,
+    pub dimension: Dimension,
 }
 
 impl Constant {
+    /// A user-defined constant with its value in the coherent SI unit of `dimension`
+    pub fn named(name: &str, value: Number, dimension: Dimension) -> Constant {
+        Constant::Named(Arc::new(NamedConstant { name: name.to_string(), value, dimension }))
+    }
+
+    /// The constant a parser reads for an identifier. The names that constants are printed with are always recognised,
+    /// the short symbols `c`, `G` and `h` of physical constants only when `physical` is set, since they are common variable names.
+    pub fn from_name(name: &str, physical: bool) -> Option<Constant> {
+        match name {
+            "pi" | "π" => Some(Constant::Pi),
+            "e" => Some(Constant::E),
+            "tau" | "τ" => Some(Constant::Tau),
+            "phi" | "φ" => Some(Constant::GoldenRatio),
+            "γ" => Some(Constant::EulerGamma),
+            "c_0" => Some(Constant::SpeedOfLight),
+            "G_N" => Some(Constant::Gravitation),
+            "h_P" => Some(Constant::Planck),
+            "ħ" | "hbar" => Some(Constant::ReducedPlanck),
+            "k_B" => Some(Constant::Boltzmann),
+            "N_A" => Some(Constant::Avogadro),
+            "q_e" => Some(Constant::ElementaryCharge),
+            "ε_0" | "epsilon_0" => Some(Constant::VacuumPermittivity),
+            "μ_0" | "mu_0" => Some(Constant::VacuumPermeability),
+            "c" if physical => Some(Constant::SpeedOfLight),
+            "G" if physical => Some(Constant::Gravitation),
+            "h" if physical => Some(Constant::Planck),
+            _ => None,
+        }
+    }
+
+    /// The value as a number: exact for the constants that the SI defines exactly,
+    /// a float for the measured ones and the irrational mathematical constants
+    pub fn number(&self) -> Number {
+        let exact = |value: &str| Number::from_str(value).unwrap_or(Number::Float(f64::NAN));
+        match self {
+            Constant::SpeedOfLight => exact("299792458"),
+            Constant::Gravitation => Number::float(6.67430e-11),
+            Constant::Planck => exact("6.62607015e-34"),
+            Constant::Boltzmann => exact("1.380649e-23"),
+            Constant::Avogadro => exact("6.02214076e23"),
+            Constant::ElementaryCharge => exact("1.602176634e-19"),
+            Constant::VacuumPermittivity => Number::float(8.8541878188e-12),
+            Constant::VacuumPermeability => Number::float(1.25663706127e-6),
+            Constant::Named(constant) => constant.value.clone(),
+            constant => Number::float(constant.solve()),
+        }
+    }
+
     pub fn solve(&self) -> f64 {
         match self {
-            Constant::Pi => std::f64::consts::PI,
+            Constant::Pi => PI,
             Constant::E => std::f64::consts::E,
+            Constant::Tau => TAU,
+            Constant::GoldenRatio => (1.0 + 5f64.sqrt()) / 2.0,
+            Constant::Sqrt2 => SQRT_2,
+            Constant::EulerGamma => 0.577_215_664_901_532_9,
+            Constant::ReducedPlanck => Constant::Planck.solve() / TAU,
+            constant => constant.number().value(),
+        }
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        //                                                          m  kg  s  A  K mol cd
+        match self {
+            Constant::SpeedOfLight => Dimension::from_integers([1, 0, -1, 0, 0, 0, 0]),
+            Constant::Gravitation => Dimension::from_integers([3, -1, -2, 0, 0, 0, 0]),
+            Constant::Planck | Constant::ReducedPlanck => Dimension::from_integers([2, 1, -1, 0, 0, 0, 0]),
+            Constant::Boltzmann => Dimension::from_integers([2, 1, -2, 0, -1, 0, 0]),
+            Constant::Avogadro => Dimension::from_integers([0, 0, 0, 0, 0, -1, 0]),
+            Constant::ElementaryCharge => Dimension::from_integers([0, 0, 1, 1, 0, 0, 0]),
+            Constant::VacuumPermittivity => Dimension::from_integers([-3, -1, 4, 2, 0, 0, 0]),
+            Constant::VacuumPermeability => Dimension::from_integers([1, 1, -2, -2, 0, 0, 0]),
+            Constant::Named(constant) => constant.dimension,
+            _ => Dimension::dimensionless(),
         }
     }
+
+    /// The coherent SI unit that the value is given in
+    pub fn unit(&self) -> Unit {
+        Unit::coherent(self.dimension())
+    }
 }
 
 impl Display for Constant {
@@ -21,6 +138,81 @@ impl Display for Constant {
         match self {
             Constant::Pi => write!(f, "π"),
             Constant::E => write!(f, "e"),
+            Constant::Tau => write!(f, "τ"),
+            Constant::GoldenRatio => write!(f, "φ"),
+            Constant::Sqrt2 => write!(f, "sqrt(2)"),
+            Constant::EulerGamma => write!(f, "γ"),
+            Constant::SpeedOfLight => write!(f, "c_0"),
+            Constant::Gravitation => write!(f, "G_N"),
+            Constant::Planck => write!(f, "h_P"),
+            Constant::ReducedPlanck => write!(f, "ħ"),
+            Constant::Boltzmann => write!(f, "k_B"),
+            Constant::Avogadro => write!(f, "N_A"),
+            Constant::ElementaryCharge => write!(f, "q_e"),
+            Constant::VacuumPermittivity => write!(f, "ε_0"),
+            Constant::VacuumPermeability => write!(f, "μ_0"),
+            Constant::Named(constant) => write!(f, "{}", constant.name),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values() {
+        assert_eq!(Constant::Tau.solve(), 2.0 * PI);
+        assert!((Constant::GoldenRatio.solve() - 1.618_033_988_749_895).abs() < 1e-15);
+        assert_eq!(Constant::SpeedOfLight.number(), Number::integer(299_792_458));
+        assert!(Constant::Planck.number().is_exact());
+        assert!(!Constant::Gravitation.number().is_exact());
+        assert!(!Constant::VacuumPermeability.number().is_exact());
+        assert!((Constant::ReducedPlanck.solve() - 1.054_571_817e-34).abs() < 1e-42);
+        // c^2 = 1 / (ε_0 μ_0) within the uncertainty of the measured constants
+        let product = Constant::VacuumPermittivity.solve() * Constant::VacuumPermeability.solve() * Constant::SpeedOfLight.solve().powi(2);
+        assert!((product - 1.0).abs() < 1e-9);
+        assert_eq!(Constant::Boltzmann.unit().to_string(), "m^2 kg s^-2 K^-1");
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Constant::from_name("τ", false), Some(Constant::Tau));
+        assert_eq!(Constant::from_name("c", false), None);
+        assert_eq!(Constant::from_name("c", true), Some(Constant::SpeedOfLight));
+        assert_eq!(Constant::from_name("hbar", false), Some(Constant::ReducedPlanck));
+        assert_eq!(Constant::from_name("v_sound", true), None);
+
+        let sound = Constant::named("v_sound", Number::integer(343), Dimension::from_integers([1, 0, -1, 0, 0, 0, 0]));
+        assert_eq!(sound.to_string(), "v_sound");
+        assert_eq!(sound.solve(), 343.0);
+    }
+
+    #[test]
+    fn test_context() {
+        use crate::expression::context::Context;
+        use crate::expression::Expression;
+        let mut context = Context::new();
+        let gravity = context.define_constant("g_n", Number::from_str("9.80665").unwrap(), Dimension::from_integers([1, 0, -2, 0, 0, 0, 0]));
+        let weight = context.parse("2 g_n").unwrap();
+        assert_eq!(weight, Expression::Number(Number::integer(2)) * Expression::Constant(gravity.clone()));
+        assert_eq!(weight.simplify().to_string(), "2 * g_n");
+        assert_eq!(context.parse(&weight.to_string()), Ok(weight.clone()));
+        assert_eq!(Expression::from_str("2 g_n").unwrap().evaluate(&context), weight.evaluate(&context));
+        assert_eq!(Constant::from_name("g_n", false), None);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        use crate::expression::Expression;
+        let constants = [
+            Constant::Pi, Constant::E, Constant::Tau, Constant::GoldenRatio, Constant::Sqrt2, Constant::EulerGamma,
+            Constant::SpeedOfLight, Constant::Gravitation, Constant::Planck, Constant::ReducedPlanck, Constant::Boltzmann,
+            Constant::Avogadro, Constant::ElementaryCharge, Constant::VacuumPermittivity, Constant::VacuumPermeability,
+        ];
+        for constant in constants {
+            let parsed = Expression::from_str(&constant.to_string()).unwrap();
+            assert_eq!(parsed.solve(None), Ok(constant.solve()), "{constant}");
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::equation::Equation;
+use crate::expression::constant::Constant;
+use crate::expression::error::ExpressionError;
+use crate::expression::from_str::{ParseOptions, ParseResult};
+use crate::expression::number::Number;
+use crate::expression::Expression;
+use crate::units::dimension::Dimension;
+use crate::units::quantity::Quantity;
+use crate::units::unit::Unit;
+
+/// Nesting depth of function calls above which evaluation and inlining give up,
+/// so that recursive definitions fail instead of overflowing the stack
+pub const MAX_CALL_DEPTH: usize = 64;
+
+/// A Rust function of the evaluated arguments of a call
+pub type NativeFunction = Rc<dyn Fn(&[f64]) -> f64>;
+
+/// The body of a user-defined function
+#[derive(Clone)]
+pub enum Definition {
+    /// An expression in terms of named parameters, like `x^2 + 1` for `f(x)`
+    Expression { parameters: Vec<String>, body: Expression },
+    /// A Rust function of the evaluated arguments, which can be evaluated but not inlined
+    Native { arity: usize, function: NativeFunction },
+}
+
+impl Definition {
+    pub fn arity(&self) -> usize {
+        match self {
+            Definition::Expression { parameters, .. } => parameters.len(),
+            Definition::Native { arity, .. } => *arity,
+        }
+    }
+}
+
+impl Debug for Definition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Definition::Expression { parameters, body } => write!(f, "({}) -> {body}", parameters.join(", ")),
+            Definition::Native { arity, .. } => write!(f, "native function of {arity} arguments"),
+        }
+    }
+}
+
+/// Values of variables and definitions of functions and constants to evaluate expressions with,
+/// and the units the values of variables are measured in
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub variables: HashMap<String, f64>,
+    functions: HashMap<String, Definition>,
+    constants: HashMap<String, Constant>,
+    units: HashMap<String, Unit>,
+    depth: Cell<usize>,
+    /// Parameters of the calls being evaluated bound to their arguments, innermost last
+    bindings: RefCell<Vec<(String, f64)>>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: f64) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    /// Value of a variable, where the parameters of the calls being evaluated shadow variables of the same name
+    pub fn variable(&self, name: &str) -> Option<f64> {
+        self.bindings.borrow().iter()
+            .rev()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| *value)
+            .or_else(|| self.variables.get(name).copied())
+    }
+
+    /// Declares the unit that the value of a variable is measured in
+    pub fn declare_unit(&mut self, name: &str, unit: Unit) {
+        self.units.insert(name.to_string(), unit);
+    }
+
+    /// Sets a variable to the value of the quantity and declares its unit
+    pub fn set_quantity(&mut self, name: &str, quantity: &Quantity) {
+        self.set_variable(name, quantity.value.value());
+        self.declare_unit(name, quantity.unit.clone());
+    }
+
+    pub fn unit(&self, name: &str) -> Option<&Unit> {
+        self.units.get(name)
+    }
+
+    /// Defines `name(parameters) = body`, replacing an earlier definition of the same name
+    pub fn define(&mut self, name: &str, parameters: &[&str], body: Expression) {
+        let parameters = parameters.iter().map(|parameter| parameter.to_string()).collect();
+        self.functions.insert(name.to_string(), Definition::Expression { parameters, body });
+    }
+
+    pub fn define_native(&mut self, name: &str, arity: usize, function: impl Fn(&[f64]) -> f64 + 'static) {
+        self.functions.insert(name.to_string(), Definition::Native { arity, function: Rc::new(function) });
+    }
+
+    /// Defines a function from an equation like `f(x, y) = x * y`,
+    /// whose left side must be a call with distinct variables as arguments
+    pub fn define_equation(&mut self, equation: &Equation) -> Result<(), ExpressionError> {
+        let Expression::Function(function) = &equation.left else {
+            return Err(ExpressionError::InvalidDefinition(equation.left.to_string()));
+        };
+        let mut parameters: Vec<&str> = Vec::new();
+        for argument in &function.args {
+            match argument {
+                Expression::Variable(name) if !parameters.contains(&name.as_str()) => parameters.push(name),
+                _ => return Err(ExpressionError::InvalidDefinition(equation.left.to_string())),
+            }
+        }
+        self.define(&function.name, &parameters, equation.right.clone());
+        Ok(())
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Definition> {
+        self.functions.get(name)
+    }
+
+    /// Defines a constant with its value in the coherent SI unit of `dimension`, which `parse` reads for `name`
+    /// and which stays symbolic until the expression is evaluated
+    pub fn define_constant(&mut self, name: &str, value: Number, dimension: Dimension) -> Constant {
+        let constant = Constant::named(name, value, dimension);
+        self.constants.insert(name.to_string(), constant.clone());
+        constant
+    }
+
+    pub fn constant(&self, name: &str) -> Option<&Constant> {
+        self.constants.get(name)
+    }
+
+    /// Parses the input with the default settings, reading the names of the constants defined here as those constants
+    pub fn parse(&self, input: &str) -> ParseResult<Expression> {
+        self.parse_with(input, ParseOptions::default())
+    }
+
+    pub fn parse_with(&self, input: &str, options: ParseOptions) -> ParseResult<Expression> {
+        let constants = self.constants.iter()
+            .map(|(name, constant)| (name.clone(), Expression::Constant(constant.clone())))
+            .collect();
+        Ok(Expression::parse_with(input, options)?.substitute_all(&constants))
+    }
+
+    /// Runs `f` one call level deeper, failing once `MAX_CALL_DEPTH` is exceeded
+    pub(crate) fn enter<T>(&self, name: &str, f: impl FnOnce() -> Result<T, ExpressionError>) -> Result<T, ExpressionError> {
+        if self.depth.get() >= MAX_CALL_DEPTH {
+            return Err(ExpressionError::RecursionLimit(name.to_string()));
+        }
+        self.depth.set(self.depth.get() + 1);
+        let result = f();
+        self.depth.set(self.depth.get() - 1);
+        result
+    }
+
+    /// Runs `f` with the parameters of a call bound to its arguments, removing the bindings again afterwards
+    pub(crate) fn bind<T>(&self, bindings: impl IntoIterator<Item = (String, f64)>, f: impl FnOnce() -> T) -> T {
+        let outer = self.bindings.borrow().len();
+        self.bindings.borrow_mut().extend(bindings);
+        let result = f();
+        self.bindings.borrow_mut().truncate(outer);
+        result
+    }
+}
+
+impl From<HashMap<String, f64>> for Context {
+    fn from(variables: HashMap<String, f64>) -> Self {
+        Context { variables, ..Self::default() }
+    }
+}
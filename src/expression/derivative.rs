@@ -0,0 +1,239 @@
+use crate::expression::{add, function, multiply, Expression};
+use crate::expression::constant::Constant::Pi;
+use crate::{abs, cos, cosh, exp, factorial, floor, gamma, inv, ln, neg, num, polygamma, pow, sin, sinh, sqrt};
+
+impl Expression {
+    /// Symbolic derivative of the expression with respect to `variable`
+    pub fn derivative(&self, variable: &str) -> Expression {
+        self.derive(variable).simplify()
+    }
+
+    fn derive(&self, variable: &str) -> Expression {
+        use Expression::*;
+        if !self.contains_variable(variable) {
+            return num!(0);
+        }
+        match self {
+            Constant(_) | Number(_) => num!(0),
+            Variable(name) => if name == variable { num!(1) } else { num!(0) },
+            Add(add) => Add(add::Add(add.0.iter().map(|child| child.derive(variable)).collect())),
+            // Product rule: (f * g * h)' = f' * g * h + f * g' * h + f * g * h'
+            Multiply(multiply) => Add(add::Add(
+                (0..multiply.0.len())
+                    .map(|i| Multiply(multiply::Multiply(
+                        multiply.0.iter()
+                            .enumerate()
+                            .map(|(j, child)| if i == j { child.derive(variable) } else { child.clone() })
+                            .collect()
+                    )))
+                    .collect()
+            )),
+            Power(base, exponent) => {
+                let base = *base.clone();
+                let exponent = *exponent.clone();
+                if !exponent.contains_variable(variable) {
+                    // (f ^ n)' = n * f ^ (n - 1) * f'
+                    exponent.clone() * pow!(base.clone(), exponent - num!(1)) * base.derive(variable)
+                } else if !base.contains_variable(variable) {
+                    // (a ^ g)' = a ^ g * ln(a) * g'
+                    pow!(base.clone(), exponent.clone()) * ln!(base) * exponent.derive(variable)
+                } else {
+                    // (f ^ g)' = f ^ g * (g' * ln(f) + g * f' / f)
+                    pow!(base.clone(), exponent.clone()) * (
+                        exponent.derive(variable) * ln!(base.clone()) + exponent * base.derive(variable) / base
+                    )
+                }
+            }
+            Sqrt(a) => a.derive(variable) / (num!(2) * sqrt!(*a.clone())),
+            // Logarithm of a with base b, which is ln(a) / ln(b)
+            Log(a, b) => {
+                if !b.contains_variable(variable) {
+                    a.derive(variable) / (*a.clone() * ln!(*b.clone()))
+                } else {
+                    let numerator = a.derive(variable) / *a.clone() * ln!(*b.clone())
+                        - ln!(*a.clone()) * b.derive(variable) / *b.clone();
+                    numerator / pow!(ln!(*b.clone()), num!(2))
+                }
+            }
+            Sin(a) => cos!(*a.clone()) * a.derive(variable),
+            ArcSin(a) => a.derive(variable) / sqrt!(num!(1) - pow!(*a.clone(), num!(2))),
+            Cos(a) => neg!(sin!(*a.clone()) * a.derive(variable)),
+            ArcCos(a) => neg!(a.derive(variable) / sqrt!(num!(1) - pow!(*a.clone(), num!(2)))),
+            Tan(a) => a.derive(variable) / pow!(cos!(*a.clone()), num!(2)),
+            ArcTan(a) => a.derive(variable) / (num!(1) + pow!(*a.clone(), num!(2))),
+            Sinh(a) => cosh!(*a.clone()) * a.derive(variable),
+            ArcSinh(a) => a.derive(variable) / sqrt!(pow!(*a.clone(), num!(2)) + num!(1)),
+            Cosh(a) => sinh!(*a.clone()) * a.derive(variable),
+            ArcCosh(a) => a.derive(variable) / sqrt!(pow!(*a.clone(), num!(2)) - num!(1)),
+            Tanh(a) => a.derive(variable) / pow!(cosh!(*a.clone()), num!(2)),
+            ArcTanh(a) => a.derive(variable) / (num!(1) - pow!(*a.clone(), num!(2))),
+            Ln(a) => a.derive(variable) / *a.clone(),
+            Abs(a) => *a.clone() * a.derive(variable) / abs!(*a.clone()),
+            Gamma(a) => gamma!(*a.clone()) * polygamma!(num!(0), *a.clone()) * a.derive(variable),
+            // The order is a constant integer, so only the argument is derived
+            Polygamma(n, a) => polygamma!(*n.clone() + num!(1), *a.clone()) * a.derive(variable),
+            Factorial(a) => factorial!(*a.clone()) * polygamma!(num!(0), *a.clone() + num!(1)) * a.derive(variable),
+            Erf(a) => num!(2) / sqrt!(Constant(Pi)) * exp!(neg!(pow!(*a.clone(), num!(2)))) * a.derive(variable),
+            Erfc(a) => neg!(num!(2) / sqrt!(Constant(Pi)) * exp!(neg!(pow!(*a.clone(), num!(2)))) * a.derive(variable)),
+            // Piecewise constant, so the derivative is zero wherever it exists
+            Floor(_) | Ceil(_) | Round(_) | Sign(_) => num!(0),
+            // min(a, b) = (a + b - |a - b|) / 2 and max(a, b) = (a + b + |a - b|) / 2, applied to the first argument and the rest
+            Min(args) | Max(args) => match args.split_first() {
+                Some((first, [])) => first.derive(variable),
+                Some((first, rest)) => {
+                    let rest = match self {
+                        Min(_) => Min(rest.to_vec()),
+                        _ => Max(rest.to_vec()),
+                    };
+                    let (sum, spread) = (first.clone() + rest.clone(), abs!(first.clone() - rest));
+                    let extremum = match self {
+                        Min(_) => sum - spread,
+                        _ => sum + spread,
+                    };
+                    (extremum / num!(2)).derive(variable)
+                }
+                None => num!(0),
+            },
+            // mod(a, b) = a - b * floor(a / b)
+            Mod(a, b) => a.derive(variable) - b.derive(variable) * floor!(*a.clone() / *b.clone()),
+            Negate(negate) => neg!(negate.0.derive(variable)),
+            // (1 / f)' = -f' / f ^ 2
+            Invert(invert) => neg!(invert.0.derive(variable) * inv!(pow!(*invert.0.clone(), num!(2)))),
+            // Branch by branch, which is the derivative everywhere except on the boundaries
+            Piecewise(branches, otherwise) => Piecewise(
+                branches.iter().map(|(condition, value)| (condition.clone(), value.derive(variable))).collect(),
+                Box::new(otherwise.derive(variable)),
+            ),
+            // Chain rule with the derivative of an undefined function written in prime notation,
+            // f'(u) for a single argument and f'_i(u, v) for the i-th of several
+            Function(function) => Add(add::Add(
+                function.args.iter().enumerate()
+                    .filter(|(_, arg)| arg.contains_variable(variable))
+                    .map(|(i, arg)| {
+                        let name = match function.args.len() {
+                            1 => format!("{}'", function.name),
+                            _ => format!("{}'_{}", function.name, i + 1),
+                        };
+                        Function(function::Function { name, args: function.args.clone() }) * arg.derive(variable)
+                    })
+                    .collect()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::{abs, acos, acosh, asin, asinh, atan, atanh, cos, cosh, erf, erfc, factorial, gamma, inv, ln, log, max, min, modulo, neg, num, polygamma, pow, sin, sinh, sqrt, tan, tanh, var};
+    use std::str::FromStr;
+    use crate::expression::constant::Constant;
+    use super::*;
+
+    fn evaluate(expression: &Expression, x: f64) -> f64 {
+        let variables = HashMap::from([("x".to_string(), x), ("y".to_string(), 1.7)]);
+        expression.solve(Some(&variables)).unwrap()
+    }
+
+    /// Compares the symbolic derivative against a central finite difference at `x`
+    fn assert_matches_finite_difference(expression: Expression, x: f64) {
+        let derivative = expression.derivative("x");
+        let h = 1e-6;
+        let numeric = (evaluate(&expression, x + h) - evaluate(&expression, x - h)) / (2.0 * h);
+        let symbolic = evaluate(&derivative, x);
+        assert!(
+            (numeric - symbolic).abs() < 1e-5 * numeric.abs().max(1.0),
+            "d/dx {expression} = {derivative} gives {symbolic}, expected {numeric}"
+        );
+    }
+
+    #[test]
+    fn test_basic_rules() {
+        assert_eq!(num!(5).derivative("x"), num!(0));
+        assert_eq!(var!("y").derivative("x"), num!(0));
+        assert_eq!(var!("x").derivative("x"), num!(1));
+        assert_eq!(pow!(var!("x"), num!(2)).derivative("x"), var!("x") * num!(2));
+        assert_eq!((var!("x") + var!("y")).derivative("x"), num!(1));
+        assert_eq!((var!("x") - var!("y")).derivative("x"), num!(1));
+        assert_eq!((var!("y") - var!("x")).derivative("x"), num!(-1));
+        let exp = pow!(Expression::Constant(Constant::E), var!("x"));
+        assert_eq!(exp.derivative("x"), exp);
+        assert_eq!(ln!(var!("x")).derivative("x").to_string(), "1 / x");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_matches_finite_difference(var!("x") * var!("y") + num!(3) * var!("x"), 0.4);
+        assert_matches_finite_difference(var!("x") * sin!(var!("x")) * var!("x"), 1.3);
+        assert_matches_finite_difference(neg!(pow!(var!("x"), num!(3))), 0.8);
+        assert_matches_finite_difference(inv!(var!("x") + num!(1)), 0.5);
+        assert_matches_finite_difference(var!("y") / var!("x"), 2.5);
+    }
+
+    #[test]
+    fn test_power() {
+        assert_matches_finite_difference(pow!(var!("x"), num!(3)), 1.1);
+        assert_matches_finite_difference(pow!(var!("x"), num!(0.5)), 2.0);
+        assert_matches_finite_difference(pow!(num!(2), var!("x")), 0.7);
+        assert_matches_finite_difference(pow!(Expression::Constant(Constant::E), num!(2) * var!("x")), 0.3);
+        assert_matches_finite_difference(pow!(var!("x"), var!("x")), 1.5);
+        assert_matches_finite_difference(sqrt!(var!("x") * var!("x") + num!(1)), 0.9);
+    }
+
+    #[test]
+    fn test_logarithms() {
+        assert_matches_finite_difference(ln!(pow!(var!("x"), num!(2))), 1.2);
+        assert_matches_finite_difference(log!(var!("x"), num!(10)), 3.0);
+        assert_matches_finite_difference(log!(num!(8), var!("x")), 2.2);
+        assert_matches_finite_difference(log!(var!("x") + num!(1), var!("x")), 2.2);
+        assert_matches_finite_difference(abs!(var!("x") - num!(2)), 0.5);
+        assert_matches_finite_difference(abs!(var!("x") - num!(2)), 3.5);
+    }
+
+    #[test]
+    fn test_trigonometry() {
+        assert_matches_finite_difference(sin!(num!(2) * var!("x")), 0.4);
+        assert_matches_finite_difference(cos!(pow!(var!("x"), num!(2))), 0.6);
+        assert_matches_finite_difference(tan!(var!("x")), 0.3);
+        assert_matches_finite_difference(asin!(var!("x") / num!(2)), 0.5);
+        assert_matches_finite_difference(acos!(var!("x")), -0.2);
+        assert_matches_finite_difference(atan!(var!("x") * var!("y")), 1.4);
+    }
+
+    #[test]
+    fn test_hyperbolic() {
+        assert_matches_finite_difference(sinh!(num!(2) * var!("x")), 0.4);
+        assert_matches_finite_difference(cosh!(pow!(var!("x"), num!(2))), 0.6);
+        assert_matches_finite_difference(tanh!(var!("x")), 0.3);
+        assert_matches_finite_difference(asinh!(var!("x") * var!("y")), -0.5);
+        assert_matches_finite_difference(acosh!(var!("x") + num!(1)), 0.7);
+        assert_matches_finite_difference(atanh!(var!("x") / num!(2)), 0.5);
+    }
+    #[test]
+    fn test_piecewise() {
+        let saturating = Expression::from_str("if(x < 0, 0, x < 1, x^2, 1)").unwrap();
+        for x in [-0.5, 0.5, 2.0] {
+            assert_matches_finite_difference(saturating.clone(), x);
+        }
+        assert_eq!(saturating.derivative("x"), Expression::from_str("if(x < 0, 0, x < 1, 2 x, 0)").unwrap().simplify());
+        assert_eq!(Expression::from_str("if(x < 0, 1, 2)").unwrap().derivative("x"), num!(0));
+    }
+
+    #[test]
+    fn test_special_functions() {
+        assert_matches_finite_difference(erf!(var!("x") * var!("y")), 0.3);
+        assert_matches_finite_difference(erfc!(pow!(var!("x"), num!(2))), 0.8);
+        assert_matches_finite_difference(min!(var!("x"), var!("y"), pow!(var!("x"), num!(2))), 1.5);
+        assert_matches_finite_difference(max!(var!("x"), num!(1) - var!("x")), 0.2);
+        assert_matches_finite_difference(modulo!(pow!(var!("x"), num!(2)), var!("y")), 1.6);
+        assert_matches_finite_difference(floor!(var!("x")) * var!("x"), 2.5);
+        assert_eq!(
+            gamma!(pow!(var!("x"), num!(2))).derivative("x"),
+            (gamma!(pow!(var!("x"), num!(2))) * polygamma!(num!(0), pow!(var!("x"), num!(2))) * num!(2) * var!("x")).simplify()
+        );
+        assert_matches_finite_difference(gamma!(pow!(var!("x"), num!(2))), 1.3);
+        assert_matches_finite_difference(factorial!(var!("x") * var!("y")), 0.7);
+        assert_matches_finite_difference(polygamma!(num!(1), var!("x") + num!(2)), 0.4);
+        assert_eq!(Expression::from_str("digamma(x)").unwrap().derivative("x").to_string(), "polygamma(1, x)");
+    }
+}
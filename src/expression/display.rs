@@ -15,6 +15,68 @@ macro_rules! parenthesize_if_of_type {
 
 pub(crate) use parenthesize_if_of_type;
 
+/// How tightly the printed form of an expression binds, from loosest to tightest.
+/// A child is wrapped in parentheses when it binds less tightly than its position requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Precedence {
+    Sum,
+    Product,
+    Unary,
+    Power,
+    Atom,
+}
+
+impl Expression {
+    pub(crate) fn precedence(&self) -> Precedence {
+        use Expression::*;
+        match self {
+            Add(add) if add.0.len() == 1 => add.0[0].precedence(),
+            Add(add) if add.0.len() > 1 => Precedence::Sum,
+            Multiply(multiply) if multiply.0.len() == 1 => multiply.0[0].precedence(),
+            Multiply(multiply) if multiply.0.len() > 1 => Precedence::Product,
+            Invert(_) => Precedence::Product,
+            Number(number) if !number.is_integer() && number.is_exact() => Precedence::Product,
+            Number(number) if number.is_negative() => Precedence::Unary,
+            Negate(_) => Precedence::Unary,
+            Power(..) => Precedence::Power,
+            Piecewise(branches, otherwise) if branches.is_empty() => otherwise.precedence(),
+            _ => Precedence::Atom,
+        }
+    }
+
+    /// The positive counterpart of a term that is printed with a leading minus sign, like -x or -3 * x
+    pub(crate) fn negated(&self) -> Option<Expression> {
+        use Expression::*;
+        match self {
+            Negate(negate) => Some(*negate.0.clone()),
+            Number(number) if number.is_negative() => Some(Number(-number.clone())),
+            Multiply(multiply) => match multiply.0.split_first() {
+                Some((Number(number), rest)) if number.is_negative() => {
+                    let mut factors = rest.to_vec();
+                    if *number != -1.0 {
+                        factors.insert(0, Number(-number.clone()));
+                    }
+                    Some(match factors.len() {
+                        1 => factors.remove(0),
+                        _ => Multiply(super::Multiply(factors)),
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Writes the expression, wrapped in parentheses when it binds less tightly than `required`
+pub(crate) fn write_with_precedence(f: &mut Formatter<'_>, expression: &Expression, required: Precedence) -> fmt::Result {
+    if expression.precedence() < required {
+        write!(f, "({expression})")
+    } else {
+        write!(f, "{expression}")
+    }
+}
+
 impl Display for Expression{
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         use Expression::*;
@@ -23,13 +85,18 @@ impl Display for Expression{
             Constant(a) => write!(f, "{a}"),
             Variable(a) => write!(f, "{a}"),
             Add(add) => write!(f, "{add}"),
-            Multiply(multiply) => {write!(f, "{multiply}")},
+            Multiply(multiply) => write!(f, "{multiply}"),
+            // Powers are right-associative and a sign binds looser than them, so only the exponent can go bare
             Power(a, b) => {
-                let a = parenthesize_if_of_type!(**a, Add(..) | Multiply(..));
-                let b = parenthesize_if_of_type!(**b, Add(..) | Multiply(..));
-                write!(f, "{a} ^ {b}")
+                write_with_precedence(f, a, Precedence::Atom)?;
+                write!(f, " ^ ")?;
+                write_with_precedence(f, b, Precedence::Unary)
             }
-            Log(a, b) => write!(f, "log_{a}({b})"),
+            Log(a, b) => match &**b {
+                Number(base) if *base == 10.0 => write!(f, "log({a})"),
+                Number(base) if *base == 2.0 => write!(f, "log2({a})"),
+                b => write!(f, "log({a}, {b})"),
+            },
             Ln(a) => write!(f, "ln({a})"),
             Sin(a) => write!(f, "sin({a})"),
             Cos(a) => write!(f, "cos({a})"),
@@ -37,10 +104,171 @@ impl Display for Expression{
             ArcSin(a) => write!(f, "arcsin({a})"),
             ArcCos(a) => write!(f, "arccos({a})"),
             ArcTan(a) => write!(f, "arctan({a})"),
+            Sinh(a) => write!(f, "sinh({a})"),
+            ArcSinh(a) => write!(f, "arsinh({a})"),
+            Cosh(a) => write!(f, "cosh({a})"),
+            ArcCosh(a) => write!(f, "arcosh({a})"),
+            Tanh(a) => write!(f, "tanh({a})"),
+            ArcTanh(a) => write!(f, "artanh({a})"),
             Sqrt(a) => write!(f, "sqrt({a})"),
             Abs(a) => write!(f, "abs({a})"),
-            Negate(negate) => { write!(f, "{negate}") }
-            Invert(invert) => { write!(f, "1/({invert})") }
+            Negate(negate) => write!(f, "{negate}"),
+            Invert(invert) => write!(f, "{invert}"),
+            Gamma(a) => write!(f, "gamma({a})"),
+            Polygamma(n, a) if **n == super::Expression::Number(0.into()) => write!(f, "digamma({a})"),
+            Polygamma(n, a) => write!(f, "polygamma({n}, {a})"),
+            Erf(a) => write!(f, "erf({a})"),
+            Erfc(a) => write!(f, "erfc({a})"),
+            // The factorial binds tighter than any other operator, so anything but an atom needs parentheses
+            Factorial(a) => {
+                write_with_precedence(f, a, Precedence::Atom)?;
+                write!(f, "!")
+            }
+            Floor(a) => write!(f, "floor({a})"),
+            Ceil(a) => write!(f, "ceil({a})"),
+            Round(a) => write!(f, "round({a})"),
+            Sign(a) => write!(f, "sign({a})"),
+            // Without arguments the extrema are the identities +∞ and -∞, which the parser cannot read as calls
+            Min(args) if args.is_empty() => write!(f, "{}", super::number::Number::float(f64::INFINITY)),
+            Max(args) if args.is_empty() => write!(f, "{}", super::number::Number::float(f64::NEG_INFINITY)),
+            Min(args) => write_call(f, "min", args),
+            Max(args) => write_call(f, "max", args),
+            Mod(a, b) => write!(f, "mod({a}, {b})"),
+            Function(function) => write_call(f, &function.name, &function.args),
+            Piecewise(branches, otherwise) if branches.is_empty() => write!(f, "{otherwise}"),
+            Piecewise(branches, otherwise) => {
+                write!(f, "if(")?;
+                for (condition, value) in branches {
+                    write!(f, "{condition}, {value}, ")?;
+                }
+                write!(f, "{otherwise})")
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Writes `name(a, b, ...)`
+fn write_call(f: &mut Formatter<'_>, name: &str, args: &[Expression]) -> fmt::Result {
+    write!(f, "{name}(")?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{arg}")?;
+    }
+    write!(f, ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use proptest::prelude::*;
+    use crate::expression::constant::Constant;
+    use crate::expression::number::Number;
+    use crate::expression::context::Context;
+    use crate::expression::piecewise::Comparison;
+    use crate::expression::{Condition, Function};
+    use crate::{abs, add, atanh, compare, cos, erf, factorial, inv, ln, log, max, min, modulo, mul, neg, num, piecewise, polygamma, pow, sin, sinh, sqrt, var};
+    use super::*;
+
+    fn expression() -> impl Strategy<Value = Expression> {
+        let leaf = prop_oneof![
+            prop::sample::select(vec!["x", "y", "z"]).prop_map(|name| var!(name)),
+            (-20i64..20).prop_map(|n| num!(n)),
+            (-20i64..20, 1i64..7).prop_map(|(n, d)| num!(n, d)),
+            Just(Expression::Constant(Constant::Pi)),
+            Just(Expression::Constant(Constant::E)),
+            Just(Expression::Constant(Constant::Tau)),
+            Just(Expression::Constant(Constant::GoldenRatio)),
+            prop::sample::select(vec![Constant::Sqrt2, Constant::EulerGamma, Constant::SpeedOfLight, Constant::Planck, Constant::Boltzmann])
+                .prop_map(Expression::Constant),
+            prop_oneof![-1e3..1e3, -1e300..1e300f64].prop_map(|float| Expression::Number(Number::float(float))),
+            Just(Expression::Number(Number::float(f64::INFINITY))),
+        ];
+        leaf.prop_recursive(4, 32, 4, |inner| prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(|children| Expression::Add(crate::expression::Add(children))),
+            prop::collection::vec(inner.clone(), 0..4).prop_map(|children| Expression::Multiply(crate::expression::Multiply(children))),
+            (inner.clone(), inner.clone()).prop_map(|(base, exponent)| pow!(base, exponent)),
+            inner.clone().prop_map(|a| neg!(a)),
+            inner.clone().prop_map(|a| inv!(a)),
+            inner.clone().prop_map(|a| sin!(a)),
+            inner.clone().prop_map(|a| cos!(a)),
+            inner.clone().prop_map(|a| sinh!(a)),
+            inner.clone().prop_map(|a| atanh!(a)),
+            inner.clone().prop_map(|a| ln!(a)),
+            (inner.clone(), prop_oneof![Just(num!(10)), Just(num!(2)), inner.clone()]).prop_map(|(a, b)| log!(a, b)),
+            inner.clone().prop_map(|a| sqrt!(a)),
+            inner.clone().prop_map(|a| abs!(a)),
+            inner.clone().prop_map(|a| erf!(a)),
+            inner.clone().prop_map(|a| factorial!(a)),
+            (0i64..3, inner.clone()).prop_map(|(n, a)| polygamma!(num!(n), a)),
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Expression::Min),
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Expression::Max),
+            // With a simple fraction as divisor, float rounding can land on either side of a jump of mod
+            (inner.clone(), prop::sample::select(vec![var!("x"), var!("y"), Expression::Constant(Constant::Pi)])).prop_map(|(a, b)| modulo!(a, b)),
+            (prop::sample::select(vec!["f", "g"]), prop::collection::vec(inner.clone(), 0..3)).prop_map(|(name, args)| Expression::Function(Function { name: name.to_string(), args })),
+            (prop::collection::vec((condition(inner.clone()), inner.clone()), 0..3), inner)
+                .prop_map(|(branches, otherwise)| Expression::Piecewise(branches, Box::new(otherwise))),
+        ])
+    }
+
+    fn condition(expression: impl Strategy<Value = Expression> + Clone + 'static) -> impl Strategy<Value = Condition> {
+        let comparisons = vec![Comparison::Less, Comparison::LessEqual, Comparison::Equal, Comparison::NotEqual, Comparison::Greater];
+        let comparison = (expression.clone(), prop::sample::select(comparisons), expression)
+            .prop_map(|(a, comparison, b)| Condition::Compare(a, comparison, b));
+        prop_oneof![any::<bool>().prop_map(Condition::Bool), comparison].prop_recursive(2, 8, 3, |inner| prop_oneof![
+            prop::collection::vec(inner.clone(), 2..3).prop_map(Condition::And),
+            prop::collection::vec(inner.clone(), 2..3).prop_map(Condition::Or),
+            inner.prop_map(|condition| Condition::Not(Box::new(condition))),
+        ])
+    }
+
+    fn same_value(a: f64, b: f64) -> bool {
+        (a.is_nan() && b.is_nan()) || a == b || (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(add!(var!("a"), neg!(var!("b")), mul!(num!(-2), var!("c"))).to_string(), "a - b - 2 * c");
+        assert_eq!(mul!(var!("a"), inv!(add!(var!("b"), var!("c")))).to_string(), "a / (b + c)");
+        assert_eq!(neg!(pow!(var!("x"), num!(2))).to_string(), "-x ^ 2");
+        assert_eq!(pow!(neg!(var!("x")), num!(2)).to_string(), "(-x) ^ 2");
+        assert_eq!(pow!(var!("x"), pow!(var!("y"), num!(-1))).to_string(), "x ^ y ^ -1");
+        assert_eq!(pow!(pow!(var!("x"), var!("y")), num!(2)).to_string(), "(x ^ y) ^ 2");
+        assert_eq!(add!(var!("a"), sin!(var!("b")), var!("c")).to_string(), "a + sin(b) + c");
+        assert_eq!(mul!(var!("a"), num!(2, 3)).to_string(), "a * (2/3)");
+        assert_eq!(log!(var!("x"), num!(10)).to_string(), "log(x)");
+        assert_eq!(log!(var!("x") + num!(1), var!("b")).to_string(), "log(x + 1, b)");
+        assert_eq!(factorial!(var!("n")).to_string(), "n!");
+        assert_eq!((polygamma!(num!(0), var!("x")) + polygamma!(num!(1), var!("x"))).to_string(), "digamma(x) + polygamma(1, x)");
+        assert_eq!(pow!(factorial!(neg!(var!("n"))), num!(2)).to_string(), "(-n)! ^ 2");
+        assert_eq!(factorial!(num!(1, 2)).to_string(), "(1/2)!");
+        assert_eq!(max!(var!("a"), modulo!(var!("b"), num!(3))).to_string(), "max(a, mod(b, 3))");
+        assert_eq!(min!(var!("a")).to_string(), "min(a)");
+        assert_eq!(Expression::Min(vec![]).to_string(), "(1/0)");
+        assert_eq!(mul!(var!("a"), Expression::Max(vec![])).to_string(), "a * (-1/0)");
+        assert_eq!(mul!(num!(2), Expression::Piecewise(vec![], Box::new(var!("x") + num!(1)))).to_string(), "2 * (x + 1)");
+        assert_eq!(
+            mul!(num!(2), piecewise!((compare!(var!("x"), <, num!(0)), neg!(var!("x"))), (compare!(var!("x"), >, num!(1)), num!(1)); var!("x"))).to_string(),
+            "2 * if(x < 0, -x, x > 1, 1, x)"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(expression in expression()) {
+            let mut context = Context::from(HashMap::from([("x", 0.7), ("y", -1.3), ("z", 2.1)].map(|(k, v)| (k.to_string(), v))));
+            context.define("f", &["a"], var!("a") * num!(2) + num!(1));
+            context.define("g", &["a", "b"], var!("a") - var!("b"));
+            let displayed = expression.to_string();
+            let parsed = Expression::from_str(&displayed);
+            prop_assert!(parsed.is_ok(), "{displayed} does not parse");
+            let (expected, actual) = (expression.evaluate(&context), parsed.unwrap().evaluate(&context));
+            match (expected, actual) {
+                (Ok(expected), Ok(actual)) => prop_assert!(same_value(expected, actual), "{displayed}: {expected} != {actual}"),
+                (expected, actual) => prop_assert_eq!(expected.is_ok(), actual.is_ok(), "{}", displayed),
+            }
+        }
+    }
+}
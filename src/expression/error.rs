@@ -1,6 +1,114 @@
+use std::fmt::{self, Display, Formatter};
+
+use num_rational::Rational32;
+
+use crate::expression::Expression;
+use crate::operation::Operation;
+use crate::units::dimension::Dimension;
+
 #[derive(Debug, PartialEq)]
 pub enum ExpressionError {
     /// The expression cannot be solved because it contains a variable that is not defined
     MissingVariable(String),
+    /// The expression calls a function that is not defined in the context
+    UnknownFunction(String),
+    /// A function is called with a different number of arguments than it was defined with
+    ArgumentCount { name: String, expected: usize, found: usize },
+    /// Function calls nest deeper than the call depth limit, usually because of a recursive definition
+    RecursionLimit(String),
+    /// A function definition whose left side is not a call with distinct variables as arguments
+    InvalidDefinition(String),
+}
+
+/// Failure to infer or convert the physical dimension of an expression
+#[derive(Debug, PartialEq)]
+pub enum UnitError {
+    /// Quantities of different dimensions are added, compared or converted into each other
+    IncompatibleDimensions(Dimension, Dimension),
+    /// A function that needs a pure number, like sin or ln, is applied to a dimensional quantity
+    DimensionalArgument(Operation, Dimension),
+    /// A dimensional quantity is raised to a power that is not an exact rational number
+    NonRationalExponent(Expression),
+    /// Raising a dimension to a power makes one of its exponents too large to represent
+    ExponentOverflow(Dimension, Rational32),
+    /// A unit symbol that is not known, even with an SI prefix
+    UnknownUnit(String),
+    /// The text of a unit could not be parsed
+    Parse(ParseError),
+    /// The expression could be checked but not evaluated
+    Evaluation(ExpressionError),
+}
+
+impl From<ExpressionError> for UnitError {
+    fn from(error: ExpressionError) -> Self {
+        UnitError::Evaluation(error)
+    }
+}
+
+impl From<ParseError> for UnitError {
+    fn from(error: ParseError) -> Self {
+        UnitError::Parse(error)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum IsolateError {
+    /// The variable appears on both sides of the equation
+    VariableOnBothSides(String),
+    /// The variable does not appear anywhere in the equation
+    VariableAbsent(String),
+    /// The variable sits inside an operation that cannot be undone,
+    /// like abs, or an operation with the variable in more than one of its operands
+    NonInvertible(Operation),
+}
+
+/// Failure to parse an expression or equation, pointing at the offending position of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The complete text that was parsed
+    pub input: String,
+    /// Byte offset of the error in the input
+    pub offset: usize,
+    /// Descriptions of what could have appeared at the error position, like `")"` or `an operand`
+    pub expected: Vec<String>,
+    /// What went wrong, like `unexpected ")"`
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(input: &str, offset: usize, expected: &[&str], message: impl Into<String>) -> Self {
+        ParseError {
+            input: input.to_string(),
+            offset,
+            expected: expected.iter().map(|expected| expected.to_string()).collect(),
+            message: message.into(),
+        }
+    }
+
+    /// Line of the error, counting from 1
+    pub fn line(&self) -> usize {
+        self.input[..self.offset].matches('\n').count() + 1
+    }
+
+    /// Column of the error in characters, counting from 1
+    pub fn column(&self) -> usize {
+        let line_start = self.input[..self.offset].rfind('\n').map_or(0, |index| index + 1);
+        self.input[line_start..self.offset].chars().count() + 1
+    }
+}
+
+/// Renders the message followed by the erroneous line with a caret under the error position
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line(), self.column())?;
+        match self.expected.as_slice() {
+            [] => {}
+            [expected] => write!(f, ", expected {expected}")?,
+            [first @ .., last] => write!(f, ", expected {} or {last}", first.join(", "))?,
+        }
+        let line = self.input.lines().nth(self.line() - 1).unwrap_or("");
+        write!(f, "\n{line}\n{}^", " ".repeat(self.column() - 1))
+    }
+}
 
-}
\ No newline at end of file
+impl std::error::Error for ParseError {}
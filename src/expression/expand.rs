@@ -0,0 +1,271 @@
+use crate::expression::{add, multiply, number, Expression};
+use crate::{abs, acos, acosh, asin, asinh, atan, atanh, cos, cosh, inv, ln, log, neg, num, pow, sin, sinh, sqrt, tan, tanh};
+
+/// Term count above which `expand` leaves a product or power unexpanded
+pub const DEFAULT_MAX_TERMS: usize = 1000;
+
+impl Expression {
+    /// Distributes products over sums and integer powers and reciprocals over products,
+    /// expands integer powers of sums, then collects like terms
+    pub fn expand(&self) -> Expression {
+        self.expand_limited(DEFAULT_MAX_TERMS)
+    }
+
+    /// Like `expand`, but any product or power that would expand into more than `max_terms` terms is kept as is
+    pub fn expand_limited(&self, max_terms: usize) -> Expression {
+        sum(collect(self.terms(max_terms)))
+    }
+
+    /// The expanded expression as a list of terms that add up to it
+    fn terms(&self, max_terms: usize) -> Vec<Expression> {
+        use Expression::*;
+        match self {
+            Add(add) => add.0.iter().flat_map(|child| child.terms(max_terms)).collect(),
+            Multiply(multiply) => {
+                let factors: Vec<Vec<Expression>> = multiply.0.iter().map(|child| child.terms(max_terms)).collect();
+                let count = factors.iter().try_fold(1usize, |count, terms| count.checked_mul(terms.len()));
+                match count {
+                    Some(count) if count <= max_terms => factors.iter()
+                        .fold(vec![Expression::Multiply(multiply::Multiply(vec![]))], |products, terms| distribute(&products, terms)),
+                    _ => vec![Multiply(multiply::Multiply(factors.into_iter().map(sum).collect()))],
+                }
+            }
+            Power(base, exponent) => {
+                let terms = base.terms(max_terms);
+                let Number(number) = &**exponent else {
+                    return vec![pow!(sum(terms), exponent.expand_limited(max_terms))];
+                };
+                if let ([term], true) = (&terms[..], number.is_integer()) {
+                    // (c * a * b) ^ n = c ^ n * a ^ n * b ^ n for an integer n
+                    return vec![distribute_over_factors(term, |factor| pow!(factor, Number(number.clone())), |coefficient| coefficient.pow(number))];
+                }
+                let power = number.to_i128().and_then(|power| usize::try_from(power).ok());
+                let Some(power) = power.filter(|power| terms.len() >= 2 && multinomial_term_count(*power, terms.len()) <= max_terms) else {
+                    return vec![pow!(sum(terms), Number(number.clone()))];
+                };
+                (0..power).fold(vec![Multiply(multiply::Multiply(vec![]))], |products, _| {
+                    collect(distribute(&products, &terms))
+                })
+            }
+            Negate(negate) => negate.0.terms(max_terms).into_iter().map(|term| neg!(term)).collect(),
+            Invert(invert) => match &invert.0.terms(max_terms)[..] {
+                // 1 / (c * a * b) = 1/c * 1/a * 1/b
+                [term] => vec![distribute_over_factors(term, |factor| inv!(factor), |coefficient| number::Number::from(1) / coefficient.clone())],
+                terms => vec![inv!(sum(collect(terms.to_vec())))],
+            },
+            Log(a, b) => vec![log!(a.expand_limited(max_terms), b.expand_limited(max_terms))],
+            Sqrt(a) => vec![sqrt!(a.expand_limited(max_terms))],
+            Sin(a) => vec![sin!(a.expand_limited(max_terms))],
+            ArcSin(a) => vec![asin!(a.expand_limited(max_terms))],
+            Cos(a) => vec![cos!(a.expand_limited(max_terms))],
+            ArcCos(a) => vec![acos!(a.expand_limited(max_terms))],
+            Tan(a) => vec![tan!(a.expand_limited(max_terms))],
+            ArcTan(a) => vec![atan!(a.expand_limited(max_terms))],
+            Sinh(a) => vec![sinh!(a.expand_limited(max_terms))],
+            ArcSinh(a) => vec![asinh!(a.expand_limited(max_terms))],
+            Cosh(a) => vec![cosh!(a.expand_limited(max_terms))],
+            ArcCosh(a) => vec![acosh!(a.expand_limited(max_terms))],
+            Tanh(a) => vec![tanh!(a.expand_limited(max_terms))],
+            ArcTanh(a) => vec![atanh!(a.expand_limited(max_terms))],
+            Ln(a) => vec![ln!(a.expand_limited(max_terms))],
+            Abs(a) => vec![abs!(a.expand_limited(max_terms))],
+            Gamma(_) | Polygamma(..) | Erf(_) | Erfc(_) | Factorial(_) | Floor(_) | Ceil(_) | Round(_) | Sign(_) | Min(_) | Max(_) | Mod(..) |
+            Function(_) | Piecewise(..) => vec![self.map_children(|arg| arg.expand_limited(max_terms))],
+            Constant(_) | Number(_) | Variable(_) => vec![self.clone()],
+        }
+    }
+}
+
+/// Every product of one of `products` with one of `terms`
+fn distribute(products: &[Expression], terms: &[Expression]) -> Vec<Expression> {
+    products.iter()
+        .flat_map(|product| terms.iter().map(move |term| product.clone() * term.clone()))
+        .collect()
+}
+
+/// Applies `f` to every factor of a single term and `coefficient` to its numeric coefficient,
+/// leaving the term to `f` as a whole when it has no other factors to split off
+fn distribute_over_factors(
+    term: &Expression,
+    f: impl Fn(Expression) -> Expression,
+    coefficient: impl Fn(&number::Number) -> number::Number,
+) -> Expression {
+    match split_coefficient(term.simplify()) {
+        (number, factors) if number == 0.0 || (number == 1.0 && factors.len() == 1) => f(term.clone()),
+        (number, factors) => {
+            let factors = factors.into_iter().map(f);
+            Expression::Multiply(multiply::Multiply(std::iter::once(num!(coefficient(&number))).chain(factors).collect())).simplify()
+        }
+    }
+}
+
+/// Combines like terms, comparing the factors of each term regardless of their order
+fn collect(terms: Vec<Expression>) -> Vec<Expression> {
+    let mut collected: Vec<(number::Number, Vec<Expression>)> = Vec::new();
+    for term in terms {
+        let (coefficient, factors) = split_coefficient(term.simplify());
+        match collected.iter_mut().find(|(_, other)| is_permutation(other, &factors)) {
+            Some((sum, _)) => *sum += coefficient,
+            None => collected.push((coefficient, factors)),
+        }
+    }
+    collected.into_iter()
+        .filter(|(coefficient, _)| *coefficient != 0.0)
+        .map(|(coefficient, factors)| match (coefficient.value(), &factors[..]) {
+            (_, []) => num!(coefficient),
+            (1.0, [factor]) => factor.clone(),
+            (1.0, _) => Expression::Multiply(multiply::Multiply(factors)),
+            (-1.0, [factor]) => neg!(factor.clone()),
+            (-1.0, _) => neg!(Expression::Multiply(multiply::Multiply(factors))),
+            _ => Expression::Multiply(multiply::Multiply(std::iter::once(num!(coefficient)).chain(factors).collect())),
+        })
+        .collect()
+}
+
+/// Splits a simplified term into its numeric coefficient and remaining factors
+fn split_coefficient(term: Expression) -> (number::Number, Vec<Expression>) {
+    use Expression::*;
+    match term {
+        Number(number) => (number, vec![]),
+        Negate(negate) => {
+            let (coefficient, factors) = split_coefficient(*negate.0);
+            (-coefficient, factors)
+        }
+        Multiply(multiply) => {
+            let (numbers, factors): (Vec<Expression>, Vec<Expression>) = multiply.0.into_iter()
+                .partition(|factor| matches!(factor, Number(..)));
+            let coefficient = numbers.into_iter()
+                .map(|number| match number { Number(number) => number, _ => unreachable!() })
+                .fold(number::Number::from(1), |product, number| product * number);
+            (coefficient, factors)
+        }
+        other => (number::Number::from(1), vec![other]),
+    }
+}
+
+fn is_permutation(a: &[Expression], b: &[Expression]) -> bool {
+    a.len() == b.len() && a.iter().all(|factor| {
+        a.iter().filter(|other| *other == factor).count() == b.iter().filter(|other| *other == factor).count()
+    })
+}
+
+fn sum(mut terms: Vec<Expression>) -> Expression {
+    match terms.len() {
+        0 => num!(0),
+        1 => terms.remove(0),
+        _ => Expression::Add(add::Add(terms)),
+    }
+}
+
+/// Number of distinct terms in (a_1 + ... + a_k) ^ n, which is the binomial coefficient (n + k - 1) choose (k - 1)
+fn multinomial_term_count(n: usize, k: usize) -> usize {
+    (1..k).try_fold(1usize, |count, i| Some(count.checked_mul(n + i)? / i)).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::{mul, num, var};
+    use super::*;
+
+    fn terms(expression: &Expression) -> Vec<Expression> {
+        match expression {
+            Expression::Add(add) => add.0.clone(),
+            other => vec![other.clone()],
+        }
+    }
+
+    fn assert_same_value(a: &Expression, b: &Expression) {
+        let variables = HashMap::from([("a", 1.5), ("b", -0.5), ("c", 2.0), ("d", 3.0), ("x", 0.7)].map(|(k, v)| (k.to_string(), v)));
+        let (a_value, b_value) = (a.solve(Some(&variables)).unwrap(), b.solve(Some(&variables)).unwrap());
+        assert!((a_value - b_value).abs() < 1e-9, "{a} = {a_value}, {b} = {b_value}");
+    }
+
+    #[test]
+    fn test_distribute() {
+        let expression = (var!("a") + var!("b")) * (var!("c") + var!("d"));
+        let expanded = expression.expand();
+        assert_eq!(terms(&expanded).len(), 4);
+        assert!(terms(&expanded).contains(&(var!("b") * var!("d")).simplify()));
+        assert_same_value(&expression, &expanded);
+
+        let expression = (var!("a") + var!("b")) / var!("c");
+        let expanded = expression.expand();
+        assert!(terms(&expanded).contains(&(var!("a") / var!("c")).simplify()));
+        assert_same_value(&expression, &expanded);
+
+        let expression = neg!(var!("a") - var!("b") * (var!("c") + num!(1)));
+        let expanded = expression.expand();
+        assert_eq!(terms(&expanded).len(), 3);
+        assert!(terms(&expanded).contains(&neg!(var!("a"))));
+        assert_same_value(&expression, &expanded);
+    }
+
+    #[test]
+    fn test_power() {
+        let expanded = pow!(var!("x") + num!(1), num!(2)).expand();
+        assert_eq!(terms(&expanded).len(), 3);
+        for term in [pow!(var!("x"), num!(2)), mul!(num!(2), var!("x")), num!(1)] {
+            assert!(terms(&expanded).contains(&term), "{term:?} missing in {expanded:?}");
+        }
+
+        let expression = pow!(var!("a") + var!("b") + var!("c"), num!(3));
+        let expanded = expression.expand();
+        assert_eq!(terms(&expanded).len(), 10);
+        assert_same_value(&expression, &expanded);
+
+        let expression = pow!(var!("x") - num!(1), num!(3)) - pow!(var!("x"), num!(3));
+        let expanded = expression.expand();
+        assert_eq!(terms(&expanded).len(), 3);
+        assert_same_value(&expression, &expanded);
+    }
+
+    #[test]
+    fn test_product_powers() {
+        let expression = pow!(num!(2) * var!("x"), num!(2));
+        assert_eq!(expression.expand(), mul!(num!(4), pow!(var!("x"), num!(2))).simplify());
+        assert_same_value(&expression, &expression.expand());
+
+        let expression = pow!(neg!(var!("a") * var!("b")), num!(3));
+        assert_eq!(expression.expand(), neg!(pow!(var!("a"), num!(3)) * pow!(var!("b"), num!(3))).simplify());
+        assert_same_value(&expression, &expression.expand());
+
+        let expression = pow!(num!(3) * var!("a"), num!(-2));
+        assert_eq!(expression.expand(), (num!(1, 9) * pow!(var!("a"), num!(-2))).simplify());
+        assert_eq!(pow!(var!("a") * var!("b"), num!(1, 2)).expand(), pow!(var!("a") * var!("b"), num!(1, 2)).simplify());
+    }
+
+    #[test]
+    fn test_invert() {
+        let expression = inv!(var!("a") * var!("b"));
+        assert_eq!(expression.expand(), (inv!(var!("a")) * inv!(var!("b"))).simplify());
+        assert_same_value(&expression, &expression.expand());
+
+        let expression = inv!(num!(2) * var!("a") * pow!(var!("b") + num!(1), num!(2)));
+        let expanded = expression.expand();
+        assert_eq!(terms(&expanded).len(), 1);
+        assert_same_value(&expression, &expanded);
+
+        let expression = num!(1) / (var!("a") + var!("b"));
+        assert_eq!(expression.expand(), expression.simplify());
+    }
+
+    #[test]
+    fn test_nested() {
+        let expression = sin!(pow!(var!("x") + num!(1), num!(2))) * (var!("a") + var!("b"));
+        let expanded = expression.expand();
+        assert_eq!(terms(&expanded).len(), 2);
+        assert_same_value(&expression, &expanded);
+        assert_eq!(pow!(var!("x") + num!(1), num!(0.5)).expand(), pow!(var!("x") + num!(1), num!(0.5)));
+    }
+
+    #[test]
+    fn test_limit() {
+        let expression = pow!(var!("a") + var!("b") + var!("c"), num!(10));
+        assert!(matches!(expression.expand_limited(10), Expression::Power(..)));
+        assert_eq!(terms(&expression.expand()).len(), 66);
+
+        let expression = (var!("a") + var!("b")) * (var!("c") + var!("d"));
+        assert_eq!(terms(&expression.expand_limited(3)).len(), 1);
+    }
+}
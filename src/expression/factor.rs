@@ -0,0 +1,365 @@
+use crate::expression::{add, multiply, Expression};
+use crate::expression::number::{self, Number};
+use crate::expression::polynomial::Polynomial;
+use crate::utils::integer_polynomial;
+use crate::{abs, acos, acosh, asin, asinh, atan, atanh, cos, cosh, inv, ln, log, neg, num, pow, sin, sinh, sqrt, tan, tanh};
+
+impl Expression {
+    /// Rewrites sums as products where possible: common factors are pulled out,
+    /// differences of squares and perfect-square trinomials are recognised,
+    /// and polynomials in one variable with integer coefficients are split into factors
+    /// that are irreducible over the rationals.
+    pub fn factor(&self) -> Expression {
+        use Expression::*;
+        match self.simplify() {
+            Add(add) => factor_sum(add.0),
+            Multiply(multiply) => product(multiply.0.iter().map(|child| child.factor()).collect()),
+            Power(base, exponent) => pow!(base.factor(), *exponent),
+            Negate(negate) => neg!(negate.0.factor()),
+            Invert(invert) => inv!(invert.0.factor()),
+            Log(a, b) => log!(a.factor(), b.factor()),
+            Sqrt(a) => sqrt!(a.factor()),
+            Sin(a) => sin!(a.factor()),
+            ArcSin(a) => asin!(a.factor()),
+            Cos(a) => cos!(a.factor()),
+            ArcCos(a) => acos!(a.factor()),
+            Tan(a) => tan!(a.factor()),
+            ArcTan(a) => atan!(a.factor()),
+            Sinh(a) => sinh!(a.factor()),
+            ArcSinh(a) => asinh!(a.factor()),
+            Cosh(a) => cosh!(a.factor()),
+            ArcCosh(a) => acosh!(a.factor()),
+            Tanh(a) => tanh!(a.factor()),
+            ArcTanh(a) => atanh!(a.factor()),
+            Ln(a) => ln!(a.factor()),
+            Abs(a) => abs!(a.factor()),
+            other @ (Gamma(_) | Polygamma(..) | Erf(_) | Erfc(_) | Factorial(_) | Floor(_) | Ceil(_) | Round(_) | Sign(_) | Min(_) | Max(_) | Mod(..) |
+                Function(_) | Piecewise(..)) => other.map_children(|arg| arg.factor()),
+            other => other,
+        }
+    }
+}
+
+fn factor_sum(terms: Vec<Expression>) -> Expression {
+    let split: Vec<Term> = terms.iter().map(Term::from).collect();
+    common_factor(&split)
+        .or_else(|| univariate(&terms))
+        .or_else(|| difference_of_squares(&split))
+        .or_else(|| perfect_square(&split))
+        .unwrap_or(Expression::Add(add::Add(terms)))
+}
+
+/// A term split into its numeric coefficient and its factors as (base, exponent) pairs
+#[derive(Clone)]
+struct Term {
+    coefficient: Number,
+    factors: Vec<(Expression, Number)>,
+}
+
+impl From<&Expression> for Term {
+    fn from(expression: &Expression) -> Self {
+        use Expression::*;
+        match expression {
+            Number(number) => Term { coefficient: number.clone(), factors: vec![] },
+            Negate(negate) => {
+                let term = Term::from(&*negate.0);
+                Term { coefficient: -term.coefficient, ..term }
+            }
+            Multiply(multiply) => multiply.0.iter().map(Term::from).fold(
+                Term::factor(vec![]),
+                |product, factor| Term {
+                    coefficient: product.coefficient * factor.coefficient,
+                    factors: product.factors.into_iter().chain(factor.factors).collect(),
+                },
+            ),
+            Power(base, exponent) => match &**exponent {
+                Number(exponent) => Term::factor(vec![(*base.clone(), exponent.clone())]),
+                _ => Term::factor(vec![(expression.clone(), number::Number::from(1))]),
+            },
+            Invert(invert) => {
+                let term = Term::from(&*invert.0);
+                Term {
+                    coefficient: number::Number::from(1) / term.coefficient,
+                    factors: term.factors.into_iter().map(|(base, exponent)| (base, -exponent)).collect(),
+                }
+            }
+            other => Term::factor(vec![(other.clone(), number::Number::from(1))]),
+        }
+    }
+}
+
+impl Term {
+    /// A term with coefficient one
+    fn factor(factors: Vec<(Expression, Number)>) -> Self {
+        Term { coefficient: Number::from(1), factors }
+    }
+
+    fn exponent_of(&self, base: &Expression) -> Number {
+        self.factors.iter()
+            .filter(|(other, _)| other == base)
+            .fold(Number::from(0), |sum, (_, exponent)| sum + exponent.clone())
+    }
+
+    fn to_expression(&self) -> Expression {
+        let factors: Vec<Expression> = self.factors.iter()
+            .filter(|(_, exponent)| *exponent != 0.0)
+            .map(|(base, exponent)| match exponent.value() {
+                1.0 => base.clone(),
+                -1.0 => inv!(base.clone()),
+                _ => pow!(base.clone(), Expression::Number(exponent.clone())),
+            })
+            .collect();
+        match (self.coefficient.value(), factors.is_empty()) {
+            (_, true) => Expression::Number(self.coefficient.clone()),
+            (1.0, false) => product(factors),
+            (-1.0, false) => neg!(product(factors)),
+            (_, false) => product(std::iter::once(Expression::Number(self.coefficient.clone())).chain(factors).collect()),
+        }
+    }
+
+    /// The term whose square is this one, when the coefficient is a perfect square and all exponents are even
+    fn square_root(&self) -> Option<Expression> {
+        let coefficient = self.coefficient.sqrt()?;
+        let two = Number::from(2);
+        if self.factors.iter().any(|(_, exponent)| !(exponent.clone() / two.clone()).is_integer()) {
+            return None;
+        }
+        let factors = self.factors.iter().map(|(base, exponent)| (base.clone(), exponent.clone() / two.clone())).collect();
+        Some(Term { coefficient, factors }.to_expression())
+    }
+}
+
+/// a * c + b * c -> c * (a + b), taking the lowest shared power of every base and
+/// the greatest common divisor of integer coefficients
+fn common_factor(terms: &[Term]) -> Option<Expression> {
+    let (first, rest) = terms.split_first()?;
+    let mut common: Vec<(Expression, Number)> = Vec::new();
+    for (base, _) in &first.factors {
+        if common.iter().any(|(other, _)| other == base) {
+            continue;
+        }
+        let exponents: Vec<Number> = terms.iter().map(|term| term.exponent_of(base)).collect();
+        if exponents.iter().all(|exponent| *exponent > 0.0) {
+            let lowest = exponents.into_iter()
+                .reduce(|lowest, exponent| if exponent.value() < lowest.value() { exponent } else { lowest })?;
+            common.push((base.clone(), lowest));
+        }
+    }
+
+    let mut coefficient = match terms.iter().map(|term| term.coefficient.to_i128()).collect::<Option<Vec<i128>>>() {
+        Some(integers) => Number::from(integers.iter().fold(0, |gcd, integer| integer_polynomial::gcd(gcd, *integer))),
+        None => Number::from(1),
+    };
+    if first.coefficient.is_negative() && rest.iter().all(|term| term.coefficient.is_negative()) {
+        coefficient = -coefficient;
+    }
+    if common.is_empty() && coefficient == 1.0 {
+        return None;
+    }
+
+    let remaining: Vec<Expression> = terms.iter()
+        .map(|term| {
+            let mut factors = term.factors.clone();
+            for (base, exponent) in &common {
+                factors.push((base.clone(), -exponent.clone()));
+            }
+            let factors = merge_exponents(factors);
+            Term { coefficient: term.coefficient.clone() / coefficient.clone(), factors }.to_expression()
+        })
+        .collect();
+    let common = Term { coefficient, factors: common }.to_expression();
+    Some(product(vec![common, factor_sum(remaining)]))
+}
+
+/// Factors a polynomial in its only variable, when all of its coefficients are integers
+fn univariate(terms: &[Expression]) -> Option<Expression> {
+    let sum = Expression::Add(add::Add(terms.to_vec()));
+    let [variable] = &sum.variables()[..] else { return None };
+    let polynomial = sum.as_polynomial(variable)?;
+    let coefficients = polynomial.coefficients().iter()
+        .map(|coefficient| match coefficient {
+            Expression::Number(number) => number.to_i128(),
+            _ => None,
+        })
+        .collect::<Option<Vec<i128>>>()?;
+
+    let (content, factors) = integer_polynomial::factor(&coefficients);
+    if content.abs() == 1 && factors.iter().map(|(_, multiplicity)| multiplicity).sum::<usize>() < 2 {
+        return None;
+    }
+    let factors = factors.into_iter()
+        .map(|(factor, multiplicity)| {
+            let factor = Polynomial::new(variable, factor.into_iter().map(|coefficient| num!(coefficient)).collect());
+            (factor.to_expression(), Number::from(multiplicity))
+        })
+        .collect();
+    Some(Term { coefficient: Number::from(content), factors }.to_expression())
+}
+
+/// a^2 - b^2 -> (a - b) * (a + b)
+fn difference_of_squares(terms: &[Term]) -> Option<Expression> {
+    let [first, second] = terms else { return None };
+    let (positive, negative) = match (first.coefficient > 0.0, second.coefficient > 0.0) {
+        (true, false) => (first, second),
+        (false, true) => (second, first),
+        _ => return None,
+    };
+    let a = positive.square_root()?;
+    let b = Term { coefficient: -negative.coefficient.clone(), ..negative.clone() }.square_root()?;
+    Some(product(vec![(a.clone() - b.clone()).factor(), (a + b).factor()]))
+}
+
+/// a^2 + 2 * a * b + b^2 -> (a + b)^2, and likewise for (a - b)^2
+fn perfect_square(terms: &[Term]) -> Option<Expression> {
+    if terms.len() != 3 {
+        return None;
+    }
+    for (i, j, k) in [(0, 1, 2), (0, 2, 1), (1, 2, 0)] {
+        let (Some(a), Some(b)) = (terms[i].square_root(), terms[j].square_root()) else { continue };
+        let middle = terms[k].to_expression();
+        let double_product = num!(2) * a.clone() * b.clone();
+        if is_zero(middle.clone() - double_product.clone()) {
+            return Some(pow!((a + b).factor(), num!(2)));
+        }
+        if is_zero(middle + double_product) {
+            return Some(pow!((a - b).factor(), num!(2)));
+        }
+    }
+    None
+}
+
+fn merge_exponents(factors: Vec<(Expression, Number)>) -> Vec<(Expression, Number)> {
+    let mut merged: Vec<(Expression, Number)> = Vec::new();
+    for (base, exponent) in factors {
+        match merged.iter_mut().find(|(other, _)| *other == base) {
+            Some((_, total)) => *total += exponent,
+            None => merged.push((base, exponent)),
+        }
+    }
+    merged
+}
+
+/// Multiplies the factors together, flattening nested products and pulling negations to the front
+fn product(factors: Vec<Expression>) -> Expression {
+    let mut negative = false;
+    let mut flattened = Vec::new();
+    for factor in factors {
+        flatten(factor, &mut flattened, &mut negative);
+    }
+    let product = match flattened.len() {
+        0 => num!(1),
+        1 => flattened.remove(0),
+        _ => Expression::Multiply(multiply::Multiply(flattened)),
+    };
+    match negative {
+        true => neg!(product),
+        false => product,
+    }
+}
+
+fn flatten(factor: Expression, factors: &mut Vec<Expression>, negative: &mut bool) {
+    match factor {
+        Expression::Multiply(multiply) => {
+            for child in multiply.0 {
+                flatten(child, factors, negative);
+            }
+        }
+        Expression::Negate(negate) => {
+            *negative = !*negative;
+            flatten(*negate.0, factors, negative);
+        }
+        other => factors.push(other),
+    }
+}
+
+fn is_zero(expression: Expression) -> bool {
+    expression.expand() == num!(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use crate::var;
+    use super::*;
+
+    fn factors(expression: &Expression) -> Vec<Expression> {
+        match expression {
+            Expression::Multiply(multiply) => multiply.0.clone(),
+            Expression::Negate(negate) => factors(&negate.0),
+            other => vec![other.clone()],
+        }
+    }
+
+    fn assert_same_value(a: &Expression, b: &Expression) {
+        for x in [-1.5, 0.3, 2.0] {
+            let variables = HashMap::from([("a", 1.5), ("b", -0.5), ("c", 2.5), ("x", x), ("y", 0.7)].map(|(k, v)| (k.to_string(), v)));
+            let (a_value, b_value) = (a.solve(Some(&variables)).unwrap(), b.solve(Some(&variables)).unwrap());
+            assert!((a_value - b_value).abs() < 1e-9, "{a} = {a_value}, {b} = {b_value}");
+        }
+    }
+
+    fn factor(input: &str) -> (Expression, Expression) {
+        let expression = Expression::from_str(input).unwrap();
+        let factored = expression.factor();
+        assert_same_value(&expression, &factored);
+        (expression, factored)
+    }
+
+    #[test]
+    fn test_common_factor() {
+        let (_, factored) = factor("a * c + b * c");
+        assert!(factors(&factored).contains(&var!("c")), "{factored:?}");
+        assert_eq!(factors(&factored).len(), 2);
+
+        let (_, factored) = factor("2 * x^3 * y + 4 * x^2");
+        assert!(factors(&factored).contains(&pow!(var!("x"), num!(2))), "{factored:?}");
+        assert!(factors(&factored).contains(&num!(2)), "{factored:?}");
+
+        let expression = neg!(var!("a") * var!("x")) - var!("a");
+        let factored = expression.factor();
+        assert_same_value(&expression, &factored);
+        assert!(matches!(factored, Expression::Negate(..)), "{factored:?}");
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let (_, factored) = factor("x^2 + 3 * x + 2");
+        assert_eq!(factors(&factored).len(), 2);
+
+        let (_, factored) = factor("x^4 - 1");
+        assert_eq!(factors(&factored).len(), 3);
+        assert!(factors(&factored).contains(&(pow!(var!("x"), num!(2)) + num!(1))), "{factored:?}");
+
+        let (_, factored) = factor("x^2 - 2 * x + 1");
+        assert_eq!(factored, pow!((var!("x") - num!(1)).simplify(), num!(2)));
+
+        let (_, factored) = factor("6 * x^3 + 3 * x^2 - 24 * x - 12");
+        assert_eq!(factors(&factored).len(), 4);
+        assert!(factors(&factored).contains(&num!(3)), "{factored:?}");
+
+        let (expression, factored) = factor("x^2 + 1");
+        assert_eq!(factored, expression.simplify());
+    }
+
+    #[test]
+    fn test_patterns() {
+        let (_, factored) = factor("x^2 - y^2");
+        assert_eq!(factors(&factored).len(), 2);
+
+        let (_, factored) = factor("4 * a^2 * b^2 - 9");
+        assert_eq!(factors(&factored).len(), 2);
+
+        let (_, factored) = factor("x^2 + 2 * x * y + y^2");
+        assert!(matches!(factored, Expression::Power(_, ref exponent) if **exponent == num!(2)), "{factored:?}");
+
+        let (_, factored) = factor("a^2 - 2 * a * b + b^2");
+        assert!(matches!(factored, Expression::Power(..)), "{factored:?}");
+    }
+
+    #[test]
+    fn test_nested() {
+        let (_, factored) = factor("sin(x^2 - 1) * (y * a + y * b)");
+        assert!(factors(&factored).contains(&var!("y")), "{factored:?}");
+    }
+}
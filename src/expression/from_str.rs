@@ -1,216 +1,598 @@
 use std::str::FromStr;
-use nom::{IResult, Parser};
-use nom::branch::alt;
-use nom::bytes::complete::{tag, take_while};
-use nom::character::complete::multispace0;
-use nom::combinator::map;
-use nom::error::{Error, ParseError};
-use nom::number::complete::double;
-use nom::sequence::{delimited, preceded, tuple};
-
-use Expression::*;
-
-use crate::{num, pow};
-use crate::expression::constant::Constant::*;
-use crate::expression::Expression;
+
+use crate::{factorial, log, modulo, neg, num, polygamma, pow, sqrt};
+use crate::expression::number::Number;
+use crate::expression::constant::Constant;
+use crate::expression::{Expression, Function};
+use crate::expression::error::ParseError;
+use crate::expression::from_str::lexer::{tokenize, Token};
+pub(crate) use crate::expression::from_str::lexer::TokenKind;
 use crate::expression::from_str::singletons::singletons;
 use crate::expression::from_str::trigonometry::trigonometry;
 
+mod condition;
+mod lexer;
 mod trigonometry;
 mod singletons;
-impl FromStr for Expression {
-    type Err = nom::Err<Error<String>>;
 
-    fn from_str(s: & str) -> Result<Self, Self::Err> {
-        let (remainder, expression) = expression(s)
-            .map_err(|err| err.map_input(|input| input.to_string()))?;
-        if remainder.is_empty() {
-            Ok(expression)
-        } else {
-            Err(nom::Err::Error(Error::new(remainder.to_string(), nom::error::ErrorKind::Eof)))
-        }
+/// Binding powers of the infix operators as (operator, left, right).
+/// A right power above the left makes an operator left-associative, below makes it right-associative.
+const INFIX_BINDING_POWERS: [(char, u8, u8); 5] = [
+    ('+', 1, 2),
+    ('-', 1, 2),
+    ('*', 3, 4),
+    ('/', 3, 4),
+    ('^', 8, 7),
+];
+
+/// Binding power of a unary sign or root: tighter than products but looser than powers, so -x^2 is -(x^2)
+const PREFIX_BINDING_POWER: u8 = 5;
+
+/// Binding power of the postfix factorial, tighter than everything else so -n! is -(n!) and 2^n! is 2^(n!)
+const POSTFIX_BINDING_POWER: u8 = 9;
+
+fn infix_binding_power(operator: char) -> Option<(char, u8, u8)> {
+    INFIX_BINDING_POWERS.into_iter().find(|(infix, _, _)| *infix == operator)
+}
+
+fn constant_or_variable(name: String, options: ParseOptions) -> Expression {
+    match Constant::from_name(&name, options.physical_constants) {
+        Some(constant) => Expression::Constant(constant),
+        None => Expression::Variable(name),
     }
 }
 
-fn singleton(input: &str) -> IResult<&str, Expression> {
-    alt((
-        bracketed,
-        trigonometry,
-        singletons,
-        constant,
-        number,
-        variable,
-    ))(input)
+/// Whether the name is a function of one argument that is not also a common symbol, like gamma for the Lorentz factor,
+/// so that an operand right after it, as in `sin x`, can only be meant as its argument
+fn is_function_name(name: &str) -> bool {
+    let numeric_base = name.strip_prefix("log_").is_some_and(|base| Number::from_str(base).is_ok());
+    numeric_base || matches!(name, "log" | "log2") || trigonometry(name, false).is_some()
+        || (singletons(name).is_some() && !matches!(name, "gamma" | "Γ"))
 }
 
-fn higher_than_power(input: &str) -> IResult<&str, Expression> {
-    alt((
-        singleton,
-    ))(input)
+/// What can start an operand, reported when one is missing
+const OPERAND: [&str; 3] = ["a number", "an identifier", "\"(\""];
+
+pub(crate) type ParseResult<T> = Result<T, ParseError>;
+
+/// Settings for parsing expressions and equations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reads juxtaposed operands as a product, like `2x`, `3(a + b)`, `2 pi r` or `x sin(x)`.
+    /// It binds like an explicit `*`, so `a / 2x` is `(a / 2) * x`.
+    /// Identifiers are never split, so `xy` and `rho_0` are single variables and `x y` is a product.
+    /// An identifier directly followed by a parenthesis is a function call, so `x * (a + b)` needs its `*`.
+    /// The right operand may not start with a number, so `x 2` is rejected while `2e3` is the number 2000.
+    pub implicit_multiplication: bool,
+    /// Reads the short symbols `c`, `G` and `h` as physical constants rather than variables. The other physical constants,
+    /// `c_0`, `G_N`, `h_P`, `ħ` or `hbar`, `k_B`, `N_A`, `q_e` for the elementary charge, `ε_0` or `epsilon_0`
+    /// and `μ_0` or `mu_0`, are always recognised. `e` stays Euler's number.
+    pub physical_constants: bool,
 }
 
-fn higher_than_multiplicative(input: &str) -> IResult<&str, Expression> {
-    alt((
-        power,
-        higher_than_power,
-    ))(input)
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions { implicit_multiplication: true, physical_constants: false }
+    }
 }
 
-fn higher_than_add(input: &str) -> IResult<&str, Expression> {
-    alt((
-        multiplicative,
-        higher_than_multiplicative,
-    ))(input)
+impl ParseOptions {
+    /// Only explicit operators, every product has to be written with `*`
+    pub fn strict() -> Self {
+        ParseOptions { implicit_multiplication: false, physical_constants: false }
+    }
 }
 
-pub(crate) fn expression(input: &str) -> IResult<&str, Expression> {
-    let (mut remaining_input, mut expression) = singleton(input)?;
-    if remaining_input.is_empty() {
-        return Ok((remaining_input, expression));
-    }
-
-    while let Ok((input, (operation, next_expression))) = alt((
-        tuple((ws(alt((tag("+"), tag("-")))), higher_than_add)),
-        tuple((ws(alt((tag("*"), tag("/")))), higher_than_multiplicative)),
-        tuple((ws(tag("^")), higher_than_power)),
-    ))(remaining_input) {
-        remaining_input = input;
-        expression = match operation {
-            "+" => expression + next_expression,
-            "-" => expression - next_expression,
-            "*" => expression * next_expression,
-            "/" => expression / next_expression,
-            "^" => pow!(expression, next_expression),
-            _ => unreachable!()
-        };
+impl FromStr for Expression {
+    type Err = ParseError;
+
+    fn from_str(s: & str) -> Result<Self, Self::Err> {
+        Expression::parse_with(s, ParseOptions::default())
     }
-    Ok((remaining_input, expression))
 }
 
-fn multiplicative(input: &str) -> IResult<&str, Expression> {
-    let (mut remaining_input, mut expression) = singleton(input)?;
-    if remaining_input.is_empty() {
-        return Ok((remaining_input, expression));
-    }
-
-    while let Ok((input, (operation, next_expression))) = alt((
-        tuple((ws(tag("*")), higher_than_multiplicative)),
-        tuple((ws(tag("/")), higher_than_multiplicative)),
-        tuple((ws(tag("^")), higher_than_power)),
-    ))(remaining_input) {
-        remaining_input = input;
-        expression = match operation {
-            "*" => expression * next_expression,
-            "/" => expression / next_expression,
-            "^" => pow!(expression, next_expression),
-            _ => unreachable!()
-        };
+impl Expression {
+    /// Parses the input with the given settings, `from_str` uses the defaults
+    pub fn parse_with(input: &str, options: ParseOptions) -> ParseResult<Expression> {
+        let mut parser = Parser::new(input, options)?;
+        let expression = parser.expression()?;
+        parser.end()?;
+        Ok(expression)
     }
-    Ok((remaining_input, expression))
 }
 
-fn power(input: &str) -> IResult<&str, Expression> {
-    let (mut remaining_input, mut expression) = singleton(input)?;
-    if remaining_input.is_empty() {
-        return Ok((remaining_input, expression));
+/// Recursive descent parser over the tokens of an input
+pub(crate) struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    options: ParseOptions,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(input: &'a str, options: ParseOptions) -> ParseResult<Self> {
+        Ok(Parser { input, tokens: tokenize(input)?, position: 0, options })
     }
-    while let Ok((input, next_expression)) = preceded(ws(tag("^")), higher_than_power)(remaining_input) {
-        remaining_input = input;
-        expression = pow!(expression, next_expression);
+
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
     }
-    Ok((remaining_input, expression))
-}
 
+    fn peek_operator(&self) -> Option<char> {
+        match self.peek() {
+            Some(TokenKind::Operator(operator)) => Some(*operator),
+            _ => None,
+        }
+    }
 
-fn constant(input: &str) -> IResult<&str, Expression> {
-    alt((
-        map(tag("pi"), |_| Constant(Pi)),
-        map(tag("e"), |_| Constant(E)),
-    ))(input)
-}
+    /// The infix operator that follows, which is a multiplication when another operand follows directly
+    fn peek_infix(&self) -> Option<(char, u8, u8)> {
+        match self.peek()? {
+            TokenKind::Operator('√') | TokenKind::Identifier(_) | TokenKind::LeftParen if self.options.implicit_multiplication => {
+                infix_binding_power('*')
+            }
+            TokenKind::Operator(operator) => infix_binding_power(*operator),
+            // A superscript exponent binds like ^
+            TokenKind::Superscript(_) => infix_binding_power('^'),
+            _ => None,
+        }
+    }
 
-fn variable(input: &str) -> IResult<&str, Expression> {
-    take_while(|c: char| c.is_alphanumeric() || c == '_')(input).map(|(input, variable)| (input, Variable(variable.to_string())))
-}
+    fn advance(&mut self) -> Option<TokenKind> {
+        let token = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some(token.kind.clone())
+    }
 
-fn number(input: &str) -> IResult<&str, Expression> {
-    double(input).map(|(input, number)| (input, num!(number)))
-}
+    /// An error at the current token, or at the end of the input when all tokens are consumed
+    fn error(&self, expected: &[&str]) -> ParseError {
+        match self.tokens.get(self.position) {
+            Some(token) => ParseError::new(self.input, token.span.start, expected, format!("unexpected {}", token.kind)),
+            None => ParseError::new(self.input, self.input.len(), expected, "unexpected end of input"),
+        }
+    }
 
-fn bracketed(input: &str) -> IResult<&str, Expression> {
-    delimited(ws(tag("(")), expression, ws(tag(")")))(input)
-}
+    pub(crate) fn expect(&mut self, expected: TokenKind) -> ParseResult<()> {
+        match self.peek() {
+            Some(kind) if *kind == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&[&expected.to_string()])),
+        }
+    }
+
+    pub(crate) fn end(&self) -> ParseResult<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error(&["an operator"])),
+        }
+    }
+
+    pub(crate) fn expression(&mut self) -> ParseResult<Expression> {
+        self.expression_binding(0)
+    }
+
+    /// Precedence climbing: parses operators until one binds less tightly than `minimum`
+    fn expression_binding(&mut self, minimum: u8) -> ParseResult<Expression> {
+        let mut expression = match self.peek_operator() {
+            Some(operator @ ('+' | '-' | '√')) => {
+                self.position += 1;
+                let inner = self.expression_binding(PREFIX_BINDING_POWER)?;
+                match operator {
+                    '+' => inner,
+                    '-' => neg!(inner),
+                    _ => sqrt!(inner),
+                }
+            }
+            _ => self.singleton()?,
+        };
+        loop {
+            if self.peek_operator() == Some('!') && POSTFIX_BINDING_POWER >= minimum {
+                expression = factorial!(expression);
+                self.position += 1;
+                continue;
+            }
+            let Some((operator, left, right)) = self.peek_infix() else {
+                break;
+            };
+            if left < minimum {
+                break;
+            }
+            if let Some(TokenKind::Superscript(exponent)) = self.peek() {
+                expression = pow!(expression, Expression::Number(exponent.clone()));
+                self.position += 1;
+                continue;
+            }
+            // An implicit multiplication has no token of its own
+            if self.peek_operator() == Some(operator) {
+                self.position += 1;
+            }
+            let next_expression = self.expression_binding(right)?;
+            expression = match operator {
+                '+' => expression + next_expression,
+                '-' => expression - next_expression,
+                '*' => expression * next_expression,
+                '/' => expression / next_expression,
+                '^' => pow!(expression, next_expression),
+                _ => unreachable!(),
+            };
+        }
+        Ok(expression)
+    }
+
+    fn singleton(&mut self) -> ParseResult<Expression> {
+        let error = self.error(&OPERAND);
+        match self.advance() {
+            Some(TokenKind::LeftParen) => {
+                let expression = self.expression()?;
+                self.expect(TokenKind::RightParen)?;
+                Ok(expression)
+            }
+            Some(TokenKind::Number(number)) => Ok(Expression::Number(number)),
+            Some(TokenKind::Identifier(name)) => self.identifier(name),
+            _ => Err(error),
+        }
+    }
+
+    /// Keywords are only recognized on whole identifiers, any other name is a variable
+    fn identifier(&mut self, name: String) -> ParseResult<Expression> {
+        if name == "if" && self.peek() == Some(&TokenKind::LeftParen) {
+            return self.piecewise();
+        }
+        if let Some(logarithm) = self.logarithm(&name)? {
+            return Ok(logarithm);
+        }
+        if let Some(function) = self.function(&name) {
+            self.expect(TokenKind::LeftParen)?;
+            let inside = self.expression()?;
+            self.expect(TokenKind::RightParen)?;
+            return Ok(function(inside));
+        }
+        if self.peek() == Some(&TokenKind::LeftParen) {
+            return self.call(name);
+        }
+        let operand_follows = matches!(self.peek(), Some(TokenKind::Identifier(_) | TokenKind::Number(_)));
+        if operand_follows && is_function_name(&name) {
+            return Err(self.error(&[&TokenKind::LeftParen.to_string()]));
+        }
+        Ok(constant_or_variable(name, self.options))
+    }
 
-pub fn ws<'a, F, O, E: ParseError<&'a str>>(
-    inner: F,
-) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
-    where F: Parser<&'a str, O, E>, {
-    delimited(multispace0, inner, multispace0)
+    /// A call with any number of comma separated arguments, of `min`, `max`, `mod`, `digamma` and `polygamma`
+    /// or else of a user-defined function
+    fn call(&mut self, name: String) -> ParseResult<Expression> {
+        let start = self.tokens[self.position - 1].span.start;
+        self.expect(TokenKind::LeftParen)?;
+        let mut args = Vec::new();
+        if self.peek() != Some(&TokenKind::RightParen) {
+            args.push(self.expression()?);
+            while self.peek() == Some(&TokenKind::Comma) {
+                self.position += 1;
+                args.push(self.expression()?);
+            }
+        }
+        self.expect(TokenKind::RightParen)?;
+        let arguments_error = |message: &str| Err(ParseError::new(self.input, start, &[], format!("{name} {message}")));
+        match (name.as_str(), args.len()) {
+            ("min", 1..) => Ok(Expression::Min(args)),
+            ("max", 1..) => Ok(Expression::Max(args)),
+            ("mod", 2) => Ok(modulo!(args[0].clone(), args[1].clone())),
+            ("digamma", 1) => Ok(polygamma!(num!(0), args[0].clone())),
+            ("polygamma", 2) => Ok(polygamma!(args[0].clone(), args[1].clone())),
+            ("min" | "max", _) => arguments_error("needs at least one argument"),
+            ("mod" | "polygamma", _) => arguments_error("takes two arguments"),
+            ("digamma", _) => arguments_error("takes one argument"),
+            _ => Ok(Expression::Function(Function { name, args })),
+        }
+    }
+
+    /// `log(x)` in base 10, `log2(x)`, `log_b(x)`, `log_(b)(x)` and `log(x, b)`, or `None` for any other identifier
+    fn logarithm(&mut self, name: &str) -> ParseResult<Option<Expression>> {
+        let base = match name {
+            "log_" => self.singleton()?,
+            _ if self.peek() != Some(&TokenKind::LeftParen) => return Ok(None),
+            "log" => num!(10),
+            "log2" => num!(2),
+            _ => match name.strip_prefix("log_") {
+                Some(base) => match Number::from_str(base) {
+                    Ok(number) => Expression::Number(number),
+                    Err(_) => constant_or_variable(base.to_string(), self.options),
+                },
+                None => return Ok(None),
+            },
+        };
+        self.expect(TokenKind::LeftParen)?;
+        let argument = self.expression()?;
+        let base = match (name, self.peek()) {
+            ("log", Some(TokenKind::Comma)) => {
+                self.position += 1;
+                self.expression()?
+            }
+            _ => base,
+        };
+        self.expect(TokenKind::RightParen)?;
+        Ok(Some(log!(argument, base)))
+    }
+
+    /// The function called by the identifier, consuming a `^-1` suffix that marks an inverse
+    fn function(&mut self, name: &str) -> Option<fn(Expression) -> Expression> {
+        let inverse_suffix = [TokenKind::Operator('^'), TokenKind::Operator('-'), TokenKind::Number(1.into()), TokenKind::LeftParen];
+        let is_inverse = self.tokens.get(self.position..self.position + 4)
+            .is_some_and(|tokens| tokens.iter().map(|token| &token.kind).eq(inverse_suffix.iter()));
+        if is_inverse {
+            let function = trigonometry(name, true)?;
+            self.position += 3;
+            return Some(function);
+        }
+        if self.peek() != Some(&TokenKind::LeftParen) {
+            return None;
+        }
+        trigonometry(name, false).or_else(|| singletons(name))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{mul, num, pow, var};
+    use std::collections::HashMap;
+    use crate::expression::Condition;
+    use crate::{acos, acosh, asinh, atanh, call, ceil, compare, cos, cosh, erf, erfc, exp, floor, gamma, ln, max, min, mul, neg, piecewise, polygamma, pow, round, sign, sin, sqrt, var};
 
     use super::*;
 
+    fn parse(input: &str) -> Expression {
+        Expression::from_str(input).unwrap()
+    }
+
     #[test]
     fn test_constant() {
-        assert_eq!(constant("pi"), Ok(("", Constant(Pi))));
-        assert_eq!(constant("e"), Ok(("", Constant(E))));
+        assert_eq!(parse("pi"), Expression::Constant(Constant::Pi));
+        assert_eq!(parse("e"), Expression::Constant(Constant::E));
+        assert_eq!(parse("π"), Expression::Constant(Constant::Pi));
+        assert_eq!(parse("2 phi γ"), num!(2) * Expression::Constant(Constant::GoldenRatio) * Expression::Constant(Constant::EulerGamma));
+        assert_eq!(parse("2 tau").simplify(), num!(2) * Expression::Constant(Constant::Tau));
+
+        let physical = ParseOptions { physical_constants: true, ..ParseOptions::default() };
+        assert_eq!(parse("m c^2"), var!("m") * pow!(var!("c"), num!(2)));
+        assert_eq!(
+            Expression::parse_with("m c^2", physical).unwrap(),
+            var!("m") * pow!(Expression::Constant(Constant::SpeedOfLight), num!(2))
+        );
+        assert_eq!(Expression::parse_with("ħ / k_B", physical).unwrap().to_string(), "ħ / k_B");
+        assert_eq!(Expression::parse_with("ε₀", physical).unwrap(), Expression::Constant(Constant::VacuumPermittivity));
+
+        assert_eq!(parse("ħ / k_B"), Expression::Constant(Constant::ReducedPlanck) / Expression::Constant(Constant::Boltzmann));
+        assert_eq!(parse("m c_0^2"), var!("m") * pow!(Expression::Constant(Constant::SpeedOfLight), num!(2)));
     }
 
     #[test]
     fn test_number() {
-        assert_eq!(number("1"), Ok(("", num!(1.0))));
-        assert_eq!(number("1.0"), Ok(("", num!(1.0))));
-        assert_eq!(number("1.0e-1"), Ok(("", num!(0.1))));
+        assert_eq!(parse("1"), num!(1.0));
+        assert_eq!(parse("1.0"), num!(1.0));
+        assert_eq!(parse("1.0e-1"), num!(0.1));
+        assert_eq!(parse("-1"), neg!(num!(1)));
+        assert_eq!(parse("1.5e-2147483648"), Expression::Number(Number::Float(0.0)));
+        assert_eq!(parse("1e99999999999"), num!(f64::INFINITY));
     }
 
     #[test]
     fn test_add() {
-        assert_eq!(expression("1 + 2"), Ok(("", num!(1.0) + num!(2.0))));
-        assert_eq!(expression("1 + 2 + 3"), Ok(("", num!(1.0) + num!(2.0) + num!(3.0))));
-        assert_eq!(expression("1 + 2 + 3 + 4"), Ok(("", num!(1.0) + num!(2.0) + num!(3.0) + num!(4.0))));
-        assert_eq!(expression("1 + 2 - 3"), Ok(("", num!(1.0) + num!(2.0) - num!(3.0))));
-        assert_eq!(expression("1 - 2 + 3"), Ok(("", num!(1.0) - num!(2.0) + num!(3.0))));
+        assert_eq!(parse("1 + 2"), num!(1.0) + num!(2.0));
+        assert_eq!(parse("1 + 2 + 3"), num!(1.0) + num!(2.0) + num!(3.0));
+        assert_eq!(parse("1 + 2 + 3 + 4"), num!(1.0) + num!(2.0) + num!(3.0) + num!(4.0));
+        assert_eq!(parse("1 + 2 - 3"), num!(1.0) + num!(2.0) - num!(3.0));
+        assert_eq!(parse("1 - 2 + 3"), num!(1.0) - num!(2.0) + num!(3.0));
     }
 
     #[test]
     fn test_multiplication() {
-        assert_eq!(expression("1 * 2"), Ok(("", num!(1.0) * num!(2.0))));
-        assert_eq!(expression("1 * 2 * 3"), Ok(("", num!(1.0) * num!(2.0) * num!(3.0))));
-        assert_eq!(expression("1 * 2 * 3 * 4"), Ok(("", num!(1.0) * num!(2.0) * num!(3.0) * num!(4.0))));
-        assert_eq!(expression("1 * 2 / 3"), Ok(("", num!(1.0) * num!(2.0) / num!(3.0))));
-        assert_eq!(expression("1 / 2 * 3"), Ok(("", num!(1.0) / num!(2.0) * num!(3.0))));
+        assert_eq!(parse("1 * 2"), num!(1.0) * num!(2.0));
+        assert_eq!(parse("1 * 2 * 3"), num!(1.0) * num!(2.0) * num!(3.0));
+        assert_eq!(parse("1 * 2 * 3 * 4"), num!(1.0) * num!(2.0) * num!(3.0) * num!(4.0));
+        assert_eq!(parse("1 * 2 / 3"), num!(1.0) * num!(2.0) / num!(3.0));
+        assert_eq!(parse("1 / 2 * 3"), num!(1.0) / num!(2.0) * num!(3.0));
     }
 
     #[test]
     fn test_combinations() {
-        assert_eq!(expression("1 + 2 * 3"), Ok(("", num!(1.0) + num!(2.0) * num!(3.0))));
-        assert_eq!(expression("1 * 2 + 3"), Ok(("", mul!(num!(1.0), num!(2.0)) + num!(3.0))));
-        assert_eq!(expression("1 * 2 + 3 * 4"), Ok(("", num!(1.0) * num!(2.0) + num!(3.0) * num!(4.0))));
-        assert_eq!(expression("1 + 2 / 3"), Ok(("", num!(1.0) + num!(2.0) / num!(3.0))));
-        assert_eq!(expression("1 / 2 + 3"), Ok(("", num!(1.0) / num!(2.0) + num!(3.0))));
+        assert_eq!(parse("1 + 2 * 3"), num!(1.0) + num!(2.0) * num!(3.0));
+        assert_eq!(parse("1 * 2 + 3"), mul!(num!(1.0), num!(2.0)) + num!(3.0));
+        assert_eq!(parse("1 * 2 + 3 * 4"), num!(1.0) * num!(2.0) + num!(3.0) * num!(4.0));
+        assert_eq!(parse("1 + 2 / 3"), num!(1.0) + num!(2.0) / num!(3.0));
+        assert_eq!(parse("1 / 2 + 3"), num!(1.0) / num!(2.0) + num!(3.0));
     }
 
     #[test]
     fn test_power() {
-        assert_eq!(expression("1 ^ 2"), Ok(("", pow!(num!(1.0), num!(2.0)))));
-        assert_eq!(expression("1 ^ 2 ^ 3"), Ok(("", pow!(pow!(num!(1.0), num!(2.0)), num!(3.0)))));
-        assert_eq!(expression("1 ^ 2 ^ 3 ^ 4"), Ok(("", pow!(pow!(pow!(num!(1.0), num!(2.0)), num!(3.0)), num!(4.0)))));
-        assert_eq!(expression("1 ^ 2 * 3"), Ok(("", pow!(num!(1.0) , num!(2.0)) * num!(3.0))));
-        assert_eq!(expression("1 * 2 ^ 3"), Ok(("", num!(1.0) * pow!(num!(2.0), num!(3.0)))));
+        assert_eq!(parse("1 ^ 2"), pow!(num!(1.0), num!(2.0)));
+        assert_eq!(parse("1 ^ 2 ^ 3"), pow!(num!(1.0), pow!(num!(2.0), num!(3.0))));
+        assert_eq!(parse("1 ^ 2 ^ 3 ^ 4"), pow!(num!(1.0), pow!(num!(2.0), pow!(num!(3.0), num!(4.0)))));
+        assert_eq!(parse("(1 ^ 2) ^ 3"), pow!(pow!(num!(1.0), num!(2.0)), num!(3.0)));
+        assert_eq!(parse("1 ^ 2 * 3"), pow!(num!(1.0) , num!(2.0)) * num!(3.0));
+        assert_eq!(parse("1 * 2 ^ 3"), num!(1.0) * pow!(num!(2.0), num!(3.0)));
     }
 
     #[test]
-    fn complex_stress() {
+    fn test_negation() {
+        assert_eq!(parse("-x"), neg!(var!("x")));
+        assert_eq!(parse("-3 ^ 2"), neg!(pow!(num!(3), num!(2))));
+        assert_eq!(parse("2 * -x"), num!(2) * neg!(var!("x")));
+        assert_eq!(parse("1 - -x"), num!(1) - neg!(var!("x")));
+        assert_eq!(parse("-(a + b)"), neg!(var!("a") + var!("b")));
+        assert_eq!(parse("-a * b"), neg!(var!("a")) * var!("b"));
+        assert_eq!(parse("+a - +b"), var!("a") - var!("b"));
+        assert_eq!(parse("2 ^ -x"), pow!(num!(2), neg!(var!("x"))));
+        assert_eq!(parse("2 ^ -3 ^ 2"), pow!(num!(2), neg!(pow!(num!(3), num!(2)))));
+    }
+
+    #[test]
+    fn test_logarithm() {
+        assert_eq!(parse("log(x)"), log!(var!("x"), num!(10)));
+        assert_eq!(parse("log2(x)"), log!(var!("x"), num!(2)));
+        assert_eq!(parse("log_3(x)"), log!(var!("x"), num!(3)));
+        assert_eq!(parse("log_b(x)"), log!(var!("x"), var!("b")));
+        assert_eq!(parse("log_e(x)"), log!(var!("x"), Expression::Constant(Constant::E)));
+        assert_eq!(parse("log_(b + 1)(x)"), log!(var!("x"), var!("b") + num!(1)));
+        assert_eq!(parse("log(x + 1, b)"), log!(var!("x") + num!(1), var!("b")));
+        assert_eq!(parse("log_b"), var!("log_b"));
+        assert_eq!(parse("exp(x) * ln(x)"), exp!(var!("x")) * ln!(var!("x")));
+        assert_eq!(parse("exp(x)"), pow!(Expression::Constant(Constant::E), var!("x")));
+        assert!(Expression::from_str("log_b(x, c)").is_err());
+    }
+
+    #[test]
+    fn test_call() {
+        assert_eq!(parse("f(x, 2 y) + g()"), call!("f", var!("x"), num!(2) * var!("y")) + call!("g"));
+        assert_eq!(parse("f'(x)"), call!("f'", var!("x")));
+        assert_eq!(parse("f(x)^2"), pow!(call!("f", var!("x")), num!(2)));
+        assert!(Expression::from_str("f(x,)").is_err());
+    }
+
+    #[test]
+    fn test_special_functions() {
+        assert_eq!(parse("n!"), factorial!(var!("n")));
+        assert_eq!(parse("-n!"), neg!(factorial!(var!("n"))));
+        assert_eq!(parse("2^n!"), pow!(num!(2), factorial!(var!("n"))));
+        assert_eq!(parse("(n + 1)! / n!"), factorial!(var!("n") + num!(1)) / factorial!(var!("n")));
+        assert_eq!(parse("3!!"), factorial!(factorial!(num!(3))));
+        assert_eq!(parse("n! x"), factorial!(var!("n")) * var!("x"));
+        assert_eq!(parse("floor(x) + ceil(x) - round(x) * sgn(x)"), floor!(var!("x")) + ceil!(var!("x")) - round!(var!("x")) * sign!(var!("x")));
+        assert_eq!(parse("gamma(x) * Γ(x) + erf(x) - erfc(x)"), gamma!(var!("x")) * gamma!(var!("x")) + erf!(var!("x")) - erfc!(var!("x")));
+        assert_eq!(parse("digamma(x) + polygamma(2, x)"), polygamma!(num!(0), var!("x")) + polygamma!(num!(2), var!("x")));
+        assert_eq!(Expression::from_str("polygamma(x)").unwrap_err().message, "polygamma takes two arguments");
+        assert_eq!(parse("min(x, 1, y) + max(x)"), min!(var!("x"), num!(1), var!("y")) + max!(var!("x")));
+        assert_eq!(parse("mod(x, 3)"), modulo!(var!("x"), num!(3)));
+        assert_eq!(Expression::from_str("min()").unwrap_err().message, "min needs at least one argument");
+        assert_eq!(Expression::from_str("1 + mod(x)").unwrap_err().offset, 4);
+        assert_eq!(parse("5!").solve(None), Ok(120.0));
+        assert_eq!(parse("mod(-7, 3) + max(1, 4, 2) + min(2)").solve(None), Ok(8.0));
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let (x, v, c) = (|| var!("x"), || var!("v"), || var!("c"));
+        assert_eq!(parse("if(x < 0, -x, x)"), piecewise!((compare!(x(), <, num!(0)), neg!(x())); x()));
+        assert_eq!(parse("if(v < c, a, v <= 2c, b, 0)"), piecewise!(
+            (compare!(v(), <, c()), var!("a")),
+            (compare!(v(), <=, num!(2) * c()), var!("b"));
+            num!(0)
+        ));
+        assert_eq!(parse("if(0 < x ≤ 1 && !(v == 2), 1, 0)"), piecewise!((Condition::And(vec![
+            compare!(num!(0), <, x()),
+            compare!(x(), <=, num!(1)),
+            Condition::Not(Box::new(compare!(v(), =, num!(2)))),
+        ]), num!(1)); num!(0)));
+        assert_eq!(parse("if((x + 1) * 2 > v || (x >= 1 && v != 0), 1, 0)"), piecewise!((Condition::Or(vec![
+            compare!((x() + num!(1)) * num!(2), >, v()),
+            Condition::And(vec![compare!(x(), >=, num!(1)), compare!(v(), !=, num!(0))]),
+        ]), num!(1)); num!(0)));
+        assert_eq!(parse("if(true, 1, 2)"), piecewise!((Condition::Bool(true), num!(1)); num!(2)));
+        assert_eq!(parse("if"), var!("if"));
+
+        let saturating = parse("if(v < c, v, c) * 2");
+        assert_eq!(parse(&saturating.to_string()), saturating);
+        let variables = HashMap::from([("v".to_string(), 3.0), ("c".to_string(), 2.0)]);
+        assert_eq!(saturating.solve(Some(&variables)), Ok(4.0));
+
+        assert_eq!(Expression::from_str("if(x, 1, 2)").unwrap_err().expected, vec!["a comparison"]);
+        let error = Expression::from_str("if(x < 1, 2, x > 1, 3)").unwrap_err();
+        assert_eq!((error.offset, error.expected.as_slice()), (21, ["\",\"".to_string()].as_slice()));
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(parse("2π r"), num!(2) * Expression::Constant(Constant::Pi) * var!("r"));
+        assert_eq!(parse("τ"), Expression::Constant(Constant::Tau));
+        assert_eq!(parse("√x"), sqrt!(var!("x")));
+        assert_eq!(parse("√(x + 1) · 2"), sqrt!(var!("x") + num!(1)) * num!(2));
+        assert_eq!(parse("a√b"), var!("a") * sqrt!(var!("b")));
+        assert_eq!(parse("a × b ÷ c − d"), var!("a") * var!("b") / var!("c") - var!("d"));
+        assert_eq!(parse("x²"), pow!(var!("x"), num!(2)));
+        assert_eq!(parse("2x⁻¹"), num!(2) * pow!(var!("x"), num!(-1)));
+        assert_eq!(parse("−x²"), neg!(pow!(var!("x"), num!(2))));
+        assert_eq!(parse("(a + b)²³"), pow!(var!("a") + var!("b"), num!(23)));
+        assert_eq!(parse("ρ₀ g h"), var!("ρ_0") * var!("g") * var!("h"));
+        assert_eq!(parse("ρ₀"), parse("ρ_0"));
+        assert_eq!(parse("sin(θ)²"), pow!(sin!(var!("θ")), num!(2)));
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        assert_eq!(parse("2x"), num!(2) * var!("x"));
+        assert_eq!(parse("3(a+b)"), num!(3) * (var!("a") + var!("b")));
+        assert_eq!(parse("(x+1)(x-1)"), (var!("x") + num!(1)) * (var!("x") - num!(1)));
+        assert_eq!(parse("2 pi r"), num!(2) * Expression::Constant(Constant::Pi) * var!("r"));
+        assert_eq!(parse("x sin(x)"), var!("x") * sin!(var!("x")));
+        assert_eq!(parse("2x^2"), num!(2) * pow!(var!("x"), num!(2)));
+        assert_eq!(parse("a / 2x"), var!("a") / num!(2) * var!("x"));
+        assert_eq!(parse("rho_0 g h"), var!("rho_0") * var!("g") * var!("h"));
+        assert_eq!(parse("xy"), var!("xy"));
+        assert_eq!(parse("2e3x"), num!(2000) * var!("x"));
+        assert!(Expression::from_str("x 2").is_err());
+
+        assert!(Expression::parse_with("2x", ParseOptions::strict()).is_err());
+        assert!(Expression::parse_with("(x+1)(x-1)", ParseOptions::strict()).is_err());
+        assert_eq!(Expression::parse_with("2 * x", ParseOptions::strict()), Ok(num!(2) * var!("x")));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = Expression::from_str("2 * (x + )").unwrap_err();
+        assert_eq!((error.offset, error.column()), (9, 10));
+        assert_eq!(error.expected, vec!["a number", "an identifier", "\"(\""]);
+        assert_eq!(error.to_string(), "unexpected \")\" at line 1, column 10, expected a number, an identifier or \"(\"\n2 * (x + )\n         ^");
+
+        let error = Expression::from_str("sqrt(π").unwrap_err();
+        assert_eq!((error.offset, error.column()), (7, 7));
+        assert_eq!(error.message, "unexpected end of input");
+        assert_eq!(error.expected, vec!["\")\""]);
+
+        let error = Expression::parse_with("2 x", ParseOptions::strict()).unwrap_err();
+        assert_eq!(error.message, "unexpected identifier \"x\"");
         assert_eq!(
-            expression("A * (E_0/rho_0)^(1/5) * t^(2/5)"),
-            Ok((
-                "",
-                var!("A") * pow!(var!("E_0") / var!("rho_0"), num!(1.0) / num!(5.0)) * pow!(var!("t"), num!(2.0) / num!(5.0))
-            ))
+            Expression::from_str("a +\nb $").unwrap_err().to_string(),
+            "unexpected character '$' at line 2, column 3, expected a number, an identifier, an operator, a parenthesis or a comma\nb $\n  ^"
         );
     }
+
+    #[test]
+    fn test_binding_powers() {
+        let power = |operator| infix_binding_power(operator).unwrap();
+        // Sums bind looser than products, which bind looser than a sign, which binds looser than powers
+        assert!(power('+').1 < power('*').1 && power('*').2 < PREFIX_BINDING_POWER && PREFIX_BINDING_POWER < power('^').1);
+        // Every operator is left-associative except for the right-associative power
+        for (operator, left, right) in INFIX_BINDING_POWERS {
+            assert_eq!(left > right, operator == '^');
+        }
+        assert!(POSTFIX_BINDING_POWER > power('^').1);
+        assert_eq!(infix_binding_power('='), None);
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(parse("energy * e"), var!("energy") * Expression::Constant(Constant::E));
+        assert_eq!(parse("exp_rate + pi_0"), var!("exp_rate") + var!("pi_0"));
+        assert_eq!(parse("cosine"), var!("cosine"));
+        assert_eq!(parse("cos(x) * sqrt(cost)"), cos!(var!("x")) * sqrt!(var!("cost")));
+        assert_eq!(parse("cos^-1(x)"), acos!(var!("x")));
+        assert_eq!(parse("cosh(x) - arsinh(y) * tanh^-1(z)"), cosh!(var!("x")) - asinh!(var!("y")) * atanh!(var!("z")));
+        assert_eq!(parse(&acosh!(var!("x")).to_string()), acosh!(var!("x")));
+        assert!(Expression::from_str("x +").is_err());
+        assert!(Expression::from_str("(x").is_err());
+        for input in ["sin x", "ln 2", "log x", "log_2 8", "2 sqrt x"] {
+            let error = Expression::from_str(input).unwrap_err();
+            assert_eq!(error.expected, vec!["\"(\""], "{input}");
+        }
+        // Names that can only be read as variables stay variables
+        assert_eq!(parse("gamma m c_0^2"), var!("gamma") * var!("m") * pow!(Expression::Constant(Constant::SpeedOfLight), num!(2)));
+        assert_eq!(parse("km/min"), var!("km") / var!("min"));
+        assert_eq!(parse("max + sin"), var!("max") + var!("sin"));
+    }
+
+    #[test]
+    fn complex_stress() {
+        assert_eq!(
+            parse("A * (E_0/rho_0)^(1/5) * t^(2/5)"),
+            var!("A") * pow!(var!("E_0") / var!("rho_0"), num!(1.0) / num!(5.0)) * pow!(var!("t"), num!(2.0) / num!(5.0)));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use crate::expression::error::ParseError;
+use crate::expression::from_str::{ParseOptions, ParseResult, Parser, TokenKind};
+use crate::expression::{Comparison, Condition, Expression};
+
+impl FromStr for Condition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Condition::parse_with(s, ParseOptions::default())
+    }
+}
+
+impl Condition {
+    /// Parses the input with the given settings, `from_str` uses the defaults
+    pub fn parse_with(input: &str, options: ParseOptions) -> ParseResult<Condition> {
+        let mut parser = Parser::new(input, options)?;
+        let condition = parser.condition()?;
+        parser.end()?;
+        Ok(condition)
+    }
+}
+
+impl Parser<'_> {
+    /// `if(c1, v1, c2, v2, ..., otherwise)` after the `if`, with at least one condition
+    pub(super) fn piecewise(&mut self) -> ParseResult<Expression> {
+        self.expect(TokenKind::LeftParen)?;
+        let mut branches = vec![self.branch()?];
+        loop {
+            let start = self.position;
+            let branch_error = match self.branch() {
+                Ok(branch) => {
+                    branches.push(branch);
+                    continue;
+                }
+                Err(error) => error,
+            };
+            self.position = start;
+            let otherwise = self.expression().and_then(|otherwise| self.expect(TokenKind::RightParen).map(|_| otherwise));
+            return match otherwise {
+                Ok(otherwise) => Ok(Expression::Piecewise(branches, Box::new(otherwise))),
+                // Report whichever reading got further
+                Err(error) if error.offset >= branch_error.offset => Err(error),
+                Err(_) => Err(branch_error),
+            };
+        }
+    }
+
+    /// A condition and the value it selects, each followed by a comma
+    fn branch(&mut self) -> ParseResult<(Condition, Expression)> {
+        let condition = self.condition()?;
+        self.expect(TokenKind::Comma)?;
+        let value = self.expression()?;
+        self.expect(TokenKind::Comma)?;
+        Ok((condition, value))
+    }
+
+    /// Conditions joined by `||`, which binds looser than `&&`, which binds looser than `!`
+    pub(crate) fn condition(&mut self) -> ParseResult<Condition> {
+        let mut operands = vec![self.conjunction()?];
+        while self.peek_operator() == Some('∨') {
+            self.position += 1;
+            operands.push(self.conjunction()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { Condition::Or(operands) })
+    }
+
+    fn conjunction(&mut self) -> ParseResult<Condition> {
+        let mut operands = Vec::new();
+        loop {
+            // Chained comparisons are conjunctions already
+            match self.negation()? {
+                Condition::And(chain) => operands.extend(chain),
+                operand => operands.push(operand),
+            }
+            if self.peek_operator() != Some('∧') {
+                break;
+            }
+            self.position += 1;
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { Condition::And(operands) })
+    }
+
+    fn negation(&mut self) -> ParseResult<Condition> {
+        let ends_condition = |kind: Option<&TokenKind>| {
+            matches!(kind, None | Some(TokenKind::Comma | TokenKind::RightParen | TokenKind::Operator('∧' | '∨')))
+        };
+        match self.peek() {
+            Some(TokenKind::Operator('!' | '¬')) => {
+                self.position += 1;
+                Ok(Condition::Not(Box::new(self.negation()?)))
+            }
+            // A parenthesis opens either a condition, like in (a < b || c) && d, or an expression, like in (a + b) < c
+            Some(TokenKind::LeftParen) => {
+                let start = self.position;
+                self.position += 1;
+                if let Ok(condition) = self.condition() {
+                    if self.peek() == Some(&TokenKind::RightParen) {
+                        self.position += 1;
+                        return Ok(condition);
+                    }
+                }
+                self.position = start;
+                self.comparison()
+            }
+            Some(TokenKind::Identifier(name)) if matches!(name.as_str(), "true" | "false")
+                && ends_condition(self.tokens.get(self.position + 1).map(|token| &token.kind)) => {
+                let value = name == "true";
+                self.position += 1;
+                Ok(Condition::Bool(value))
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    /// Expressions separated by comparisons, where a chain like `0 < x <= 1` compares each neighbouring pair
+    fn comparison(&mut self) -> ParseResult<Condition> {
+        let mut left = self.expression()?;
+        let mut comparisons = Vec::new();
+        while let Some(comparison) = self.peek_operator().and_then(Comparison::from_operator) {
+            self.position += 1;
+            let right = self.expression()?;
+            comparisons.push(Condition::Compare(left, comparison, right.clone()));
+            left = right;
+        }
+        match comparisons.len() {
+            0 => Err(self.error(&["a comparison"])),
+            1 => Ok(comparisons.remove(0)),
+            _ => Ok(Condition::And(comparisons)),
+        }
+    }
+
+    /// One of the `allowed` comparison operators, like the one between the sides of an inequality
+    pub(crate) fn comparator(&mut self, allowed: &[Comparison]) -> ParseResult<Comparison> {
+        match self.peek_operator().and_then(Comparison::from_operator) {
+            Some(comparison) if allowed.contains(&comparison) => {
+                self.position += 1;
+                Ok(comparison)
+            }
+            _ => {
+                let expected: Vec<String> = allowed.iter().map(|comparison| format!("\"{comparison}\"")).collect();
+                Err(self.error(&expected.iter().map(String::as_str).collect::<Vec<&str>>()))
+            }
+        }
+    }
+}
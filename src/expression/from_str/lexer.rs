@@ -0,0 +1,192 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+
+use nom::branch::alt;
+use nom::bytes::complete::{take_while, take_while1};
+use nom::character::complete::{char, digit0, digit1, one_of, satisfy};
+use nom::combinator::{opt, recognize};
+use nom::error::{Error, ErrorKind};
+use nom::sequence::{pair, tuple};
+use nom::IResult;
+
+use crate::expression::error::ParseError;
+use crate::expression::number::Number;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    Identifier(String),
+    Number(Number),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+    /// A superscript exponent like the ² in x², with its sign
+    Superscript(Number),
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => write!(f, "identifier \"{name}\""),
+            TokenKind::Number(number) => write!(f, "number {number}"),
+            TokenKind::Operator(operator) => write!(f, "\"{operator}\""),
+            TokenKind::LeftParen => write!(f, "\"(\""),
+            TokenKind::RightParen => write!(f, "\")\""),
+            TokenKind::Comma => write!(f, "\",\""),
+            TokenKind::Superscript(exponent) => write!(f, "exponent {exponent}"),
+        }
+    }
+}
+
+/// A token together with the byte range of the input it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+const OPERATORS: &str = "+-*/^=√!<>≤≥≠∧∨¬";
+
+/// Typographic operators and the ASCII operators they stand for
+const OPERATOR_ALIASES: [(char, char); 5] = [('×', '*'), ('·', '*'), ('⋅', '*'), ('÷', '/'), ('−', '-')];
+
+/// Operators written with two characters and the single character operators they stand for
+const DIGRAPHS: [(&str, char); 6] = [("<=", '≤'), (">=", '≥'), ("!=", '≠'), ("==", '='), ("&&", '∧'), ("||", '∨')];
+
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+const SUBSCRIPT_DIGITS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+
+/// Splits the input into tokens, skipping whitespace
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut remaining = input.trim_start();
+    while !remaining.is_empty() {
+        let start = input.len() - remaining.len();
+        let (rest, kind) = token(remaining).map_err(|_| {
+            let character = remaining.chars().next().unwrap_or_default();
+            ParseError::new(input, start, &["a number", "an identifier", "an operator", "a parenthesis", "a comma"], format!("unexpected character '{character}'"))
+        })?;
+        tokens.push(Token { kind, span: start..input.len() - rest.len() });
+        remaining = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn token(input: &str) -> IResult<&str, TokenKind> {
+    alt((
+        number,
+        identifier,
+        superscript,
+        digraph,
+        operator,
+    ))(input)
+}
+
+/// An identifier is read as a whole, so names like `energy` are never split into a constant and a rest.
+/// Primes are part of the name, like in the derivative `f'`, and subscript digits are read as `_` and digits,
+/// so `ρ₀` is the same variable as `ρ_0`.
+fn identifier(input: &str) -> IResult<&str, TokenKind> {
+    let (rest, name) = recognize(pair(
+        satisfy(|c| c.is_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_alphabetic() || c.is_ascii_digit() || c == '_' || c == '\'' || SUBSCRIPT_DIGITS.contains(&c)),
+    ))(input)?;
+    let mut normalized = String::new();
+    for c in name.chars() {
+        match SUBSCRIPT_DIGITS.iter().position(|digit| *digit == c) {
+            Some(digit) => {
+                if !normalized.ends_with(|c: char| c == '_' || c.is_ascii_digit()) {
+                    normalized.push('_');
+                }
+                normalized.push(char::from(b'0' + digit as u8));
+            }
+            None => normalized.push(c),
+        }
+    }
+    Ok((rest, TokenKind::Identifier(normalized)))
+}
+
+/// Superscript exponent like `²` or `⁻¹`
+fn superscript(input: &str) -> IResult<&str, TokenKind> {
+    let (rest, (sign, digits)) = pair(
+        opt(one_of("⁺⁻")),
+        take_while1(|c: char| SUPERSCRIPT_DIGITS.contains(&c)),
+    )(input)?;
+    let digits: String = digits.chars()
+        .filter_map(|c| SUPERSCRIPT_DIGITS.iter().position(|digit| *digit == c))
+        .map(|digit| char::from(b'0' + digit as u8))
+        .collect();
+    let exponent = Number::from_str(&digits)
+        .map_err(|_| nom::Err::Error(Error::new(input, ErrorKind::Digit)))?;
+    Ok((rest, TokenKind::Superscript(if sign == Some('⁻') { -exponent } else { exponent })))
+}
+
+/// Unsigned decimal literal like `12`, `.5` or `1.5e-3`; signs are operators
+fn number(input: &str) -> IResult<&str, TokenKind> {
+    let (rest, literal) = recognize(tuple((
+        alt((
+            recognize(pair(digit1, opt(pair(char('.'), digit0)))),
+            recognize(pair(char('.'), digit1)),
+        )),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+    )))(input)?;
+    let number = Number::from_str(literal)
+        .map_err(|_| nom::Err::Error(Error::new(input, ErrorKind::Float)))?;
+    Ok((rest, TokenKind::Number(number)))
+}
+
+fn digraph(input: &str) -> IResult<&str, TokenKind> {
+    match DIGRAPHS.iter().find(|(digraph, _)| input.starts_with(digraph)) {
+        Some((digraph, operator)) => Ok((&input[digraph.len()..], TokenKind::Operator(*operator))),
+        None => Err(nom::Err::Error(Error::new(input, ErrorKind::Tag))),
+    }
+}
+
+fn operator(input: &str) -> IResult<&str, TokenKind> {
+    let (rest, c) = one_of(OPERATORS)(input)
+        .or_else(|_: nom::Err<Error<&str>>| one_of("(),")(input))
+        .or_else(|_: nom::Err<Error<&str>>| satisfy(|c| OPERATOR_ALIASES.iter().any(|(alias, _)| *alias == c))(input))?;
+    let c = OPERATOR_ALIASES.iter().find(|(alias, _)| *alias == c).map_or(c, |(_, operator)| *operator);
+    Ok((rest, match c {
+        '(' => TokenKind::LeftParen,
+        ')' => TokenKind::RightParen,
+        ',' => TokenKind::Comma,
+        c => TokenKind::Operator(c),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input).unwrap().into_iter().map(|token| token.kind).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        use TokenKind::{Identifier, LeftParen, Operator, RightParen};
+        assert_eq!(kinds("2*x_1 ^(a-.5)"), vec![
+            TokenKind::Number(2.into()), Operator('*'), Identifier("x_1".to_string()), Operator('^'),
+            LeftParen, Identifier("a".to_string()), Operator('-'), TokenKind::Number(Number::rational(1, 2)), RightParen,
+        ]);
+        assert_eq!(kinds("energy exp_rate cosh"), vec![
+            Identifier("energy".to_string()), Identifier("exp_rate".to_string()), Identifier("cosh".to_string()),
+        ]);
+        assert_eq!(kinds("1.5e-3e"), vec![TokenKind::Number(Number::rational(3, 2000)), Identifier("e".to_string())]);
+        assert_eq!(tokenize(" a +  b").unwrap()[2].span, 6..7);
+        assert_eq!(kinds("ρ₀ × x²⁻¹ − a÷b·c"), vec![
+            Identifier("ρ_0".to_string()), Operator('*'), Identifier("x".to_string()), TokenKind::Superscript(2.into()),
+            TokenKind::Superscript((-1).into()), Operator('-'), Identifier("a".to_string()), Operator('/'),
+            Identifier("b".to_string()), Operator('*'), Identifier("c".to_string()),
+        ]);
+        assert_eq!(kinds("x_₁₂ √"), vec![Identifier("x_12".to_string()), Operator('√')]);
+        assert_eq!(kinds("a<=b != c<d && !e == f"), vec![
+            Identifier("a".to_string()), Operator('≤'), Identifier("b".to_string()), Operator('≠'), Identifier("c".to_string()),
+            Operator('<'), Identifier("d".to_string()), Operator('∧'), Operator('!'), Identifier("e".to_string()), Operator('='),
+            Identifier("f".to_string()),
+        ]);
+        assert_eq!(tokenize("a $ b").unwrap_err().offset, 2);
+    }
+}
@@ -1,30 +1,21 @@
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::IResult;
-use nom::sequence::{tuple};
 use crate::expression::Expression;
-use crate::expression::from_str::{expression, ws};
-use crate::{abs, ln, sqrt};
+use crate::{abs, ceil, erf, erfc, exp, factorial, floor, gamma, ln, round, sign, sqrt};
 
-pub(crate) fn singletons(input: &str) -> IResult<&str, Expression> {
-    let (input, (operation, _, inside, _)) = tuple((
-        alt((
-            tag("sqrt"),
-            tag("ln"),
-            tag("abs"),
-        )),
-        tag("("),
-        ws(expression),
-        tag(")")
-    ))(input)?;
-
-    let expression = match operation {
-        "sqrt" => sqrt!(inside),
-        "ln" => ln!(inside),
-        "abs" => abs!(inside),
-        _ => unreachable!()
-    };
-
-    return Ok((input, expression));
+/// The function of a single argument with the given name
+pub(crate) fn singletons(name: &str) -> Option<fn(Expression) -> Expression> {
+    Some(match name {
+        "sqrt" => |inside| sqrt!(inside),
+        "ln" => |inside| ln!(inside),
+        "abs" => |inside| abs!(inside),
+        "exp" => |inside| exp!(inside),
+        "gamma" | "Γ" => |inside| gamma!(inside),
+        "erf" => |inside| erf!(inside),
+        "erfc" => |inside| erfc!(inside),
+        "factorial" => |inside| factorial!(inside),
+        "floor" => |inside| floor!(inside),
+        "ceil" => |inside| ceil!(inside),
+        "round" => |inside| round!(inside),
+        "sign" | "sgn" => |inside| sign!(inside),
+        _ => return None,
+    })
 }
-
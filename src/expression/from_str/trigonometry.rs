@@ -1,39 +1,21 @@
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::IResult;
-use nom::sequence::tuple;
-
 use crate::expression::Expression;
-use crate::expression::from_str::{expression, ws};
-
-pub fn trigonometry(input: &str) -> IResult<&str, Expression> {
-    let (input, (trig, _, inside, _)) = tuple((
-        alt((
-            tag("cos"),
-            tag("sin"),
-            tag("tan"),
-            tag("arccos"),
-            tag("arcsin"),
-            tag("arctan"),
-            tag("acos"),
-            tag("asin"),
-            tag("atan"),
-            tag("cos^-1"),
-            tag("sin^-1"),
-            tag("tan^-1"),
-        )),
-        tag("("),
-        ws(expression),
-        tag(")")
-    ))(input)?;
+use crate::{acos, acosh, asin, asinh, atan, atanh, cos, cosh, sin, sinh, tan, tanh};
 
-    match trig {
-        "cos" => Ok((input, Expression::Cos(Box::new(inside)))),
-        "sin" => Ok((input, Expression::Sin(Box::new(inside)))),
-        "tan" => Ok((input, Expression::Tan(Box::new(inside)))),
-        "arccos" | "acos" | "cos^-1" => Ok((input, Expression::ArcCos(Box::new(inside)))),
-        "arcsin" | "asin" | "sin^-1" => Ok((input, Expression::ArcSin(Box::new(inside)))),
-        "arctan" | "atan" | "tan^-1" => Ok((input, Expression::ArcTan(Box::new(inside)))),
-        _ => unreachable!()
-    }
-}
\ No newline at end of file
+/// The circular or hyperbolic function with the given name, or its inverse when written like `cos^-1`
+pub(crate) fn trigonometry(name: &str, inverse: bool) -> Option<fn(Expression) -> Expression> {
+    Some(match (name, inverse) {
+        ("cos", false) => |inside| cos!(inside),
+        ("sin", false) => |inside| sin!(inside),
+        ("tan", false) => |inside| tan!(inside),
+        ("arccos" | "acos", false) | ("cos", true) => |inside| acos!(inside),
+        ("arcsin" | "asin", false) | ("sin", true) => |inside| asin!(inside),
+        ("arctan" | "atan", false) | ("tan", true) => |inside| atan!(inside),
+        ("sinh", false) => |inside| sinh!(inside),
+        ("cosh", false) => |inside| cosh!(inside),
+        ("tanh", false) => |inside| tanh!(inside),
+        ("arsinh" | "arcsinh" | "asinh", false) | ("sinh", true) => |inside| asinh!(inside),
+        ("arcosh" | "arccosh" | "acosh", false) | ("cosh", true) => |inside| acosh!(inside),
+        ("artanh" | "arctanh" | "atanh", false) | ("tanh", true) => |inside| atanh!(inside),
+        _ => return None,
+    })
+}
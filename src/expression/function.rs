@@ -0,0 +1,132 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::expression::context::{Context, Definition};
+use crate::expression::error::ExpressionError;
+use crate::expression::Expression;
+
+/// A call of a user-defined function, like f(x, 2)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Function {
+    pub name: String,
+    pub args: Vec<Expression>,
+}
+
+impl Function {
+    pub(crate) fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError> {
+        let definition = self.definition(context)?;
+        let args = self.args.iter().map(|arg| arg.evaluate(context)).collect::<Result<Vec<f64>, _>>()?;
+        match definition {
+            Definition::Native { function, .. } => Ok(function(&args)),
+            Definition::Expression { parameters, body } => context.enter(&self.name, || {
+                context.bind(parameters.iter().cloned().zip(args), || body.evaluate(context))
+            }),
+        }
+    }
+
+    /// The definition of the called function, checked against the number of arguments
+    pub(crate) fn definition<'a>(&self, context: &'a Context) -> Result<&'a Definition, ExpressionError> {
+        let definition = context.function(&self.name)
+            .ok_or_else(|| ExpressionError::UnknownFunction(self.name.clone()))?;
+        if definition.arity() != self.args.len() {
+            return Err(ExpressionError::ArgumentCount {
+                name: self.name.clone(),
+                expected: definition.arity(),
+                found: self.args.len(),
+            });
+        }
+        Ok(definition)
+    }
+}
+
+impl Expression {
+    /// Replaces calls of functions defined by an expression with their body,
+    /// so that simplify, derivative and the solvers can see through them.
+    /// Calls of native or unknown functions are kept as calls.
+    pub fn inline(&self, context: &Context) -> Result<Expression, ExpressionError> {
+        let Expression::Function(function) = self else {
+            let error = RefCell::new(None);
+            let inlined = self.map_children(|child| child.inline(context).unwrap_or_else(|err| {
+                error.borrow_mut().get_or_insert(err);
+                child.clone()
+            }));
+            return error.into_inner().map_or(Ok(inlined), Err);
+        };
+        let args = function.args.iter().map(|arg| arg.inline(context)).collect::<Result<Vec<_>, _>>()?;
+        match function.definition(context) {
+            Ok(Definition::Expression { parameters, body }) => context.enter(&function.name, || {
+                let substitutions: HashMap<String, Expression> = parameters.iter().cloned().zip(args).collect();
+                body.substitute_all(&substitutions).inline(context)
+            }),
+            Err(err @ ExpressionError::ArgumentCount { .. }) => Err(err),
+            _ => Ok(Expression::Function(Function { name: function.name.clone(), args })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::equation::Equation;
+    use crate::{call, num, pow, var};
+    use super::*;
+
+    fn context() -> Context {
+        let mut context = Context::new();
+        context.define_equation(&Equation::from_str("f(x) = x^2 + 1").unwrap()).unwrap();
+        context.define("g", &["x", "y"], var!("x") - var!("y"));
+        context.define_native("hypot", 2, |args| args[0].hypot(args[1]));
+        context
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut context = context();
+        context.set_variable("t", 3.0);
+        assert_eq!(Expression::from_str("f(2t) + 1").unwrap().evaluate(&context), Ok(38.0));
+        assert_eq!(Expression::from_str("g(t, 1) * hypot(3, 4)").unwrap().evaluate(&context), Ok(10.0));
+        // Parameters shadow variables of the same name
+        context.set_variable("x", 100.0);
+        assert_eq!(Expression::from_str("g(1, x)").unwrap().evaluate(&context), Ok(-99.0));
+        assert_eq!(Expression::from_str("g(f(x), x) + x").unwrap().evaluate(&context), Ok(10001.0));
+        // The parameters are unbound again once the call returns
+        assert_eq!(context.variable("x"), Some(100.0));
+        assert_eq!(context.variable("y"), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut context = context();
+        assert_eq!(call!("h", num!(1)).evaluate(&context), Err(ExpressionError::UnknownFunction("h".to_string())));
+        assert_eq!(
+            call!("f", num!(1), num!(2)).evaluate(&context),
+            Err(ExpressionError::ArgumentCount { name: "f".to_string(), expected: 1, found: 2 })
+        );
+        context.define("r", &["x"], call!("r", var!("x") + num!(1)));
+        assert_eq!(call!("r", num!(0)).evaluate(&context), Err(ExpressionError::RecursionLimit("r".to_string())));
+        assert_eq!(call!("r", num!(0)).inline(&context), Err(ExpressionError::RecursionLimit("r".to_string())));
+        assert!(context.define_equation(&Equation::from_str("f(2) = 1").unwrap()).is_err());
+        assert!(context.define_equation(&Equation::from_str("f(x, x) = 1").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_inline() {
+        let context = context();
+        let expression = Expression::from_str("f(2 t) - g(t, 1)").unwrap();
+        let inlined = expression.inline(&context).unwrap();
+        for t in [-1.0, 0.5, 2.0] {
+            let context = Context::from(HashMap::from([("t".to_string(), t)]));
+            assert_eq!(inlined.evaluate(&context), Ok(4.0 * t * t + 1.0 - (t - 1.0)));
+        }
+        assert_eq!(inlined.derivative("t").to_string(), Expression::from_str("8 * t - 1").unwrap().simplify().to_string());
+        assert_eq!(call!("hypot", call!("f", var!("t")), num!(1)).inline(&context), Ok(call!("hypot", pow!(var!("t"), num!(2)) + num!(1), num!(1))));
+    }
+
+    #[test]
+    fn test_undefined_derivative() {
+        let expression = call!("f", pow!(var!("x"), num!(2)));
+        assert_eq!(expression.derivative("x"), (call!("f'", pow!(var!("x"), num!(2))) * (num!(2) * var!("x"))).simplify());
+        assert_eq!(call!("g", var!("y"), var!("x")).derivative("x"), call!("g'_2", var!("y"), var!("x")));
+        assert_eq!(call!("f", var!("y")).derivative("x"), num!(0));
+    }
+}
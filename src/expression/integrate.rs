@@ -0,0 +1,308 @@
+use crate::expression::{add, multiply, Comparison, Condition, Expression};
+use crate::expression::constant::Constant::E;
+use crate::{abs, acos, acosh, asin, asinh, atan, atanh, cos, cosh, ln, neg, num, pow, sin, sinh, sqrt, var};
+
+/// Upper bound on the degree of the polynomial factor in integration by parts
+const MAX_PARTS_DEGREE: usize = 16;
+
+impl Expression {
+    /// Antiderivative of the expression with respect to `variable`, without integration constant.
+    /// Returns `None` when no integration rule applies.
+    pub fn integrate(&self, variable: &str) -> Option<Expression> {
+        Some(self.simplify().antiderivative(variable)?.simplify())
+    }
+
+    fn antiderivative(&self, variable: &str) -> Option<Expression> {
+        use Expression::*;
+        let x = var!(variable);
+        if !self.contains_variable(variable) {
+            return Some(self.clone() * x);
+        }
+        match self {
+            Variable(_) => Some(pow!(x, num!(2)) / num!(2)),
+            Add(add) => Some(Add(add::Add(
+                add.0.iter()
+                    .map(|child| child.antiderivative(variable))
+                    .collect::<Option<_>>()?
+            ))),
+            Multiply(multiply) => integrate_product(&multiply.0, variable),
+            Negate(negate) => Some(neg!(negate.0.antiderivative(variable)?)),
+            Invert(invert) => pow!(*invert.0.clone(), num!(-1)).simplify().antiderivative(variable),
+            Power(base, exponent) if !exponent.contains_variable(variable) => match exponent.solve(None) {
+                Ok(-1.0) => linear_substitution(base, variable, |u| ln!(abs!(u))),
+                Ok(_) => {
+                    let exponent = *exponent.clone() + num!(1);
+                    linear_substitution(base, variable, |u| pow!(u, exponent.clone()) / exponent)
+                }
+                // A symbolic exponent may be -1, where the power rule does not hold
+                Err(_) => None,
+            },
+            Power(base, exponent) if !base.contains_variable(variable) => {
+                let base = *base.clone();
+                if base == Constant(E) {
+                    linear_substitution(exponent, variable, |u| pow!(base, u))
+                } else {
+                    linear_substitution(exponent, variable, |u| pow!(base.clone(), u) / ln!(base))
+                }
+            }
+            Sqrt(u) => linear_substitution(u, variable, |u| pow!(u, num!(1.5)) / num!(1.5)),
+            // Logarithm of u with base b is ln(u) / ln(b)
+            Log(u, b) if !b.contains_variable(variable) => {
+                linear_substitution(u, variable, |u| (u.clone() * ln!(u.clone()) - u) / ln!(*b.clone()))
+            }
+            Ln(u) => linear_substitution(u, variable, |u| u.clone() * ln!(u.clone()) - u),
+            Sin(u) => linear_substitution(u, variable, |u| neg!(cos!(u))),
+            Cos(u) => linear_substitution(u, variable, |u| sin!(u)),
+            Tan(u) => linear_substitution(u, variable, |u| neg!(ln!(abs!(cos!(u))))),
+            ArcSin(u) => linear_substitution(u, variable, |u| {
+                u.clone() * asin!(u.clone()) + sqrt!(num!(1) - pow!(u, num!(2)))
+            }),
+            ArcCos(u) => linear_substitution(u, variable, |u| {
+                u.clone() * acos!(u.clone()) - sqrt!(num!(1) - pow!(u, num!(2)))
+            }),
+            ArcTan(u) => linear_substitution(u, variable, |u| {
+                u.clone() * atan!(u.clone()) - ln!(num!(1) + pow!(u, num!(2))) / num!(2)
+            }),
+            Sinh(u) => linear_substitution(u, variable, |u| cosh!(u)),
+            Cosh(u) => linear_substitution(u, variable, |u| sinh!(u)),
+            // cosh is positive, so no absolute value is needed
+            Tanh(u) => linear_substitution(u, variable, |u| ln!(cosh!(u))),
+            ArcSinh(u) => linear_substitution(u, variable, |u| {
+                u.clone() * asinh!(u.clone()) - sqrt!(pow!(u, num!(2)) + num!(1))
+            }),
+            ArcCosh(u) => linear_substitution(u, variable, |u| {
+                u.clone() * acosh!(u.clone()) - sqrt!(pow!(u, num!(2)) - num!(1))
+            }),
+            ArcTanh(u) => linear_substitution(u, variable, |u| {
+                u.clone() * atanh!(u.clone()) + ln!(num!(1) - pow!(u, num!(2))) / num!(2)
+            }),
+            Abs(u) => linear_substitution(u, variable, |u| u.clone() * abs!(u) / num!(2)),
+            Piecewise(branches, otherwise) => integrate_piecewise(branches, otherwise, variable),
+            _ => None,
+        }
+    }
+}
+
+/// Integrates each branch. When the conditions are ascending upper bounds of the variable, like in
+/// `if(x < a, f, x < b, g, h)`, the branches are shifted by constants so that the result is continuous at the bounds.
+fn integrate_piecewise(branches: &[(Condition, Expression)], otherwise: &Expression, variable: &str) -> Option<Expression> {
+    let mut antiderivatives = branches.iter()
+        .map(|(_, value)| value.antiderivative(variable))
+        .collect::<Option<Vec<_>>>()?;
+    antiderivatives.push(otherwise.antiderivative(variable)?);
+    if let Some(bounds) = branches.iter().map(|(condition, _)| upper_bound(condition, variable)).collect::<Option<Vec<_>>>() {
+        for (i, bound) in bounds.into_iter().enumerate() {
+            let offset = antiderivatives[i].substitute(variable, bound) - antiderivatives[i + 1].substitute(variable, bound);
+            antiderivatives[i + 1] = antiderivatives[i + 1].clone() + offset;
+        }
+    }
+    let otherwise = antiderivatives.pop()?;
+    let branches = branches.iter().map(|(condition, _)| condition.clone()).zip(antiderivatives).collect();
+    Some(Expression::Piecewise(branches, Box::new(otherwise)))
+}
+
+/// The bound b of a condition like `x < b`, `x <= b` or `b > x` that does not depend on x
+fn upper_bound<'a>(condition: &'a Condition, variable: &str) -> Option<&'a Expression> {
+    let bound = match condition {
+        Condition::Compare(Expression::Variable(name), Comparison::Less | Comparison::LessEqual, bound) |
+        Condition::Compare(bound, Comparison::Greater | Comparison::GreaterEqual, Expression::Variable(name)) if name == variable => bound,
+        _ => return None,
+    };
+    (!bound.contains_variable(variable)).then_some(bound)
+}
+
+/// Integrates f(a * x + b) as F(a * x + b) / a, given the antiderivative F of f
+fn linear_substitution(inner: &Expression, variable: &str, antiderivative: impl FnOnce(Expression) -> Expression) -> Option<Expression> {
+    let slope = inner.derivative(variable);
+    if slope.contains_variable(variable) || slope == num!(0) {
+        return None;
+    }
+    Some(antiderivative(inner.clone()) / slope)
+}
+
+/// Pulls factors without the variable out of the integral, and integrates by parts when several remain
+fn integrate_product(factors: &[Expression], variable: &str) -> Option<Expression> {
+    use Expression::*;
+    let (dependent, constant): (Vec<Expression>, Vec<Expression>) = factors.iter()
+        .cloned()
+        .partition(|factor| factor.contains_variable(variable));
+
+    let integral = match &dependent[..] {
+        [single] => single.antiderivative(variable)?,
+        _ => integrate_by_parts(&dependent, variable)?,
+    };
+    if constant.is_empty() {
+        return Some(integral);
+    }
+    Some(Multiply(multiply::Multiply(constant)) * integral)
+}
+
+/// Tabular integration by parts, ∫ p * f = p * F1 - p' * F2 + p'' * F3 - ...,
+/// where p is a polynomial factor and F1, F2, ... are repeated antiderivatives of the rest.
+/// The rest must be a polynomial or an exponential, sine or cosine, whose antiderivatives stay of the same kind.
+/// Others like ln(x) have antiderivatives that need integration by parts again, which would not terminate.
+fn integrate_by_parts(factors: &[Expression], variable: &str) -> Option<Expression> {
+    use Expression::*;
+    for (i, polynomial) in factors.iter().enumerate() {
+        if !is_polynomial(polynomial, variable) {
+            continue;
+        }
+        let rest = Multiply(multiply::Multiply(
+            factors.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, factor)| factor.clone()).collect()
+        )).simplify();
+        if !is_polynomial(&rest, variable) && !has_cyclic_antiderivatives(&rest, variable) {
+            continue;
+        }
+
+        let mut terms = Vec::new();
+        let mut derivative = polynomial.clone();
+        let mut integral = rest;
+        for k in 0..MAX_PARTS_DEGREE {
+            if derivative == num!(0) {
+                return Some(Add(add::Add(terms)));
+            }
+            let Some(next) = integral.antiderivative(variable) else { break };
+            integral = next.simplify();
+            let term = derivative.clone() * integral.clone();
+            terms.push(if k % 2 == 0 { term } else { neg!(term) });
+            derivative = derivative.derivative(variable);
+        }
+    }
+    None
+}
+
+fn has_cyclic_antiderivatives(expression: &Expression, variable: &str) -> bool {
+    use Expression::*;
+    match expression {
+        Power(base, _) => !base.contains_variable(variable),
+        Sin(_) | Cos(_) | Sinh(_) | Cosh(_) => true,
+        _ => false,
+    }
+}
+
+fn is_polynomial(expression: &Expression, variable: &str) -> bool {
+    use Expression::*;
+    if !expression.contains_variable(variable) {
+        return true;
+    }
+    match expression {
+        Variable(_) => true,
+        Add(add) => add.0.iter().all(|child| is_polynomial(child, variable)),
+        Multiply(multiply) => multiply.0.iter().all(|child| is_polynomial(child, variable)),
+        Negate(negate) => is_polynomial(&negate.0, variable),
+        Power(base, exponent) => match &**exponent {
+            Number(exponent) => exponent.is_integer() && !exponent.is_negative() && is_polynomial(base, variable),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::{abs, acos, acosh, asin, asinh, atan, atanh, compare, cos, cosh, inv, ln, log, num, piecewise, pow, sin, sinh, sqrt, tan, tanh, var};
+    use super::*;
+
+    /// Differentiates the antiderivative back and compares it to the integrand at a few points
+    fn assert_integrates(expression: Expression, points: &[f64]) {
+        let integral = expression.integrate("x")
+            .unwrap_or_else(|| panic!("could not integrate {expression}"));
+        let derivative = integral.derivative("x");
+        for x in points {
+            let variables = HashMap::from([("x".to_string(), *x), ("y".to_string(), 1.3)]);
+            let expected = expression.solve(Some(&variables)).unwrap();
+            let actual = derivative.solve(Some(&variables)).unwrap();
+            assert!(
+                (expected - actual).abs() < 1e-9 * expected.abs().max(1.0),
+                "∫ {expression} = {integral}, whose derivative gives {actual} instead of {expected} at x = {x}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_polynomials() {
+        assert_eq!(num!(3).integrate("x"), Some(var!("x") * num!(3)));
+        assert_integrates(var!("y"), &[0.5, 2.0]);
+        assert_integrates(num!(3) * pow!(var!("x"), num!(2)) + var!("x") - num!(4), &[0.5, 2.0]);
+        assert_integrates(var!("x") * (var!("x") + num!(1)), &[0.5, 2.0]);
+        assert_integrates(pow!(num!(2) * var!("x") + num!(1), num!(4)), &[0.5, 2.0]);
+        assert_integrates(pow!(var!("x"), Expression::Constant(E)), &[0.5, 2.0]);
+    }
+
+    #[test]
+    fn test_reciprocal() {
+        assert_integrates(inv!(var!("x")), &[0.5, -2.0]);
+        assert_integrates(num!(3) / (num!(2) * var!("x") - num!(1)), &[2.0, -2.0]);
+        assert_integrates(pow!(var!("x"), num!(-1)), &[0.5, -2.0]);
+        assert_integrates(inv!(pow!(var!("x"), num!(2))), &[0.5, -2.0]);
+    }
+
+    #[test]
+    fn test_exponentials_and_logarithms() {
+        assert_integrates(pow!(Expression::Constant(E), var!("x")), &[0.5, 2.0]);
+        assert_integrates(pow!(Expression::Constant(E), num!(3) * var!("x") + num!(1)), &[0.5, 2.0]);
+        assert_integrates(pow!(num!(2), var!("x")), &[0.5, 2.0]);
+        assert_integrates(ln!(var!("x")), &[0.5, 2.0]);
+        assert_integrates(log!(num!(2) * var!("x"), num!(10)), &[0.5, 2.0]);
+        assert_integrates(sqrt!(var!("x") + num!(1)), &[0.5, 2.0]);
+        assert_integrates(abs!(var!("x")), &[-0.5, 2.0]);
+    }
+
+    #[test]
+    fn test_trigonometry() {
+        assert_integrates(sin!(num!(2) * var!("x")), &[0.5, 2.0]);
+        assert_integrates(cos!(var!("x") - num!(1)), &[0.5, 2.0]);
+        assert_integrates(tan!(var!("x")), &[0.5, 1.0]);
+        assert_integrates(asin!(var!("x") / num!(2)), &[0.5, -1.0]);
+        assert_integrates(acos!(var!("x")), &[0.5, -0.3]);
+        assert_integrates(atan!(num!(3) * var!("x")), &[0.5, 2.0]);
+    }
+
+    #[test]
+    fn test_hyperbolic() {
+        assert_integrates(sinh!(num!(2) * var!("x")), &[0.5, -2.0]);
+        assert_integrates(cosh!(var!("x") - num!(1)), &[0.5, 2.0]);
+        assert_integrates(tanh!(var!("x")), &[0.5, -1.0]);
+        assert_integrates(asinh!(var!("x") / num!(2)), &[0.5, -1.0]);
+        assert_integrates(acosh!(var!("x")), &[1.5, 3.0]);
+        assert_integrates(atanh!(var!("x")), &[0.5, -0.3]);
+    }
+
+    #[test]
+    fn test_by_parts() {
+        assert_integrates(var!("x") * pow!(Expression::Constant(E), var!("x")), &[0.5, 2.0]);
+        assert_integrates(pow!(var!("x"), num!(2)) * sin!(var!("x")), &[0.5, 2.0]);
+        assert_integrates(num!(4) * (var!("x") + num!(1)) * cos!(num!(2) * var!("x")), &[0.5, 2.0]);
+        assert_integrates(pow!(var!("x"), num!(3)) * pow!(Expression::Constant(E), num!(-1) * var!("x")), &[0.5, 2.0]);
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let x = || var!("x");
+        let sign = piecewise!((compare!(x(), <, num!(0)), num!(-1)); num!(1));
+        assert_integrates(sign.clone(), &[-2.0, 0.5]);
+        let ramp = piecewise!((compare!(x(), <, num!(1)), num!(0)), (compare!(num!(2), >=, x()), x() - num!(1)); num!(1));
+        assert_integrates(ramp.clone(), &[0.5, 1.5, 3.0]);
+        // Ascending bounds give a continuous antiderivative, so differences of it are definite integrals
+        let at = |expression: &Expression, x: f64| expression.solve(Some(&HashMap::from([("x".to_string(), x)]))).unwrap();
+        let integral = sign.integrate("x").unwrap();
+        assert_eq!((at(&integral, -2.0), at(&integral, 2.0)), (2.0, 2.0));
+        let integral = ramp.integrate("x").unwrap();
+        assert!((at(&integral, 3.0) - at(&integral, 0.0) - 1.5).abs() < 1e-12, "{integral}");
+        assert_integrates(piecewise!((compare!(x() * x(), <, num!(1)), sin!(x())); x()), &[0.5, 2.0]);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(sin!(pow!(var!("x"), num!(2))).integrate("x"), None);
+        assert_eq!(pow!(var!("x"), var!("x")).integrate("x"), None);
+        assert_eq!((ln!(var!("x")) * sin!(var!("x"))).integrate("x"), None);
+        // The power rule fails for an exponent of -1, which a symbolic exponent may be
+        assert_eq!(pow!(var!("x"), var!("y")).integrate("x"), None);
+        // Repeated antiderivatives of ln and atan never run out, so integration by parts is not attempted
+        assert_eq!((var!("x") * ln!(var!("x"))).integrate("x"), None);
+        assert_eq!((pow!(var!("x"), num!(2)) * ln!(var!("x"))).integrate("x"), None);
+        assert_eq!((var!("x") * atan!(var!("x"))).integrate("x"), None);
+    }
+}
@@ -1,30 +1,26 @@
 use std::borrow::Borrow;
-use std::collections::HashMap;
 use std::fmt::Display;
-use crate::expression::display::parenthesize_if_of_type;
+use crate::expression::display::{write_with_precedence, Precedence};
 use crate::expression::{Expression, multiply, number, Operand};
+use crate::expression::context::Context;
 use crate::expression::error::ExpressionError;
-use crate::{inv, num};
+use crate::{inv, neg};
 
 #[derive(Clone, Debug, PartialEq, Hash, Eq)]
 pub struct Invert(pub Box<Expression>);
 
 impl Display for Invert{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use Expression::*;
-        let a: String = parenthesize_if_of_type!(*self.0, Add(..) | Multiply(..) | Power(..) | Negate(..));
-        write!(f, "1 / {}", a)
+        write!(f, "1 / ")?;
+        write_with_precedence(f, &self.0, Precedence::Unary)
     }
 }
 
 impl Operand for Invert{
-    fn solve(&self, variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError> {
-        Ok( 1.0 / self.0.solve(variables)? )
+    fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError> {
+        Ok( 1.0 / self.0.evaluate(context)? )
     }
 
-    fn operand_count(&self) -> usize {
-        1
-    }
     fn children(&self) -> Vec<&Expression> {
         vec![self.0.borrow()]
     }
@@ -32,10 +28,11 @@ impl Operand for Invert{
         use crate::expression::Expression::*;
         match self.0.simplify(){
             Invert(a) => *a.0,
+            Negate(a) => neg!(inv!(*a.0).simplify()),
             Multiply(multiply::Multiply(children)) => {
                 Multiply(multiply::Multiply(children.iter().map(|child| inv!(child.clone()).simplify()).collect()))
             },
-            Number(number::Number(num)) => num!(1.0/num),
+            Number(num) => Number(number::Number::from(1) / num),
             a => inv!(a),
         }
     }
@@ -44,7 +41,6 @@ impl Operand for Invert{
 
 #[cfg(test)]
 mod tests{
-    use super::*;
     use crate::{inv, num, var};
     #[test]
     fn inverted_multiply(){
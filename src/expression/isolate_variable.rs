@@ -1,53 +1,165 @@
-use crate::expression::Expression;
+use crate::expression::{add, multiply, Expression};
+use crate::expression::constant::Constant::E;
+use crate::expression::error::IsolateError;
+use crate::operation::Operation;
+use crate::{acos, acosh, asin, asinh, atan, atanh, cos, cosh, inv, ln, log, neg, num, pow, sin, sinh, tan, tanh};
 
-// a * c + b * c -> c * (a + b)
-// (a + b) * (c + d) -> a * (c + d) + b * (c + d)
 impl Expression {
-    pub fn isolate_variable(&self, variable: &str) -> Option<Expression> {
-        use crate::expression::Expression::*;
-        match self.simplify(){
-            Constant(_) | Number(_) => None,
-            Variable(name) if name == variable => Some(self.clone()),
-            Variable(_) => None,
-            Add(add) => {
-                let (containing, non_containing): (Vec<_>, Vec<_>) = add.0.iter()
-                    .partition(|child| child.contains_variable(variable));
+    /// Solves `self = other` for `variable` by peeling operations off `self`
+    /// and applying their inverses to `other`, one at a time.
+    /// Only the principal branch of each inverse is taken, so a single solution is returned:
+    /// `x ^ 2 = y` gives the positive root `y ^ (1/2)` and `sin(x) = y` gives `arcsin(y)`.
+    /// Use `Equation::solve_for` for all roots of a polynomial.
+    pub fn isolate_variable(&self, variable: &str, other: Expression) -> Result<Expression, IsolateError> {
+        if !self.contains_variable(variable) {
+            return Err(IsolateError::VariableAbsent(variable.to_string()));
+        }
+        Ok(self.simplify().peel(variable, other)?.simplify())
+    }
 
-                if containing.len() == 1{
-                    return Some(self.clone());
+    fn peel(&self, variable: &str, other: Expression) -> Result<Expression, IsolateError> {
+        use Expression::*;
+        match self {
+            Variable(_) => Ok(other),
+            Add(add) => {
+                let (containing, rest) = match split_by_variable(&add.0, variable, Operation::Add) {
+                    // a * c + b * c -> c * (a + b), which may leave the variable in a single place
+                    Err(IsolateError::NonInvertible(operation)) => return match self.factor() {
+                        Add(_) => Err(IsolateError::NonInvertible(operation)),
+                        factored => factored.peel(variable, other),
+                    },
+                    split => split?,
+                };
+                let other = if rest.is_empty() { other } else { other - Add(add::Add(rest)) };
+                containing.peel(variable, other)
+            }
+            Multiply(multiply) => {
+                let (containing, rest) = split_by_variable(&multiply.0, variable, Operation::Multiply)?;
+                let other = if rest.is_empty() { other } else { other / Multiply(multiply::Multiply(rest)) };
+                containing.peel(variable, other)
+            }
+            Power(base, exponent) => {
+                match (base.contains_variable(variable), exponent.contains_variable(variable)) {
+                    (true, true) => Err(IsolateError::NonInvertible(Operation::PowerBase)),
+                    // a ^ n = y  ->  a = y ^ (1 / n), the positive root for even n
+                    (true, false) => base.peel(variable, pow!(other, inv!(*exponent.clone()))),
+                    // e ^ a = y  ->  a = ln(y)
+                    (false, true) if **base == Constant(E) => exponent.peel(variable, ln!(other)),
+                    // b ^ a = y  ->  a = log_b(y)
+                    _ => exponent.peel(variable, log!(other, *base.clone())),
                 }
-                // if containing.iter().all(|child| matches!(child, Multiply(..))){
-                //
-                // }
-                None
             }
-            _ => {
-                let children = self.children();
-                if children.len() == 1{
-                    return Some(self.clone());
+            Sqrt(a) => a.peel(variable, pow!(other, num!(2))),
+            // Logarithm of a with base b
+            Log(a, b) => {
+                match (a.contains_variable(variable), b.contains_variable(variable)) {
+                    (true, true) => Err(IsolateError::NonInvertible(Operation::LogBase)),
+                    // log_b(a) = y  ->  a = b ^ y
+                    (true, false) => a.peel(variable, pow!(*b.clone(), other)),
+                    // log_b(a) = y  ->  b = a ^ (1 / y)
+                    _ => b.peel(variable, pow!(*a.clone(), inv!(other))),
                 }
-
-                let isolations: Vec<_> = children
-                    .iter()
-                    .filter_map(|child| child.isolate_variable(variable))
-                    .collect();
-                None
-                // match isolations.len(){
-                //     0 => return None,
-                //     1 => return Some(self.clone()),
-                //     2 => {
-                //         match self{
-                //             Add(a) => {},
-                //             Multiply(a) => {},
-                //             Power(a, b) => {},
-                //             Log(a, b) => {}
-                //             _ => unreachable!()
-                //         }
-                //     }
-                //     _ => unreachable!()
-                // }
             }
+            Sin(a) => a.peel(variable, asin!(other)),
+            ArcSin(a) => a.peel(variable, sin!(other)),
+            Cos(a) => a.peel(variable, acos!(other)),
+            ArcCos(a) => a.peel(variable, cos!(other)),
+            Tan(a) => a.peel(variable, atan!(other)),
+            ArcTan(a) => a.peel(variable, tan!(other)),
+            Sinh(a) => a.peel(variable, asinh!(other)),
+            ArcSinh(a) => a.peel(variable, sinh!(other)),
+            Cosh(a) => a.peel(variable, acosh!(other)),
+            ArcCosh(a) => a.peel(variable, cosh!(other)),
+            Tanh(a) => a.peel(variable, atanh!(other)),
+            ArcTanh(a) => a.peel(variable, tanh!(other)),
+            Ln(a) => a.peel(variable, pow!(Constant(E), other)),
+            Abs(_) => Err(IsolateError::NonInvertible(Operation::Abs)),
+            Gamma(_) => Err(IsolateError::NonInvertible(Operation::Gamma)),
+            Polygamma(..) => Err(IsolateError::NonInvertible(Operation::Polygamma)),
+            Erf(_) => Err(IsolateError::NonInvertible(Operation::Erf)),
+            Erfc(_) => Err(IsolateError::NonInvertible(Operation::Erfc)),
+            Factorial(_) => Err(IsolateError::NonInvertible(Operation::Factorial)),
+            Floor(_) => Err(IsolateError::NonInvertible(Operation::Floor)),
+            Ceil(_) => Err(IsolateError::NonInvertible(Operation::Ceil)),
+            Round(_) => Err(IsolateError::NonInvertible(Operation::Round)),
+            Sign(_) => Err(IsolateError::NonInvertible(Operation::Sign)),
+            Min(_) => Err(IsolateError::NonInvertible(Operation::Min)),
+            Max(_) => Err(IsolateError::NonInvertible(Operation::Max)),
+            Mod(..) => Err(IsolateError::NonInvertible(Operation::Mod)),
+            // Calls have to be inlined before their argument can be isolated
+            Function(_) => Err(IsolateError::NonInvertible(Operation::Function)),
+            Piecewise(..) => Err(IsolateError::NonInvertible(Operation::Piecewise)),
+            Negate(negate) => negate.0.peel(variable, neg!(other)),
+            Invert(invert) => invert.0.peel(variable, inv!(other)),
+            Constant(_) | Number(_) => Err(IsolateError::VariableAbsent(variable.to_string())),
         }
+    }
+}
+
+/// Separates the single child containing the variable from the others
+fn split_by_variable<'a>(children: &'a [Expression], variable: &str, operation: Operation) -> Result<(&'a Expression, Vec<Expression>), IsolateError> {
+    let (containing, rest): (Vec<&Expression>, Vec<&Expression>) = children.iter()
+        .partition(|child| child.contains_variable(variable));
+
+    match containing[..] {
+        [containing] => Ok((containing, rest.into_iter().cloned().collect())),
+        [] => Err(IsolateError::VariableAbsent(variable.to_string())),
+        _ => Err(IsolateError::NonInvertible(operation)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use crate::{abs, floor, num, pow, sin, var};
+    use super::*;
+
+    #[test]
+    fn test_peel_operations() {
+        let variables = HashMap::from([("y".to_string(), 0.5), ("a".to_string(), 3.0)]);
+        let cases = [
+            sin!(var!("x")),
+            pow!(var!("x"), num!(3)) * var!("a"),
+            pow!(num!(2), var!("x") - var!("a")),
+            ln!(num!(2) * var!("x")),
+            log!(var!("x"), var!("a")),
+            log!(var!("a"), var!("x")),
+            num!(4) - inv!(var!("x")),
+            var!("x") * var!("a") + var!("x"),
+            sinh!(var!("x")) * var!("a"),
+            cosh!(var!("x")) - num!(1),
+            tanh!(var!("x") - var!("a")),
+            asinh!(var!("x")),
+            atanh!(var!("x") / var!("a")),
+        ];
+        for case in cases {
+            let x = case.isolate_variable("x", var!("y")).unwrap();
+            let mut with_x = variables.clone();
+            with_x.insert("x".to_string(), x.solve(Some(&variables)).unwrap());
+            let result = case.solve(Some(&with_x)).unwrap();
+            assert!((result - 0.5).abs() < 1e-9, "{case} = y gave x = {x}");
+        }
+    }
+
+    #[test]
+    fn test_principal_branch() {
+        assert_eq!(pow!(var!("x"), num!(2)).isolate_variable("x", var!("y")), Ok(pow!(var!("y"), num!(1, 2))));
+        assert_eq!(pow!(var!("x"), num!(2)).isolate_variable("x", num!(4)), Ok(num!(2)));
+        assert_eq!(sin!(var!("x")).isolate_variable("x", var!("y")), Ok(asin!(var!("y"))));
+        assert_eq!(cos!(var!("x")).isolate_variable("x", var!("y")), Ok(acos!(var!("y"))));
+        assert_eq!(cosh!(var!("x")).isolate_variable("x", var!("y")), Ok(acosh!(var!("y"))));
+        assert_eq!(Expression::from_str("exp(x)").unwrap().isolate_variable("x", var!("y")), Ok(ln!(var!("y"))));
+    }
 
+    #[test]
+    fn test_non_invertible() {
+        assert_eq!(abs!(var!("x")).isolate_variable("x", num!(1)), Err(IsolateError::NonInvertible(Operation::Abs)));
+        assert_eq!(floor!(var!("x") / num!(2)).isolate_variable("x", num!(1)), Err(IsolateError::NonInvertible(Operation::Floor)));
+        assert_eq!(
+            (var!("x") + sin!(var!("x"))).isolate_variable("x", num!(1)),
+            Err(IsolateError::NonInvertible(Operation::Add))
+        );
+        assert_eq!(num!(1).isolate_variable("x", num!(1)), Err(IsolateError::VariableAbsent("x".to_string())));
     }
-}
\ No newline at end of file
+}
@@ -8,125 +8,290 @@ macro_rules! var {
 #[macro_export]
 macro_rules! num {
     ($expression:expr) => {
-        $crate::expression::Expression::Number($crate::expression::number::Number($expression as f64))
+        $crate::expression::Expression::Number($crate::expression::number::Number::from($expression))
+    };
+    ($numerator:expr, $denominator:expr) => {
+        $crate::expression::Expression::Number($crate::expression::number::Number::rational($numerator, $denominator))
     };
 }
 
 #[macro_export]
 macro_rules! add {
     ($($expression:expr),+ $(,)?) => {
-        $crate::expression::Expression::Add($crate::expression::add::Add( vec![$($expression),+]))
+        $crate::expression::Expression::Add($crate::expression::Add( vec![$($expression),+]))
     };
 }
 
 #[macro_export]
 macro_rules! mul {
     ($($expression:expr),+ $(,)?) => {
-        $crate::expression::Expression::Multiply($crate::expression::multiply::Multiply( vec![$($expression),+]))
+        $crate::expression::Expression::Multiply($crate::expression::Multiply( vec![$($expression),+]))
     };
 }
 
 #[macro_export]
 macro_rules! div {
     ($expression1:expr, $expression2:expr $(,)?) => {
-        Expression::Divide(Box::new($expression1), Box::new($expression2))
+        $crate::mul!($expression1, $crate::inv!($expression2))
     };
 }
 
 #[macro_export]
 macro_rules! pow {
     ($expression1:expr, $expression2:expr $(,)?) => {
-        Expression::Power(Box::new($expression1), Box::new($expression2))
+        $crate::expression::Expression::Power(Box::new($expression1), Box::new($expression2))
     };
 }
 
 #[macro_export]
 macro_rules! neg {
     ($expression:expr) => {
-        Expression::Negate($crate::expression::Negate(Box::new($expression)))
+        $crate::expression::Expression::Negate($crate::expression::Negate(Box::new($expression)))
     };
 }
 
 #[macro_export]
 macro_rules! inv {
     ($expression:expr) => {
-        Expression::Invert(($crate::expression::Invert(Box::new($expression))))
+        $crate::expression::Expression::Invert($crate::expression::Invert(Box::new($expression)))
     }
 }
 
 #[macro_export]
 macro_rules! sqrt {
     ($expression:expr) => {
-        Expression::Sqrt(Box::new($expression))
+        $crate::expression::Expression::Sqrt(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! abs {
     ($expression:expr) => {
-        Expression::Abs(Box::new($expression))
+        $crate::expression::Expression::Abs(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! log {
     ($expression1:expr, $expression2:expr $(,)?) => {
-        Expression::Log(Box::new($expression1), Box::new($expression2))
+        $crate::expression::Expression::Log(Box::new($expression1), Box::new($expression2))
     };
 }
 
 #[macro_export]
 macro_rules! exp {
     ($expression:expr) => {
-        Expression::Exp(Box::new($expression))
+        $crate::pow!($crate::expression::Expression::Constant($crate::expression::constant::Constant::E), $expression)
+    };
+}
+
+#[macro_export]
+macro_rules! call {
+    ($name:expr $(, $arg:expr)* $(,)?) => {
+        $crate::expression::Expression::Function($crate::expression::Function { name: $name.to_string(), args: vec![$($arg),*] })
     };
 }
 
 #[macro_export]
 macro_rules! ln {
     ($expression:expr) => {
-        Expression::Ln(Box::new($expression))
+        $crate::expression::Expression::Ln(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! sin {
     ($expression:expr) => {
-        Expression::Sin(Box::new($expression))
+        $crate::expression::Expression::Sin(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! cos {
     ($expression:expr) => {
-        Expression::Cos(Box::new($expression))
+        $crate::expression::Expression::Cos(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! tan {
     ($expression:expr) => {
-        Expression::Tan(Box::new($expression))
+        $crate::expression::Expression::Tan(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! asin {
     ($expression:expr) => {
-        Expression::ArcSin(Box::new($expression))
+        $crate::expression::Expression::ArcSin(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! acos {
     ($expression:expr) => {
-        Expression::ArcCos(Box::new($expression))
+        $crate::expression::Expression::ArcCos(Box::new($expression))
     };
 }
 
 #[macro_export]
 macro_rules! atan {
     ($expression:expr) => {
-        Expression::ArcTan(Box::new($expression))
+        $crate::expression::Expression::ArcTan(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! sinh {
+    ($expression:expr) => {
+        $crate::expression::Expression::Sinh(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! asinh {
+    ($expression:expr) => {
+        $crate::expression::Expression::ArcSinh(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! cosh {
+    ($expression:expr) => {
+        $crate::expression::Expression::Cosh(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! acosh {
+    ($expression:expr) => {
+        $crate::expression::Expression::ArcCosh(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! tanh {
+    ($expression:expr) => {
+        $crate::expression::Expression::Tanh(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! atanh {
+    ($expression:expr) => {
+        $crate::expression::Expression::ArcTanh(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! gamma {
+    ($expression:expr) => {
+        $crate::expression::Expression::Gamma(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! erf {
+    ($expression:expr) => {
+        $crate::expression::Expression::Erf(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! erfc {
+    ($expression:expr) => {
+        $crate::expression::Expression::Erfc(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! factorial {
+    ($expression:expr) => {
+        $crate::expression::Expression::Factorial(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! floor {
+    ($expression:expr) => {
+        $crate::expression::Expression::Floor(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! ceil {
+    ($expression:expr) => {
+        $crate::expression::Expression::Ceil(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! round {
+    ($expression:expr) => {
+        $crate::expression::Expression::Round(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! sign {
+    ($expression:expr) => {
+        $crate::expression::Expression::Sign(Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! min {
+    ($($expression:expr),+ $(,)?) => {
+        $crate::expression::Expression::Min(vec![$($expression),+])
+    };
+}
+
+#[macro_export]
+macro_rules! max {
+    ($($expression:expr),+ $(,)?) => {
+        $crate::expression::Expression::Max(vec![$($expression),+])
+    };
+}
+
+#[macro_export]
+macro_rules! polygamma {
+    ($order:expr, $expression:expr $(,)?) => {
+        $crate::expression::Expression::Polygamma(Box::new($order), Box::new($expression))
+    };
+}
+
+#[macro_export]
+macro_rules! modulo {
+    ($expression1:expr, $expression2:expr $(,)?) => {
+        $crate::expression::Expression::Mod(Box::new($expression1), Box::new($expression2))
+    };
+}
+
+#[macro_export]
+macro_rules! compare {
+    ($left:expr, <, $right:expr) => {
+        $crate::expression::Condition::Compare($left, $crate::expression::Comparison::Less, $right)
+    };
+    ($left:expr, <=, $right:expr) => {
+        $crate::expression::Condition::Compare($left, $crate::expression::Comparison::LessEqual, $right)
+    };
+    ($left:expr, >, $right:expr) => {
+        $crate::expression::Condition::Compare($left, $crate::expression::Comparison::Greater, $right)
+    };
+    ($left:expr, >=, $right:expr) => {
+        $crate::expression::Condition::Compare($left, $crate::expression::Comparison::GreaterEqual, $right)
+    };
+    ($left:expr, =, $right:expr) => {
+        $crate::expression::Condition::Compare($left, $crate::expression::Comparison::Equal, $right)
+    };
+    ($left:expr, !=, $right:expr) => {
+        $crate::expression::Condition::Compare($left, $crate::expression::Comparison::NotEqual, $right)
+    };
+}
+
+#[macro_export]
+macro_rules! piecewise {
+    ($(($condition:expr, $value:expr)),+ ; $otherwise:expr) => {
+        $crate::expression::Expression::Piecewise(vec![$(($condition, $value)),+], Box::new($otherwise))
     };
 }
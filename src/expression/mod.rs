@@ -1,29 +1,43 @@
 use std::collections::HashMap;
 use std::fmt::Display;
-use crate::expression::add::Add;
-use crate::expression::invert::Invert;
-use crate::expression::multiply::Multiply;
-use crate::expression::negate::Negate;
+pub use crate::expression::add::Add;
+pub use crate::expression::invert::Invert;
+pub use crate::expression::multiply::Multiply;
+pub use crate::expression::negate::Negate;
+pub use crate::expression::function::Function;
+pub use crate::expression::piecewise::{Comparison, Condition};
 use crate::expression::number::Number;
+use crate::utils::special;
+use crate::{abs, acos, acosh, asin, asinh, atan, atanh, ceil, cos, cosh, erf, erfc, factorial, floor, gamma, ln, log, modulo, num, polygamma, pow, round, sign, sin, sinh, sqrt, tan, tanh};
 
-use self::{constant::Constant, error::ExpressionError};
+use self::{constant::Constant, context::Context, error::ExpressionError};
 pub mod constant;
+pub mod context;
+mod derivative;
+mod expand;
+mod factor;
+mod function;
 pub mod display;
 pub mod error;
 pub mod from_str;
 pub mod macros;
 pub mod find_variable;
+mod integrate;
 mod add;
 mod negate;
 mod multiply;
 mod invert;
 mod isolate_variable;
 pub mod number;
+pub mod polynomial;
+mod substitute;
 mod operations;
+mod ordering;
+pub mod piecewise;
+mod special_functions;
 
 trait Operand: Display + Clone + PartialEq{
-    fn solve(&self, variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError>;
-    fn operand_count(&self) -> usize;
+    fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError>;
     fn children(&self) -> Vec<&Expression>;
     fn simplify(&self) -> Expression;
 }
@@ -31,9 +45,9 @@ trait Operand: Display + Clone + PartialEq{
 type Expr = Box<Expression>;
 #[derive(Debug, PartialEq, Clone, Hash, Eq)]
 pub enum Expression {
-    /// Known mathematical constants, Like pi, e, etc
+    /// Known mathematical and physical constants, like pi, e or c, and constants defined in a context
     Constant(Constant),
-    /// A 64bit floating point number
+    /// A number, kept as an exact rational unless it came from an irrational operation
     Number(Number),
     /// An unresolved variable, like x
     Variable(String),
@@ -49,37 +63,103 @@ pub enum Expression {
     ArcCos(Expr),
     Tan(Expr),
     ArcTan(Expr),
+    Sinh(Expr),
+    ArcSinh(Expr),
+    Cosh(Expr),
+    ArcCosh(Expr),
+    Tanh(Expr),
+    ArcTanh(Expr),
     Ln(Expr),
     Abs(Expr),
+    Gamma(Expr),
+    /// The polygamma function of an order and an argument, the derivatives of the digamma function gamma' / gamma
+    Polygamma(Expr, Expr),
+    /// The error function
+    Erf(Expr),
+    /// The complementary error function 1 - erf
+    Erfc(Expr),
+    Factorial(Expr),
+    Floor(Expr),
+    Ceil(Expr),
+    /// Rounding to the nearest integer, with halves away from zero
+    Round(Expr),
+    /// -1, 0 or 1 by the sign of the argument
+    Sign(Expr),
+    /// The least of one or more arguments
+    Min(Vec<Expression>),
+    /// The greatest of one or more arguments
+    Max(Vec<Expression>),
+    /// Remainder of a floored division, with the sign of the divisor
+    Mod(Expr, Expr),
     Negate(Negate),
     Invert(Invert),
+    /// A call of a user-defined function, resolved through a `Context`
+    Function(Function),
+    /// The value of the first branch whose condition holds, or the last expression when none does
+    Piecewise(Vec<(Condition, Expression)>, Expr),
 }
 
 impl Expression {
     pub fn solve(&self, variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError> {
+        self.evaluate(&Context::from(variables.cloned().unwrap_or_default()))
+    }
+
+    /// Evaluates the expression with the variables and functions of the context
+    pub fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError> {
         use Expression::*;
         Ok(match self {
-            Number(a) => a.solve(variables)?,
+            Number(a) => a.evaluate(context)?,
             Constant(a) => a.solve(),
-            Variable(a) => *variables
-                .ok_or(ExpressionError::MissingVariable(a.clone()))?
-                .get(a)
-                .ok_or(ExpressionError::MissingVariable(a.clone()))?,
-            Add(add) => add.solve(variables)?,
-            Multiply(multiply) => multiply.solve(variables)?,
-            Power(a, b) => a.solve(variables)?.powf(b.solve(variables)?),
-            Sqrt(a) => a.solve(variables)?.sqrt(),
-            Log(a, b) => a.solve(variables)?.log(b.solve(variables)?),
-            Sin(a) => a.solve(variables)?.sin(),
-            ArcSin(a) => a.solve(variables)?.asin(),
-            Cos(a) => a.solve(variables)?.cos(),
-            ArcCos(a) => a.solve(variables)?.acos(),
-            Tan(a) => a.solve(variables)?.tan(),
-            ArcTan(a) => a.solve(variables)?.atan(),
-            Ln(a) => a.solve(variables)?.ln(),
-            Abs(a) => a.solve(variables)?.abs(),
-            Negate(negate) => negate.solve(variables)?,
-            Invert(invert) => invert.solve(variables)?,
+            Variable(a) => match (context.variable(a), context.constant(a)) {
+                (Some(value), _) => value,
+                (None, Some(constant)) => constant.solve(),
+                (None, None) => return Err(ExpressionError::MissingVariable(a.clone())),
+            },
+            Add(add) => add.evaluate(context)?,
+            Multiply(multiply) => multiply.evaluate(context)?,
+            Power(a, b) => a.evaluate(context)?.powf(b.evaluate(context)?),
+            Sqrt(a) => a.evaluate(context)?.sqrt(),
+            Log(a, b) => a.evaluate(context)?.log(b.evaluate(context)?),
+            Sin(a) => a.evaluate(context)?.sin(),
+            ArcSin(a) => a.evaluate(context)?.asin(),
+            Cos(a) => a.evaluate(context)?.cos(),
+            ArcCos(a) => a.evaluate(context)?.acos(),
+            Tan(a) => a.evaluate(context)?.tan(),
+            ArcTan(a) => a.evaluate(context)?.atan(),
+            Sinh(a) => a.evaluate(context)?.sinh(),
+            ArcSinh(a) => a.evaluate(context)?.asinh(),
+            Cosh(a) => a.evaluate(context)?.cosh(),
+            ArcCosh(a) => a.evaluate(context)?.acosh(),
+            Tanh(a) => a.evaluate(context)?.tanh(),
+            ArcTanh(a) => a.evaluate(context)?.atanh(),
+            Ln(a) => a.evaluate(context)?.ln(),
+            Abs(a) => a.evaluate(context)?.abs(),
+            Gamma(a) => special::gamma(a.evaluate(context)?),
+            Polygamma(n, a) => special::polygamma(n.evaluate(context)?, a.evaluate(context)?),
+            Erf(a) => special::erf(a.evaluate(context)?),
+            Erfc(a) => special::erfc(a.evaluate(context)?),
+            Factorial(a) => special::gamma(a.evaluate(context)? + 1.0),
+            Floor(a) => a.evaluate(context)?.floor(),
+            Ceil(a) => a.evaluate(context)?.ceil(),
+            Round(a) => a.evaluate(context)?.round(),
+            Sign(a) => number::Number::float(a.evaluate(context)?).signum().value(),
+            Min(args) => args.iter().try_fold(f64::INFINITY, |min, arg| Ok(min.min(arg.evaluate(context)?)))?,
+            Max(args) => args.iter().try_fold(f64::NEG_INFINITY, |max, arg| Ok(max.max(arg.evaluate(context)?)))?,
+            Mod(a, b) => {
+                let (a, b) = (a.evaluate(context)?, b.evaluate(context)?);
+                a - b * (a / b).floor()
+            }
+            Negate(negate) => negate.evaluate(context)?,
+            Invert(invert) => invert.evaluate(context)?,
+            Function(function) => function.evaluate(context)?,
+            Piecewise(branches, otherwise) => {
+                for (condition, value) in branches {
+                    if condition.evaluate(context)? {
+                        return value.evaluate(context);
+                    }
+                }
+                otherwise.evaluate(context)?
+            }
         })
     }
 
@@ -90,6 +170,103 @@ impl Expression {
             Add(add) => add.simplify(),
             Multiply(multiply) => multiply.simplify(),
             Invert(invert) => invert.simplify(),
+            Power(base, exponent) => match (base.simplify(), exponent.simplify()) {
+                // Irrational roots of exact numbers stay exact, like 2 ^ (1/3), as square roots do
+                (Number(base), Number(exponent)) => match base.pow(&exponent) {
+                    power if base.is_exact() && !power.is_exact() && exponent.is_exact() && !exponent.is_integer() => {
+                        pow!(Number(base), Number(exponent))
+                    }
+                    power => Number(power),
+                },
+                // (a ^ n) ^ m = a ^ (n * m) holds for integer m
+                (Power(base, inner), Number(exponent)) if exponent.is_integer() => {
+                    pow!(*base, *inner * Number(exponent)).simplify()
+                }
+                (_, Number(exponent)) if exponent == 0.0 => num!(1),
+                (base, Number(exponent)) if exponent == 1.0 => base,
+                (Number(base), _) if base == 1.0 => num!(1),
+                (base, exponent) => pow!(base, exponent),
+            },
+            Log(a, b) => log!(a.simplify(), b.simplify()),
+            Sqrt(a) => match a.simplify() {
+                // Only fold perfect squares and pull out square factors, so that sqrt(8) = 2 * sqrt(2) stays exact
+                Number(a) => match (a.sqrt(), a.split_sqrt()) {
+                    (Some(root), _) => Number(root),
+                    (None, Some((coefficient, radicand))) if coefficient != 1.0 => (Number(coefficient) * sqrt!(Number(radicand))).simplify(),
+                    _ => sqrt!(Number(a)),
+                },
+                a => sqrt!(a),
+            },
+            Sin(a) => sin!(a.simplify()),
+            ArcSin(a) => asin!(a.simplify()),
+            Cos(a) => cos!(a.simplify()),
+            ArcCos(a) => acos!(a.simplify()),
+            Tan(a) => tan!(a.simplify()),
+            ArcTan(a) => atan!(a.simplify()),
+            Sinh(a) => sinh!(a.simplify()),
+            ArcSinh(a) => asinh!(a.simplify()),
+            Cosh(a) => cosh!(a.simplify()),
+            ArcCosh(a) => acosh!(a.simplify()),
+            Tanh(a) => tanh!(a.simplify()),
+            ArcTanh(a) => atanh!(a.simplify()),
+            Ln(a) => match a.simplify() {
+                Constant(constant::Constant::E) => num!(1),
+                Number(n) if n == 1.0 => num!(0),
+                a => ln!(a),
+            },
+            Abs(a) => abs!(a.simplify()),
+            Gamma(a) => match a.simplify() {
+                // gamma(n) = (n - 1)! for positive integers
+                Number(n) => match (n.clone() - number::Number::from(1)).factorial() {
+                    Some(factorial) => Number(factorial),
+                    None => gamma!(Number(n)),
+                },
+                a => gamma!(a),
+            },
+            Polygamma(n, a) => polygamma!(n.simplify(), a.simplify()),
+            Erf(a) => match a.simplify() {
+                Number(n) if n == 0.0 => num!(0),
+                a => erf!(a),
+            },
+            Erfc(a) => match a.simplify() {
+                Number(n) if n == 0.0 => num!(1),
+                a => erfc!(a),
+            },
+            Factorial(a) => match a.simplify() {
+                Number(n) => n.factorial().map(Number).unwrap_or_else(|| factorial!(Number(n))),
+                a => factorial!(a),
+            },
+            Floor(a) => match a.simplify() {
+                Number(n) => Number(n.floor()),
+                a if a.is_integer_valued() => a,
+                a => floor!(a),
+            },
+            Ceil(a) => match a.simplify() {
+                Number(n) => Number(n.ceil()),
+                a if a.is_integer_valued() => a,
+                a => ceil!(a),
+            },
+            Round(a) => match a.simplify() {
+                Number(n) => Number(n.round()),
+                a if a.is_integer_valued() => a,
+                a => round!(a),
+            },
+            Sign(a) => match a.simplify() {
+                Number(n) => Number(n.signum()),
+                a @ Sign(_) => a,
+                a => sign!(a),
+            },
+            Min(args) => special_functions::simplify_extremum(args, false),
+            Max(args) => special_functions::simplify_extremum(args, true),
+            Mod(a, b) => match (a.simplify(), b.simplify()) {
+                (Number(a), Number(b)) if b != 0.0 => Number(a.modulo(&b)),
+                (a, b) => modulo!(a, b),
+            },
+            Function(function) => Function(function::Function {
+                name: function.name.clone(),
+                args: function.args.iter().map(|arg| arg.simplify()).collect(),
+            }),
+            Piecewise(branches, otherwise) => piecewise::simplify_piecewise(branches, otherwise),
             a => a.clone()
         }
     }
@@ -110,12 +287,102 @@ impl Expression {
             ArcCos(a) => vec![a],
             Tan(a) => vec![a],
             ArcTan(a) => vec![a],
+            Sinh(a) => vec![a],
+            ArcSinh(a) => vec![a],
+            Cosh(a) => vec![a],
+            ArcCosh(a) => vec![a],
+            Tanh(a) => vec![a],
+            ArcTanh(a) => vec![a],
             Ln(a) => vec![a],
             Abs(a) => vec![a],
+            Gamma(a) => vec![a],
+            Polygamma(n, a) => vec![n, a],
+            Erf(a) => vec![a],
+            Erfc(a) => vec![a],
+            Factorial(a) => vec![a],
+            Floor(a) => vec![a],
+            Ceil(a) => vec![a],
+            Round(a) => vec![a],
+            Sign(a) => vec![a],
+            Min(args) | Max(args) => args.iter().collect(),
+            Mod(a, b) => vec![a, b],
+            Function(function) => function.args.iter().collect(),
+            Piecewise(branches, otherwise) => branches.iter()
+                .flat_map(|(condition, value)| condition.expressions().into_iter().chain([value]))
+                .chain([&**otherwise])
+                .collect(),
             _ => vec![],
         }
     }
 
+    /// Rebuilds the expression with `f` applied to each of its direct children
+    pub fn map_children(&self, f: impl Fn(&Expression) -> Expression) -> Expression {
+        use Expression::*;
+        let boxed = |child: &Expression| Box::new(f(child));
+        match self {
+            Add(expr) => Add(add::Add(expr.0.iter().map(&f).collect())),
+            Multiply(expr) => Multiply(multiply::Multiply(expr.0.iter().map(&f).collect())),
+            Negate(expr) => Negate(negate::Negate(boxed(&expr.0))),
+            Invert(expr) => Invert(invert::Invert(boxed(&expr.0))),
+            Power(a, b) => Power(boxed(a), boxed(b)),
+            Log(a, b) => Log(boxed(a), boxed(b)),
+            Sqrt(a) => Sqrt(boxed(a)),
+            Sin(a) => Sin(boxed(a)),
+            ArcSin(a) => ArcSin(boxed(a)),
+            Cos(a) => Cos(boxed(a)),
+            ArcCos(a) => ArcCos(boxed(a)),
+            Tan(a) => Tan(boxed(a)),
+            ArcTan(a) => ArcTan(boxed(a)),
+            Sinh(a) => Sinh(boxed(a)),
+            ArcSinh(a) => ArcSinh(boxed(a)),
+            Cosh(a) => Cosh(boxed(a)),
+            ArcCosh(a) => ArcCosh(boxed(a)),
+            Tanh(a) => Tanh(boxed(a)),
+            ArcTanh(a) => ArcTanh(boxed(a)),
+            Ln(a) => Ln(boxed(a)),
+            Abs(a) => Abs(boxed(a)),
+            Gamma(a) => Gamma(boxed(a)),
+            Polygamma(n, a) => Polygamma(boxed(n), boxed(a)),
+            Erf(a) => Erf(boxed(a)),
+            Erfc(a) => Erfc(boxed(a)),
+            Factorial(a) => Factorial(boxed(a)),
+            Floor(a) => Floor(boxed(a)),
+            Ceil(a) => Ceil(boxed(a)),
+            Round(a) => Round(boxed(a)),
+            Sign(a) => Sign(boxed(a)),
+            Min(args) => Min(args.iter().map(&f).collect()),
+            Max(args) => Max(args.iter().map(&f).collect()),
+            Mod(a, b) => Mod(boxed(a), boxed(b)),
+            Function(function) => Function(function::Function {
+                name: function.name.clone(),
+                args: function.args.iter().map(&f).collect(),
+            }),
+            Piecewise(branches, otherwise) => Piecewise(
+                branches.iter().map(|(condition, value)| (condition.map_expressions(&f), f(value))).collect(),
+                boxed(otherwise),
+            ),
+            Constant(_) | Number(_) | Variable(_) => self.clone(),
+        }
+    }
+
+    /// Names of all variables in the expression, in order of first appearance
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<String>) {
+        match self {
+            Expression::Variable(name) => {
+                if !variables.contains(name) {
+                    variables.push(name.clone());
+                }
+            }
+            _ => self.children().into_iter().for_each(|child| child.collect_variables(variables)),
+        }
+    }
+
     pub fn contains_variable(&self, variable: &str) -> bool {
         use Expression::*;
         match self {
@@ -125,7 +392,9 @@ impl Expression {
             Add(add) => add.0.iter().any(|child| child.contains_variable(variable)),
             Multiply(multiply) => multiply.0.iter().any(|child| child.contains_variable(variable)),
             Power(left, right) |
-            Log(left, right) => left.contains_variable(variable) || right.contains_variable(variable),
+            Log(left, right) |
+            Polygamma(left, right) |
+            Mod(left, right) => left.contains_variable(variable) || right.contains_variable(variable),
             Sqrt(inner) |
             Sin(inner) |
             ArcSin(inner) |
@@ -133,10 +402,28 @@ impl Expression {
             ArcCos(inner) |
             Tan(inner) |
             ArcTan(inner) |
+            Sinh(inner) |
+            ArcSinh(inner) |
+            Cosh(inner) |
+            ArcCosh(inner) |
+            Tanh(inner) |
+            ArcTanh(inner) |
             Ln(inner) |
-            Abs(inner) => inner.contains_variable(variable),
+            Abs(inner) |
+            Gamma(inner) |
+            Erf(inner) |
+            Erfc(inner) |
+            Factorial(inner) |
+            Floor(inner) |
+            Ceil(inner) |
+            Round(inner) |
+            Sign(inner) => inner.contains_variable(variable),
+            Min(args) | Max(args) => args.iter().any(|arg| arg.contains_variable(variable)),
             Negate(negate) => negate.0.contains_variable(variable),
             Invert(invert) => invert.0.contains_variable(variable),
+            Function(function) => function.args.iter().any(|arg| arg.contains_variable(variable)),
+            Piecewise(branches, otherwise) => otherwise.contains_variable(variable) || branches.iter()
+                .any(|(condition, value)| condition.contains_variable(variable) || value.contains_variable(variable)),
         }
     }
 }
@@ -157,4 +444,11 @@ mod tests {
         let variables = HashMap::from([("x".to_string(), 2.0)] );
         assert_eq!(input.solve(Some(&variables)).unwrap(), 1.0);
     }
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(sqrt!(num!(8)).simplify(), mul!(num!(2), sqrt!(num!(2))));
+        assert_eq!(sqrt!(num!(9, 4)).simplify(), num!(3, 2));
+        assert_eq!((num!(1, 2) * sqrt!(num!(8))).simplify(), sqrt!(num!(2)));
+        assert_eq!(sqrt!(num!(7)).simplify(), sqrt!(num!(7)));
+    }
 }
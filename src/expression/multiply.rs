@@ -1,73 +1,105 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 use rustc_hash::FxHashMap;
-use crate::expression::{Expression, invert, multiply, number, Operand};
-use crate::expression::display::parenthesize_if_of_type;
+use crate::expression::{Expression, invert, multiply, negate, number, ordering, Operand};
+use crate::expression::display::{write_with_precedence, Precedence};
+use crate::expression::context::Context;
 use crate::expression::error::ExpressionError;
-use crate::{inv, num, pow};
+use crate::{inv, neg, num, pow};
 use crate::utils::insert_or_add::InsertOrAdd;
 
-#[derive(Clone, Debug, PartialEq, Hash, Eq)]
+#[derive(Clone, Debug)]
 pub struct Multiply(pub Vec<Expression>);
 
+/// Operands are compared in canonical order, so that the order they were written in does not matter
+impl PartialEq for Multiply {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Multiply {}
+
+impl Ord for Multiply {
+    fn cmp(&self, other: &Self) -> Ordering {
+        ordering::sorted(&self.0).cmp(&ordering::sorted(&other.0))
+    }
+}
+
+impl PartialOrd for Multiply {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Multiply {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ordering::sorted(&self.0).hash(state)
+    }
+}
+
 impl Operand for Multiply{
-    fn solve(&self, variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError> {
+    fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError> {
         let results: Result<Vec<f64>, _> = self.0.iter()
-            .map(|child| child.solve(variables))
+            .map(|child| child.evaluate(context))
             .collect();
         Ok(results?.iter().product())
     }
 
-    fn operand_count(&self) -> usize{ 2 }
-
     fn children(&self) -> Vec<&Expression> {
         self.0.iter().collect()
     }
     fn simplify(&self) -> Expression {
-        let mut number_product: f64 = 1.0;
+        let mut number_product = number::Number::from(1);
         let mut new_children: Vec<Expression> = Vec::new();
         use Expression::*;
-        let children: Vec<Expression> = self.0.iter()
-            .flat_map(|child| {
-                match child.simplify(){
-                    Multiply(multiply::Multiply(children)) => children, // Merge inner multiplies
-                    other => vec![other]
-                }})
-            .collect();
+        let mut children: Vec<Expression> = Vec::new();
+        for child in &self.0 {
+            collect_factors(child.simplify(), &mut children, &mut number_product);
+        }
 
-        let mut multiplications: FxHashMap<Expression, f64> = FxHashMap::default();
+        let mut multiplications: FxHashMap<Expression, number::Number> = FxHashMap::default();
         for child in children {
-            match &child {
-                Number(number::Number(num)) => {
-                    if num == &0.0 {
-                        return num!(0.0);
+            match child {
+                Number(num) => {
+                    if num == 0.0 {
+                        return num!(0);
                     }
                     number_product *= num;
                 },
-                Invert(invert::Invert(invert)) => multiplications.insert_or_add(*invert.clone(), -1.0),
-                Power(ref base, ref exponent) => {
-                    if let Number(number::Number(num)) = exponent.as_ref() {
-                        multiplications.insert_or_add(*base.clone(), *num);
-                    } else {
-                        multiplications.insert_or_add(child, 1.0);
-                    }
+                child => {
+                    let (base, count) = base_and_exponent(child);
+                    multiplications.insert_or_add(base, count)
                 }
-                _ => multiplications.insert_or_add(child, 1.0)
             }
         }
         for (multiplication, count) in multiplications.into_iter(){
-            if count == 1. {
+            if count == 1.0 {
                 new_children.push(multiplication);
-            } else if count == -1.{
+            } else if count == -1.0 {
                 new_children.push(inv!(multiplication));
-            } else if count != 0.{
+            } else if count != 0.0 {
                 new_children.push(pow!(multiplication, num!(count)));
             }
         }
 
+        if new_children.is_empty() {
+            return num!(number_product);
+        }
+
+        if number_product == -1.0 {
+            let remainder = match new_children.len() {
+                1 => new_children.remove(0),
+                _ => Multiply(Self(new_children)),
+            };
+            return neg!(remainder);
+        }
+
         if number_product != 1.0 {
             new_children.push(num!(number_product));
         }
+        new_children.sort();
 
         if new_children.len() == 1{
             return new_children[0].clone();
@@ -77,6 +109,39 @@ impl Operand for Multiply{
     }
 }
 
+/// Flattens nested multiplications and pulls negations out into the numeric factor
+fn collect_factors(expression: Expression, factors: &mut Vec<Expression>, number_product: &mut number::Number) {
+    use Expression::*;
+    match expression {
+        Multiply(multiply::Multiply(children)) => {
+            for child in children {
+                collect_factors(child, factors, number_product);
+            }
+        }
+        Negate(negate::Negate(inner)) => {
+            *number_product = -number_product.clone();
+            collect_factors(*inner, factors, number_product);
+        }
+        other => factors.push(other),
+    }
+}
+
+/// Splits a factor into its base and numeric exponent, so that `a`, `1 / a` and `a ^ 2` can be combined
+fn base_and_exponent(expression: Expression) -> (Expression, number::Number) {
+    use Expression::*;
+    match expression {
+        Power(base, exponent) => match *exponent {
+            Number(num) => (*base, num),
+            exponent => (pow!(*base, exponent), number::Number::from(1)),
+        },
+        Invert(invert::Invert(inner)) => {
+            let (base, count) = base_and_exponent(*inner);
+            (base, -count)
+        }
+        other => (other, number::Number::from(1)),
+    }
+}
+
 impl Multiply{
     pub fn not_containing_variable(&self, variable: &str) -> Vec<Expression>{
         self.0.iter()
@@ -89,20 +154,20 @@ impl Multiply{
 impl Display for Multiply{
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         use Expression::*;
-        if self.0.is_empty(){
-            return write!(f, "0");
-        }
-        write!(f, "{}", self.0[0])?;
-
-        if self.0.len() == 1{
-            return Ok(());
-        }
-
-        for child in &self.0[1..]{
-            if matches!(child, Invert(..)){
-                write!(f, " / {}", parenthesize_if_of_type!(child, Add(..)))?;
-            } else {
-                write!(f, " * {}", parenthesize_if_of_type!(child, Add(..)))?;
+        let Some((first, rest)) = self.0.split_first() else {
+            return write!(f, "1");
+        };
+        write_with_precedence(f, first, Precedence::Product)?;
+        for child in rest {
+            match child {
+                Invert(invert) => {
+                    write!(f, " / ")?;
+                    write_with_precedence(f, &invert.0, Precedence::Unary)?;
+                }
+                child => {
+                    write!(f, " * ")?;
+                    write_with_precedence(f, child, Precedence::Unary)?;
+                }
             }
         }
         Ok(())
@@ -118,7 +183,7 @@ mod tests{
         let expression = num!(1.0) * var!("a");
         assert_eq!(expression.simplify(), var!("a"));
         let expression = num!(2.0) / num!(3.0);
-        assert_eq!(expression.simplify(), num!(2.0/3.0));
+        assert_eq!(expression.simplify(), num!(2, 3));
         let expression = num!(5) * var!("a") * pow!(var!("a"), num!(5)) * num!(4) / var!("a");
         assert_eq!(expression.simplify(), pow!(var!("a"), num!(5)) * num!(20));
     }
@@ -1,21 +1,18 @@
 use std::borrow::Borrow;
-use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use crate::expression::context::Context;
 use crate::expression::error::ExpressionError;
-use crate::expression::{Expression, number, Operand};
-use crate::expression::display::parenthesize_if_of_type;
-use crate::num;
+use crate::expression::{Expression, Operand};
+use crate::expression::display::{write_with_precedence, Precedence};
+use crate::{mul, neg, num};
 
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct Negate(pub Box<Expression>);
 
 impl Operand for Negate{
-    fn solve(&self, variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError> {
-        Ok(-self.0.solve(variables)?)
-    }
-    fn operand_count(&self) -> usize {
-        1
+    fn evaluate(&self, context: &Context) -> Result<f64, ExpressionError> {
+        Ok(-self.0.evaluate(context)?)
     }
 
     fn children(&self) -> Vec<&Expression> {
@@ -25,9 +22,10 @@ impl Operand for Negate{
     fn simplify(&self) -> Expression {
         use crate::expression::Expression::*;
         match self.0.simplify() {
-            Number(number::Number(a)) => num!(-a),
+            Number(a) => Number(-a),
             Negate(a) => *a.0, // Double negative
-            a => a,
+            Multiply(multiply) => mul!(Multiply(multiply), num!(-1)).simplify(),
+            a => neg!(a),
         }
     }
 }
@@ -35,8 +33,18 @@ impl Operand for Negate{
 
 impl Display for Negate{
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        use Expression::*;
-        let a: String = parenthesize_if_of_type!(*self.0, Add(..) | Multiply(..) | Power(..) | Negate(..));
-        write!(f, "-{a}")
+        write!(f, "-")?;
+        write_with_precedence(f, &self.0, Precedence::Power)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::{neg, num, var};
+    #[test]
+    fn test_simplification(){
+        assert_eq!(neg!(var!("a")).simplify(), neg!(var!("a")));
+        assert_eq!(neg!(neg!(var!("a"))).simplify(), var!("a"));
+        assert_eq!(neg!(num!(2) * var!("a")).simplify(), var!("a") * num!(-2));
+    }
+}
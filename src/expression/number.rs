@@ -1,30 +1,235 @@
-use std::collections::HashMap;
 use std::fmt::Display;
 use std::hash;
 use std::hash::Hash;
 use std::ops::Neg;
+use std::str::FromStr;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use crate::expression::context::Context;
 use crate::expression::error::ExpressionError;
 use crate::expression::{Expression, Operand};
-use crate::num;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Number(pub f64);
+/// Floats whose shortest decimal form needs more significant digits than this are not converted to rationals,
+/// so that `1.0 / 3.0` stays a float instead of becoming 3333333333333333 / 10000000000000000
+const MAX_EXACT_DIGITS: usize = 15;
 
-impl Operand for Number{
-    fn solve(&self, _variables: Option<&HashMap<String, f64>>) -> Result<f64, ExpressionError> {
-        Ok(self.0)
+/// Powers with larger integer exponents, roots of higher degree and literals with larger
+/// decimal exponents are computed as floats
+const MAX_EXACT_EXPONENT: u32 = 1024;
+
+/// Floats below this in magnitude are integers exactly when they have no fraction, and fit in an i64
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Largest factor whose square is pulled out of a square root, which bounds the trial division
+const SQUARE_FACTOR_LIMIT: u32 = 10_000;
+
+#[derive(Debug, Clone)]
+pub enum Number {
+    /// An exact ratio of arbitrary-precision integers
+    Rational(BigRational),
+    /// A 64bit floating point number, for results that cannot be represented exactly
+    Float(f64),
+}
+
+impl Number {
+    pub fn integer(value: impl Into<BigInt>) -> Self {
+        Number::Rational(BigRational::from_integer(value.into()))
+    }
+
+    /// The exact fraction numerator / denominator, or a float infinity when the denominator is zero
+    pub fn rational(numerator: impl Into<BigInt>, denominator: impl Into<BigInt>) -> Self {
+        let (numerator, denominator) = (numerator.into(), denominator.into());
+        if denominator.is_zero() {
+            return Number::Float(numerator.to_f64().unwrap_or(f64::NAN) / 0.0);
+        }
+        Number::Rational(BigRational::new(numerator, denominator))
+    }
+
+    pub fn float(value: f64) -> Self {
+        Number::Float(value)
+    }
+
+    /// The nearest float to the number
+    pub fn value(&self) -> f64 {
+        match self {
+            Number::Rational(rational) => rational.to_f64().unwrap_or(f64::NAN),
+            Number::Float(float) => *float,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Number::Rational(..))
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Rational(rational) => rational.is_integer(),
+            Number::Float(float) => float.fract() == 0.0,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.value() < 0.0
+    }
+
+    /// The number as an i128, if it is an integer that fits
+    pub fn to_i128(&self) -> Option<i128> {
+        match self {
+            Number::Rational(rational) if rational.is_integer() => rational.numer().to_i128(),
+            Number::Float(float) if float.fract() == 0.0 && float.abs() < 1e15 => Some(*float as i128),
+            _ => None,
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        match self {
+            Number::Rational(rational) => Number::Rational(rational.abs()),
+            Number::Float(float) => Number::Float(float.abs()),
+        }
+    }
+
+    /// Raises the number to a power, exactly when both are rational and the result is too
+    pub fn pow(&self, exponent: &Number) -> Self {
+        if let (Number::Rational(base), Number::Rational(exponent)) = (self, exponent) {
+            let root = exponent.denom().to_u32()
+                .filter(|degree| *degree <= MAX_EXACT_EXPONENT)
+                .and_then(|degree| exact_root(base, degree));
+            let power = exponent.numer().abs().to_u32().filter(|power| *power <= MAX_EXACT_EXPONENT);
+            if let (Some(root), Some(power)) = (root, power) {
+                let result = num_traits::pow(root, power as usize);
+                return match exponent.is_negative() {
+                    true if result.is_zero() => Number::Float(f64::INFINITY),
+                    true => Number::Rational(result.recip()),
+                    false => Number::Rational(result),
+                };
+            }
+        }
+        Number::Float(self.value().powf(exponent.value()))
     }
 
-    fn operand_count(&self) -> usize {
-        0
+    /// The square root, when it is rational
+    pub fn sqrt(&self) -> Option<Self> {
+        match self {
+            Number::Rational(rational) => exact_root(rational, 2).map(Number::Rational),
+            Number::Float(_) => None,
+        }
+    }
+
+    /// Splits the square root of a positive exact number into `coefficient * sqrt(radicand)`
+    /// with the radicand an integer that has no square factors up to `SQUARE_FACTOR_LIMIT`, like sqrt(8) = 2 * sqrt(2)
+    pub fn split_sqrt(&self) -> Option<(Self, Self)> {
+        let Number::Rational(rational) = self else {
+            return None;
+        };
+        if !rational.is_positive() {
+            return None;
+        }
+        // sqrt(p / q) = sqrt(p * q) / q
+        let mut radicand = rational.numer() * rational.denom();
+        let mut coefficient = BigInt::one();
+        let mut factor = BigInt::from(2);
+        while factor <= BigInt::from(SQUARE_FACTOR_LIMIT) && &factor * &factor <= radicand {
+            let square = &factor * &factor;
+            while (&radicand % &square).is_zero() {
+                radicand /= &square;
+                coefficient *= &factor;
+            }
+            factor += 1;
+        }
+        Some((Number::Rational(BigRational::new(coefficient, rational.denom().clone())), Number::integer(radicand)))
+    }
+
+    pub fn floor(&self) -> Self {
+        match self {
+            Number::Rational(rational) => Number::Rational(rational.floor()),
+            Number::Float(float) => Number::Float(float.floor()),
+        }
+    }
+
+    pub fn ceil(&self) -> Self {
+        match self {
+            Number::Rational(rational) => Number::Rational(rational.ceil()),
+            Number::Float(float) => Number::Float(float.ceil()),
+        }
+    }
+
+    /// The nearest integer, rounding halves away from zero
+    pub fn round(&self) -> Self {
+        match self {
+            Number::Rational(rational) => Number::Rational(rational.round()),
+            Number::Float(float) => Number::Float(float.round()),
+        }
+    }
+
+    /// -1, 0 or 1 by the sign of the number, unlike `f64::signum` which is 1 for zero
+    pub fn signum(&self) -> Self {
+        match self {
+            Number::Rational(rational) => Number::Rational(rational.signum()),
+            Number::Float(float) if *float == 0.0 => Number::Float(0.0),
+            Number::Float(float) => Number::Float(float.signum()),
+        }
+    }
+
+    /// The remainder of a floored division, which has the sign of the divisor like in mathematics
+    pub fn modulo(&self, divisor: &Number) -> Self {
+        self.clone() - divisor.clone() * (self.clone() / divisor.clone()).floor()
+    }
+
+    /// n! of an exact non-negative integer up to `MAX_EXACT_EXPONENT`
+    pub fn factorial(&self) -> Option<Self> {
+        let n = self.to_i128().filter(|n| self.is_exact() && (0..=MAX_EXACT_EXPONENT as i128).contains(n))?;
+        Some(Number::integer((1..=n).map(BigInt::from).product::<BigInt>()))
+    }
+}
+
+/// The rational number whose `degree`th power is `value`, if there is one
+fn exact_root(value: &BigRational, degree: u32) -> Option<BigRational> {
+    if degree == 1 {
+        return Some(value.clone());
+    }
+    if value.is_negative() && degree.is_multiple_of(2) {
+        return None;
+    }
+    let root = |integer: &BigInt| {
+        let root = integer.nth_root(degree);
+        (num_traits::pow(root.clone(), degree as usize) == *integer).then_some(root)
+    };
+    Some(BigRational::new(root(value.numer())?, root(value.denom())?))
+}
+
+impl Operand for Number{
+    fn evaluate(&self, _context: &Context) -> Result<f64, ExpressionError> {
+        Ok(self.value())
     }
 
+
     fn children(&self) -> Vec<&Expression> {
         vec![]
     }
 
     fn simplify(&self) -> Expression {
-       num!(self.0)
+       Expression::Number(self.clone())
+    }
+}
+
+/// The float a float number is compared and hashed by, with -0.0 read as 0.0 and every NaN as the same NaN,
+/// so that equality, hashing and ordering agree
+fn float_key(float: f64) -> f64 {
+    match float {
+        float if float.is_nan() => f64::NAN,
+        0.0 => 0.0,
+        float => float,
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Number::Rational(a), Number::Rational(b)) => a == b,
+            (Number::Float(a), Number::Float(b)) => float_key(*a).to_bits() == float_key(*b).to_bits(),
+            _ => false,
+        }
     }
 }
 
@@ -33,27 +238,87 @@ impl Hash for Number {
         where
             H: hash::Hasher,
     {
-        self.0.to_bits().hash(state)
+        match self {
+            Number::Rational(rational) => rational.hash(state),
+            Number::Float(float) => float_key(*float).to_bits().hash(state),
+        }
     }
 }
 
 impl Eq for Number {}
 
+/// Orders by value, with exact numbers before floats of the same value and NaN after everything
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Number::Rational(a), Number::Rational(b)) => a.cmp(b),
+            (a, b) => float_key(a.value()).total_cmp(&float_key(b.value())).then(b.is_exact().cmp(&a.is_exact())),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Display for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.0.fract() == 0.0 {
-            write!(f, "{}", self.0 as i64)
-        } else {
-            write!(f, "{}", self.0)
+        match self {
+            Number::Rational(rational) if rational.is_integer() => write!(f, "{}", rational.numer()),
+            Number::Rational(rational) => write!(f, "{}/{}", rational.numer(), rational.denom()),
+            // Forms the parser reads back, parenthesized so that they stay atoms
+            Number::Float(float) if float.is_nan() => write!(f, "(0/0)"),
+            Number::Float(float) if float.is_infinite() && *float > 0.0 => write!(f, "(1/0)"),
+            Number::Float(float) if float.is_infinite() => write!(f, "(-1/0)"),
+            Number::Float(float) if float.fract() == 0.0 && float.abs() < MAX_SAFE_INTEGER => write!(f, "{}", *float as i64),
+            Number::Float(float) if float.fract() == 0.0 => write!(f, "{float:e}"),
+            Number::Float(float) => write!(f, "{float}"),
         }
     }
 }
 
+/// Parses decimal literals like `12`, `0.25` and `1.5e-3` into exact rationals
+impl FromStr for Number {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let float = || input.parse().map(Number::Float).map_err(|_| ());
+        let (mantissa, exponent) = match input.find(['e', 'E']) {
+            Some(index) => match input[index + 1..].parse::<i32>() {
+                Ok(exponent) => (&input[..index], exponent),
+                // An exponent too large for an i32 overflows or underflows a float as well
+                Err(_) => return float(),
+            },
+            None => (input, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{integer}{fraction}");
+        if digits.is_empty() || digits == "-" || digits == "+" {
+            return Err(());
+        }
+        let numerator = BigInt::from_str(&digits).map_err(|_| ())?;
+        let scale = match i32::try_from(fraction.len()).ok().and_then(|length| exponent.checked_sub(length)) {
+            Some(scale) if scale.unsigned_abs() <= MAX_EXACT_EXPONENT => scale,
+            _ => return float(),
+        };
+        let power = num_traits::pow(BigInt::from(10), scale.unsigned_abs() as usize);
+        Ok(match scale < 0 {
+            true => Number::Rational(BigRational::new(numerator, power)),
+            false => Number::integer(numerator * power),
+        })
+    }
+}
+
 impl Neg for Number{
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        Self(-self.0)
+        match self {
+            Number::Rational(rational) => Number::Rational(-rational),
+            Number::Float(float) => Number::Float(-float),
+        }
     }
 }
 
@@ -61,7 +326,10 @@ impl std::ops::Add for Number{
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self(self.0 + rhs.0)
+        match (self, rhs) {
+            (Number::Rational(a), Number::Rational(b)) => Number::Rational(a + b),
+            (a, b) => Number::Float(a.value() + b.value()),
+        }
     }
 }
 
@@ -69,7 +337,7 @@ impl std::ops::Sub for Number{
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self(self.0 - rhs.0)
+        self + -rhs
     }
 }
 
@@ -77,7 +345,10 @@ impl std::ops::Mul for Number{
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Self(self.0 * rhs.0)
+        match (self, rhs) {
+            (Number::Rational(a), Number::Rational(b)) => Number::Rational(a * b),
+            (a, b) => Number::Float(a.value() * b.value()),
+        }
     }
 }
 
@@ -85,7 +356,10 @@ impl std::ops::Div for Number{
     type Output = Self;
 
     fn div(self, rhs: Self) -> Self::Output {
-        Self(self.0 / rhs.0)
+        match (self, rhs) {
+            (Number::Rational(a), Number::Rational(b)) if !b.is_zero() => Number::Rational(a / b),
+            (a, b) => Number::Float(a.value() / b.value()),
+        }
     }
 }
 
@@ -93,66 +367,207 @@ impl std::ops::Rem for Number{
     type Output = Self;
 
     fn rem(self, rhs: Self) -> Self::Output {
-        Self(self.0 % rhs.0)
+        match (self, rhs) {
+            (Number::Rational(a), Number::Rational(b)) if !b.is_zero() => Number::Rational(a % b),
+            (a, b) => Number::Float(a.value() % b.value()),
+        }
     }
 }
 
 impl std::ops::AddAssign for Number{
     fn add_assign(&mut self, rhs: Self) {
-        self.0 += rhs.0;
+        *self = self.clone() + rhs;
     }
 }
 
 impl std::ops::SubAssign for Number{
     fn sub_assign(&mut self, rhs: Self) {
-        self.0 -= rhs.0;
+        *self = self.clone() - rhs;
     }
 }
 
 impl std::ops::MulAssign for Number{
     fn mul_assign(&mut self, rhs: Self) {
-        self.0 *= rhs.0;
+        *self = self.clone() * rhs;
     }
 }
 
 impl std::ops::DivAssign for Number{
     fn div_assign(&mut self, rhs: Self) {
-        self.0 /= rhs.0;
+        *self = self.clone() / rhs;
     }
 }
 
 impl std::ops::RemAssign for Number{
     fn rem_assign(&mut self, rhs: Self) {
-        self.0 %= rhs.0;
+        *self = self.clone() % rhs;
     }
 }
 
+/// Floats are converted to exact rationals when their shortest decimal form is short enough,
+/// so `0.1` becomes exactly 1/10 while `1.0 / 3.0` stays a float
 impl From<f64> for Number{
     fn from(num: f64) -> Self {
-        Self(num)
+        if !num.is_finite() {
+            return Number::Float(num);
+        }
+        let scientific = format!("{num:e}");
+        let significant_digits = scientific.split('e').next().unwrap_or_default()
+            .chars()
+            .filter(char::is_ascii_digit)
+            .count();
+        match significant_digits <= MAX_EXACT_DIGITS {
+            true => Number::from_str(&scientific).unwrap_or(Number::Float(num)),
+            false => Number::Float(num),
+        }
+    }
+}
+
+macro_rules! from_integer {
+    ($($integer:ty),+) => {
+        $(impl From<$integer> for Number{
+            fn from(num: $integer) -> Self {
+                Number::integer(num)
+            }
+        })+
+    };
+}
+from_integer!(i32, i64, i128, u32, u64, usize, BigInt);
+
+impl From<BigRational> for Number{
+    fn from(num: BigRational) -> Self {
+        Number::Rational(num)
     }
 }
+
 // Check inequality with f64
 impl PartialEq<f64> for Number{
     fn eq(&self, other: &f64) -> bool {
-        self.0 == *other
+        self.value() == *other
     }
 }
 
 impl PartialEq<Number> for f64{
     fn eq(&self, other: &Number) -> bool {
-        *self == other.0
+        *self == other.value()
     }
 }
 
 impl PartialOrd<f64> for Number{
     fn partial_cmp(&self, other: &f64) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(other)
+        self.value().partial_cmp(other)
     }
 }
 
 impl PartialOrd<Number> for f64{
     fn partial_cmp(&self, other: &Number) -> Option<std::cmp::Ordering> {
-        self.partial_cmp(&other.0)
+        self.partial_cmp(&other.value())
+    }
+}
+
+impl One for Number {
+    fn one() -> Self {
+        Number::integer(1)
     }
-}
\ No newline at end of file
+}
+
+impl Zero for Number {
+    fn zero() -> Self {
+        Number::integer(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_arithmetic() {
+        let third = Number::rational(1, 3);
+        assert_eq!(third.clone() + third.clone() + third.clone(), Number::integer(1));
+        assert_eq!(Number::from(2) / Number::from(3), Number::rational(2, 3));
+        assert_eq!(Number::from(0.1) + Number::from(0.2), Number::from(0.3));
+        assert_eq!(Number::from(1.0 / 3.0), Number::Float(1.0 / 3.0));
+        assert!((Number::rational(1, 3) + Number::float(0.5)).value() - 5.0 / 6.0 < 1e-12);
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(Number::from(2).pow(&Number::from(10)), Number::from(1024));
+        assert_eq!(Number::rational(4, 9).pow(&Number::rational(-1, 2)), Number::rational(3, 2));
+        assert_eq!(Number::from(-8).pow(&Number::rational(1, 3)), Number::from(-2));
+        assert_eq!(Number::from(2).pow(&Number::rational(1, 2)), Number::Float(2f64.sqrt()));
+        assert_eq!(Number::from(0).pow(&Number::from(-1)), Number::Float(f64::INFINITY));
+        assert_eq!(Number::from(2).sqrt(), None);
+        assert_eq!(Number::from(8).split_sqrt(), Some((Number::from(2), Number::from(2))));
+        assert_eq!(Number::from(72).split_sqrt(), Some((Number::from(6), Number::from(2))));
+        assert_eq!(Number::rational(1, 2).split_sqrt(), Some((Number::rational(1, 2), Number::from(2))));
+        assert_eq!(Number::from(-8).split_sqrt(), None);
+    }
+
+    #[test]
+    fn test_rounding() {
+        let (a, b) = (Number::rational(-7, 2), Number::float(2.5));
+        assert_eq!((a.floor(), a.ceil(), a.round()), (Number::from(-4), Number::from(-3), Number::from(-4)));
+        assert_eq!((b.floor(), b.ceil(), b.round()), (Number::float(2.0), Number::float(3.0), Number::float(3.0)));
+        assert_eq!((a.signum(), Number::float(0.0).signum()), (Number::from(-1), Number::float(0.0)));
+        assert_eq!(Number::from(-7).modulo(&Number::from(3)), Number::from(2));
+        assert_eq!(Number::from(7).modulo(&Number::rational(-3, 2)), Number::rational(-1, 2));
+        assert_eq!(Number::from(20).factorial(), Some(Number::from(2432902008176640000u64)));
+        assert_eq!(Number::rational(1, 2).factorial(), None);
+        assert_eq!(Number::from(-1).factorial(), None);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Number::from_str("12"), Ok(Number::from(12)));
+        assert_eq!(Number::from_str("0.25"), Ok(Number::rational(1, 4)));
+        assert_eq!(Number::from_str("1.5e-3"), Ok(Number::rational(3, 2000)));
+        assert_eq!(Number::from_str("2E3"), Ok(Number::from(2000)));
+        assert_eq!(Number::from_str("e"), Err(()));
+        assert_eq!(Number::from_str("1e"), Err(()));
+        assert_eq!(Number::from_str("1.5e-2147483648"), Ok(Number::Float(0.0)));
+        assert_eq!(Number::from_str("1.5e2147483647"), Ok(Number::Float(f64::INFINITY)));
+        assert_eq!(Number::from_str("1e99999999999"), Ok(Number::Float(f64::INFINITY)));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Number::rational(-2, 4).to_string(), "-1/2");
+        assert_eq!(Number::from(3).to_string(), "3");
+        assert_eq!(Number::float(0.5).to_string(), "0.5");
+        assert_eq!(Number::float(-3.0).to_string(), "-3");
+        assert_eq!(Number::float(1e30).to_string(), "1e30");
+        assert_eq!(Number::float(-1e20).to_string(), "-1e20");
+        assert_eq!(Number::float(f64::INFINITY).to_string(), "(1/0)");
+        assert_eq!(Number::float(f64::NEG_INFINITY).to_string(), "(-1/0)");
+        assert_eq!(Number::float(f64::NAN).to_string(), "(0/0)");
+    }
+
+    #[test]
+    fn test_float_identity() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+        let hash = |number: &Number| {
+            let mut hasher = DefaultHasher::new();
+            number.hash(&mut hasher);
+            hasher.finish()
+        };
+        let (zero, negative_zero) = (Number::float(0.0), Number::float(-0.0));
+        assert_eq!(zero, negative_zero);
+        assert_eq!(hash(&zero), hash(&negative_zero));
+        assert_eq!(zero.cmp(&negative_zero), std::cmp::Ordering::Equal);
+
+        let (nan, other_nan) = (Number::float(f64::NAN), Number::float(-f64::NAN));
+        assert_eq!(nan, other_nan);
+        assert_eq!(hash(&nan), hash(&other_nan));
+        assert_eq!(nan.cmp(&other_nan), std::cmp::Ordering::Equal);
+        assert!(nan > Number::float(f64::INFINITY));
+        assert_ne!(Number::from(1), Number::float(1.0));
+        assert_ne!(Number::from(1).cmp(&Number::float(1.0)), std::cmp::Ordering::Equal);
+    }
+}
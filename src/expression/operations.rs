@@ -50,7 +50,6 @@ impl Sub<Expression> for Expression{
         match (self, rhs){
             (Add(add), Add(add2)) => Add(add::Add(add.0.into_iter().chain(add2.0.into_iter().map(|child| neg!(child))).collect())),
             (Add(add), rhs) => Add(add::Add(add.0.into_iter().chain(vec![neg!(rhs)]).collect())),
-            (lhs, Add(add)) => Add(add::Add(add.0.into_iter().chain(vec![neg!(lhs)]).collect())),
             (lhs, rhs) => Add(add::Add(vec![lhs, neg!(rhs)]))
         }
     }
@@ -74,4 +73,17 @@ impl From<&str> for Expression{
     fn from(string: &str) -> Self {
         Expression::Variable(string.into())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::{num, var};
+
+    #[test]
+    fn test_sub() {
+        let variables = HashMap::from([("a", 5.0), ("b", 3.0), ("c", 1.0)].map(|(k, v)| (k.to_string(), v)));
+        assert_eq!((var!("a") - (var!("b") + var!("c"))).solve(Some(&variables)), Ok(1.0));
+        assert_eq!(((var!("a") + var!("b")) - var!("c")).solve(Some(&variables)), Ok(7.0));
+        assert_eq!(((var!("a") + var!("b")) - (var!("c") + num!(2))).solve(Some(&variables)), Ok(5.0));
+    }
+}
@@ -0,0 +1,160 @@
+use std::cmp::Ordering;
+
+use crate::expression::Expression;
+
+/// A total order over expressions, used to put the children of sums and products in a canonical order.
+/// Numbers come first, then constants, then variables alphabetically, then compound expressions.
+/// Powers sort right after their base, so that `x * x^2 * y` keeps x and its powers together.
+impl Ord for Expression {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use Expression::*;
+        match (self, other) {
+            (Power(a, n), Power(b, m)) => a.cmp(b).then_with(|| n.cmp(m)),
+            (Power(a, _), b) => (**a).cmp(b).then(Ordering::Greater),
+            (a, Power(b, _)) => a.cmp(b).then(Ordering::Less),
+            (Number(a), Number(b)) => a.cmp(b),
+            (Constant(a), Constant(b)) => a.cmp(b),
+            (Variable(a), Variable(b)) => a.cmp(b),
+            (Add(a), Add(b)) => a.cmp(b),
+            (Multiply(a), Multiply(b)) => a.cmp(b),
+            (Function(a), Function(b)) => a.cmp(b),
+            // The conditions are not among the children, so they are compared here
+            (Piecewise(a, x), Piecewise(b, y)) => a.cmp(b).then_with(|| x.cmp(y)),
+            _ => self.rank().cmp(&other.rank()).then_with(|| self.children().cmp(&other.children())),
+        }
+    }
+}
+
+impl PartialOrd for Expression {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Expression {
+    fn rank(&self) -> u8 {
+        use Expression::*;
+        match self {
+            Number(_) => 0,
+            Constant(_) => 1,
+            Variable(_) => 2,
+            Power(..) => 3,
+            Sqrt(_) => 4,
+            Log(..) => 5,
+            Sin(_) => 6,
+            ArcSin(_) => 7,
+            Cos(_) => 8,
+            ArcCos(_) => 9,
+            Tan(_) => 10,
+            ArcTan(_) => 11,
+            Sinh(_) => 12,
+            ArcSinh(_) => 13,
+            Cosh(_) => 14,
+            ArcCosh(_) => 15,
+            Tanh(_) => 16,
+            ArcTanh(_) => 17,
+            Ln(_) => 18,
+            Abs(_) => 19,
+            Gamma(_) => 20,
+            Polygamma(..) => 21,
+            Erf(_) => 22,
+            Erfc(_) => 23,
+            Factorial(_) => 24,
+            Floor(_) => 25,
+            Ceil(_) => 26,
+            Round(_) => 27,
+            Sign(_) => 28,
+            Min(_) => 29,
+            Max(_) => 30,
+            Mod(..) => 31,
+            Function(_) => 32,
+            Piecewise(..) => 33,
+            Multiply(_) => 34,
+            Add(_) => 35,
+            Negate(_) => 36,
+            Invert(_) => 37,
+        }
+    }
+}
+
+/// The children in canonical order, so that sums and products compare equal regardless of operand order
+pub(crate) fn sorted(children: &[Expression]) -> Vec<&Expression> {
+    let mut sorted: Vec<&Expression> = children.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+/// Order of the terms of a sum: highest degree first and numbers last, like x^2 + 2 * x + 1
+pub(crate) fn compare_terms(a: &Expression, b: &Expression) -> Ordering {
+    degree(b).total_cmp(&degree(a))
+        .then_with(|| a.variables().is_empty().cmp(&b.variables().is_empty()))
+        .then_with(|| non_numeric_factors(a).cmp(&non_numeric_factors(b)))
+        .then_with(|| a.cmp(b))
+}
+
+/// Total degree of a term in all of its variables
+fn degree(term: &Expression) -> f64 {
+    use Expression::*;
+    match term {
+        Variable(_) => 1.0,
+        Power(base, exponent) => match &**exponent {
+            Number(exponent) => degree(base) * exponent.value(),
+            _ => 0.0,
+        },
+        Multiply(multiply) => multiply.0.iter().map(degree).sum(),
+        Negate(negate) => degree(&negate.0),
+        Invert(invert) => -degree(&invert.0),
+        _ => 0.0,
+    }
+}
+
+fn non_numeric_factors(term: &Expression) -> Vec<&Expression> {
+    use Expression::*;
+    match term {
+        Negate(negate) => non_numeric_factors(&negate.0),
+        Multiply(multiply) => sorted(&multiply.0).into_iter().filter(|factor| !matches!(factor, Number(_))).collect(),
+        Number(_) => vec![],
+        other => vec![other],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compare, cos, mul, num, piecewise, pow, sin, var};
+    use super::*;
+
+    #[test]
+    fn test_order_insensitive_equality() {
+        assert_eq!(var!("a") + var!("b"), var!("b") + var!("a"));
+        assert_eq!(mul!(var!("a"), num!(2), var!("b")), mul!(var!("b"), var!("a"), num!(2)));
+        assert_ne!(var!("a") + var!("b"), var!("a") + var!("a"));
+        assert_ne!(var!("a") - var!("b"), var!("b") - var!("a"));
+
+        let x = || var!("x");
+        let negative = piecewise!((compare!(x(), <, num!(0)), num!(1)); num!(2));
+        let positive = piecewise!((compare!(x(), >, num!(0)), num!(1)); num!(2));
+        assert_ne!(negative.clone() + var!("y"), positive.clone() + var!("y"));
+        assert_eq!((negative.clone() + positive.clone()).simplify(), (positive + negative).simplify());
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        let Expression::Multiply(product) = (var!("y") * sin!(var!("x")) * num!(3) * pow!(var!("x"), num!(2))).simplify() else {
+            panic!("expected a product");
+        };
+        assert_eq!(product.0, vec![num!(3), pow!(var!("x"), num!(2)), var!("y"), sin!(var!("x"))]);
+
+        let Expression::Add(sum) = (num!(1) + cos!(var!("x")) + var!("x") + pow!(var!("x"), num!(2))).simplify() else {
+            panic!("expected a sum");
+        };
+        assert_eq!(sum.0, vec![pow!(var!("x"), num!(2)), var!("x"), cos!(var!("x")), num!(1)]);
+    }
+
+    #[test]
+    fn test_deterministic_display() {
+        let expression = var!("c") * var!("b") + var!("a") * num!(2) + var!("d");
+        let displayed = expression.simplify().to_string();
+        let reordered = var!("d") + num!(2) * var!("a") + var!("b") * var!("c");
+        assert_eq!(reordered.simplify().to_string(), displayed);
+    }
+}
@@ -0,0 +1,330 @@
+use core::fmt;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use crate::expression::context::Context;
+use crate::expression::error::ExpressionError;
+use crate::expression::Expression;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    /// The comparison for an operator token, where `≤`, `≥` and `≠` also stand for `<=`, `>=` and `!=`
+    pub(crate) fn from_operator(operator: char) -> Option<Self> {
+        Some(match operator {
+            '<' => Comparison::Less,
+            '≤' => Comparison::LessEqual,
+            '>' => Comparison::Greater,
+            '≥' => Comparison::GreaterEqual,
+            '=' => Comparison::Equal,
+            '≠' => Comparison::NotEqual,
+            _ => return None,
+        })
+    }
+
+    /// Whether the comparison holds for operands that are ordered like `ordering`
+    pub fn holds_for(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterEqual => ordering.is_ge(),
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::NotEqual => ordering.is_ne(),
+        }
+    }
+
+    /// Whether `a` compares to `b` like this, which is false for NaN except for `NotEqual`
+    pub fn holds(self, a: f64, b: f64) -> bool {
+        match a.partial_cmp(&b) {
+            Some(ordering) => self.holds_for(ordering),
+            None => self == Comparison::NotEqual,
+        }
+    }
+
+    /// The comparison with its operands swapped, so that `a < b` becomes `b > a`
+    pub fn reversed(self) -> Self {
+        match self {
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessEqual => Comparison::GreaterEqual,
+            Comparison::Greater => Comparison::Less,
+            Comparison::GreaterEqual => Comparison::LessEqual,
+            comparison => comparison,
+        }
+    }
+
+    /// The comparison that holds exactly when this one does not, for operands other than NaN
+    pub fn negated(self) -> Self {
+        match self {
+            Comparison::Less => Comparison::GreaterEqual,
+            Comparison::LessEqual => Comparison::Greater,
+            Comparison::Greater => Comparison::LessEqual,
+            Comparison::GreaterEqual => Comparison::Less,
+            Comparison::Equal => Comparison::NotEqual,
+            Comparison::NotEqual => Comparison::Equal,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Comparison::Less => "<",
+            Comparison::LessEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterEqual => ">=",
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+        })
+    }
+}
+
+/// A boolean condition on expressions, which selects a branch of a piecewise expression
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Condition {
+    /// A condition that is already decided
+    Bool(bool),
+    Compare(Expression, Comparison, Expression),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn evaluate(&self, context: &Context) -> Result<bool, ExpressionError> {
+        Ok(match self {
+            Condition::Bool(value) => *value,
+            Condition::Compare(a, comparison, b) => comparison.holds(a.evaluate(context)?, b.evaluate(context)?),
+            Condition::And(conditions) => {
+                for condition in conditions {
+                    if !condition.evaluate(context)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Condition::Or(conditions) => {
+                for condition in conditions {
+                    if condition.evaluate(context)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Condition::Not(condition) => !condition.evaluate(context)?,
+        })
+    }
+
+    /// Simplifies the compared expressions and decides comparisons whose sides are both constant
+    pub fn simplify(&self) -> Condition {
+        match self {
+            Condition::Bool(_) => self.clone(),
+            Condition::Compare(a, comparison, b) => {
+                let (a, b) = (a.simplify(), b.simplify());
+                match decide(&a, *comparison, &b) {
+                    Some(value) => Condition::Bool(value),
+                    None => Condition::Compare(a, *comparison, b),
+                }
+            }
+            Condition::And(conditions) => simplify_connective(conditions, true),
+            Condition::Or(conditions) => simplify_connective(conditions, false),
+            Condition::Not(condition) => match condition.simplify() {
+                Condition::Bool(value) => Condition::Bool(!value),
+                Condition::Not(inner) => *inner,
+                Condition::Compare(a, comparison, b) => Condition::Compare(a, comparison.negated(), b),
+                condition => Condition::Not(Box::new(condition)),
+            },
+        }
+    }
+
+    /// The compared expressions, from left to right
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            Condition::Bool(_) => vec![],
+            Condition::Compare(a, _, b) => vec![a, b],
+            Condition::And(conditions) | Condition::Or(conditions) => conditions.iter().flat_map(Condition::expressions).collect(),
+            Condition::Not(condition) => condition.expressions(),
+        }
+    }
+
+    /// Rebuilds the condition with `f` applied to each compared expression
+    pub fn map_expressions(&self, f: &impl Fn(&Expression) -> Expression) -> Condition {
+        match self {
+            Condition::Bool(_) => self.clone(),
+            Condition::Compare(a, comparison, b) => Condition::Compare(f(a), *comparison, f(b)),
+            Condition::And(conditions) => Condition::And(conditions.iter().map(|condition| condition.map_expressions(f)).collect()),
+            Condition::Or(conditions) => Condition::Or(conditions.iter().map(|condition| condition.map_expressions(f)).collect()),
+            Condition::Not(condition) => Condition::Not(Box::new(condition.map_expressions(f))),
+        }
+    }
+
+    pub fn contains_variable(&self, variable: &str) -> bool {
+        self.expressions().into_iter().any(|expression| expression.contains_variable(variable))
+    }
+}
+
+/// Whether a comparison of two simplified expressions always holds, if that can be told without the variables
+fn decide(a: &Expression, comparison: Comparison, b: &Expression) -> Option<bool> {
+    if let (Expression::Number(a), Expression::Number(b)) = (a, b) {
+        if a.is_exact() && b.is_exact() {
+            return Some(comparison.holds_for(a.cmp(b)));
+        }
+    }
+    if a == b {
+        return Some(comparison.holds_for(Ordering::Equal));
+    }
+    if !a.variables().is_empty() || !b.variables().is_empty() {
+        return None;
+    }
+    // Constant sides like pi and 22/7, unless a call of an unknown function keeps them from being evaluated
+    let context = Context::new();
+    match (a.evaluate(&context), b.evaluate(&context)) {
+        (Ok(a), Ok(b)) if !a.is_nan() && !b.is_nan() => Some(comparison.holds(a, b)),
+        _ => None,
+    }
+}
+
+/// Simplifies a conjunction when `and` is set and a disjunction otherwise,
+/// flattening nested connectives of the same kind and dropping decided operands
+fn simplify_connective(conditions: &[Condition], and: bool) -> Condition {
+    let mut simplified = Vec::new();
+    for condition in conditions.iter().map(Condition::simplify) {
+        let operands = match condition {
+            // true decides a disjunction and false a conjunction
+            Condition::Bool(value) if value != and => return Condition::Bool(value),
+            Condition::Bool(_) => vec![],
+            Condition::And(inner) if and => inner,
+            Condition::Or(inner) if !and => inner,
+            condition => vec![condition],
+        };
+        for operand in operands {
+            if !simplified.contains(&operand) {
+                simplified.push(operand);
+            }
+        }
+    }
+    match (simplified.len(), and) {
+        (0, _) => Condition::Bool(and),
+        (1, _) => simplified.remove(0),
+        (_, true) => Condition::And(simplified),
+        (_, false) => Condition::Or(simplified),
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut Formatter<'_>, conditions: &[Condition], separator: &str| {
+            for (i, condition) in conditions.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {separator} ")?;
+                }
+                // Parentheses are needed for a disjunction inside a conjunction, and keep the grouping of any other nesting
+                match condition {
+                    Condition::Or(_) | Condition::And(_) => write!(f, "({condition})")?,
+                    _ => write!(f, "{condition}")?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Condition::Bool(value) => write!(f, "{value}"),
+            Condition::Compare(a, comparison, b) => write!(f, "{a} {comparison} {b}"),
+            Condition::And(conditions) => join(f, conditions, "&&"),
+            Condition::Or(conditions) => join(f, conditions, "||"),
+            Condition::Not(condition) => write!(f, "!({condition})"),
+        }
+    }
+}
+
+/// Drops branches that can never be taken, stops at the first branch that is always taken
+/// and merges trailing branches with the same value as the fallback
+pub(crate) fn simplify_piecewise(branches: &[(Condition, Expression)], otherwise: &Expression) -> Expression {
+    let mut simplified: Vec<(Condition, Expression)> = Vec::new();
+    let mut fallback = None;
+    for (condition, value) in branches {
+        match condition.simplify() {
+            Condition::Bool(false) => {}
+            Condition::Bool(true) => {
+                fallback = Some(value.simplify());
+                break;
+            }
+            condition => simplified.push((condition, value.simplify())),
+        }
+    }
+    let otherwise = fallback.unwrap_or_else(|| otherwise.simplify());
+    while simplified.last().is_some_and(|(_, value)| *value == otherwise) {
+        simplified.pop();
+    }
+    match simplified.is_empty() {
+        true => otherwise,
+        false => Expression::Piecewise(simplified, Box::new(otherwise)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::expression::constant::Constant;
+    use crate::{compare, num, piecewise, var};
+    use super::*;
+
+    fn condition(input: &str) -> Condition {
+        Condition::from_str(input).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut context = Context::new();
+        context.set_variable("x", 2.0);
+        assert_eq!(condition("0 < x <= 2 && !(x = 1)").evaluate(&context), Ok(true));
+        assert_eq!(condition("x > 3 || x != 2").evaluate(&context), Ok(false));
+        assert_eq!(condition("y < 1 || x > 1").evaluate(&context), Err(ExpressionError::MissingVariable("y".to_string())));
+        // Evaluation stops at the first operand that decides a connective
+        assert_eq!(condition("x > 1 || y < 1").evaluate(&context), Ok(true));
+        assert!(!Comparison::LessEqual.holds(f64::NAN, 1.0));
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(condition("1/3 < 0.34").simplify(), Condition::Bool(true));
+        assert_eq!(condition("pi > 22/7").simplify(), Condition::Bool(false));
+        assert_eq!(condition("x + 1 >= 1 + x").simplify(), Condition::Bool(true));
+        assert_eq!(condition("!(x < 1)").simplify(), compare!(var!("x"), >=, num!(1)));
+        assert_eq!(condition("x < 1 && 2 > 1 && (y > 0 && x < 1)").simplify(), Condition::And(vec![
+            compare!(var!("x"), <, num!(1)),
+            compare!(var!("y"), >, num!(0)),
+        ]));
+        assert_eq!(condition("x < 1 || 2 > 1").simplify(), Condition::Bool(true));
+        assert_eq!(condition("f(1) < 2").simplify(), condition("f(1) < 2"));
+    }
+
+    #[test]
+    fn test_simplify_piecewise() {
+        let x = || var!("x");
+        assert_eq!(piecewise!((compare!(num!(1), >, num!(2)), num!(1)), (compare!(x(), <, num!(0)), x() * num!(2)); num!(3)).simplify(),
+            piecewise!((compare!(x(), <, num!(0)), num!(2) * x()); num!(3)));
+        assert_eq!(piecewise!((compare!(x(), <, num!(0)), num!(1)), (compare!(Expression::Constant(Constant::Pi), >, num!(3)), num!(2)); num!(3)).simplify(),
+            piecewise!((compare!(x(), <, num!(0)), num!(1)); num!(2)));
+        assert_eq!(piecewise!((compare!(x(), <, num!(0)), num!(1)), (compare!(x(), >, num!(5)), num!(2)); num!(1 + 1)).simplify(),
+            piecewise!((compare!(x(), <, num!(0)), num!(1)); num!(2)));
+        assert_eq!(piecewise!((compare!(x(), <, num!(0)), num!(2) - num!(1)); num!(1)).simplify(), num!(1));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(condition("a < b && (c >= d || !(e = f))").to_string(), "a < b && (c >= d || !(e = f))");
+        assert_eq!(condition("a ≠ b ∨ a ≤ 1").to_string(), "a != b || a <= 1");
+        assert_eq!(
+            piecewise!((compare!(var!("v"), <, var!("c")), var!("a")); var!("b")).to_string(),
+            "if(v < c, a, b)"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::expression::Expression;
+use crate::{mul, neg, num, pow, var};
+
+/// Highest degree a power is expanded to, beyond it the expression is not viewed as a polynomial
+const MAX_DEGREE: usize = 64;
+
+/// An expression viewed as a polynomial in a single variable.
+/// Coefficients are expressions themselves, so other symbols end up inside them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    variable: String,
+    /// Coefficient of `variable ^ n` at index n, without trailing zeros
+    coefficients: Vec<Expression>,
+}
+
+impl Expression {
+    /// Views the expression as a polynomial in `variable`, or returns `None` when
+    /// the variable appears anywhere other than under non-negative integer powers.
+    pub fn as_polynomial(&self, variable: &str) -> Option<Polynomial> {
+        use Expression::*;
+        if !self.contains_variable(variable) {
+            return Some(Polynomial::constant(variable, self.clone()));
+        }
+        match self {
+            Variable(_) => Some(Polynomial::new(variable, vec![num!(0), num!(1)])),
+            Add(add) => add.0.iter()
+                .map(|child| child.as_polynomial(variable))
+                .try_fold(Polynomial::zero(variable), |sum, term| Some(sum + term?)),
+            Multiply(multiply) => multiply.0.iter()
+                .map(|child| child.as_polynomial(variable))
+                .try_fold(Polynomial::constant(variable, num!(1)), |product, factor| Some(product * factor?)),
+            Power(base, exponent) => {
+                let Number(exponent) = &**exponent else { return None };
+                let exponent = u128::try_from(exponent.to_i128()?).ok()?;
+                base.as_polynomial(variable)?.pow(exponent)
+            }
+            Negate(negate) => Some(-negate.0.as_polynomial(variable)?),
+            _ => None,
+        }
+    }
+
+    /// Views the expression as a quotient of two polynomials in `variable`, returned as (numerator, denominator).
+    /// The fractions are combined over a common denominator without cancelling common factors.
+    pub fn as_rational_function(&self, variable: &str) -> Option<(Polynomial, Polynomial)> {
+        use Expression::*;
+        if let Some(polynomial) = self.as_polynomial(variable) {
+            return Some((polynomial, Polynomial::constant(variable, num!(1))));
+        }
+        match self {
+            Add(add) => add.0.iter()
+                .map(|child| child.as_rational_function(variable))
+                .try_fold((Polynomial::zero(variable), Polynomial::constant(variable, num!(1))), |(n1, d1), term| {
+                    let (n2, d2) = term?;
+                    Some((n1 * d2.clone() + n2 * d1.clone(), d1 * d2))
+                }),
+            Multiply(multiply) => multiply.0.iter()
+                .map(|child| child.as_rational_function(variable))
+                .try_fold((Polynomial::constant(variable, num!(1)), Polynomial::constant(variable, num!(1))), |(n1, d1), factor| {
+                    let (n2, d2) = factor?;
+                    Some((n1 * n2, d1 * d2))
+                }),
+            Power(base, exponent) => {
+                let Number(exponent) = &**exponent else { return None };
+                let exponent = exponent.to_i128()?;
+                let (numerator, denominator) = base.as_rational_function(variable)?;
+                let (numerator, denominator) = (numerator.pow(exponent.unsigned_abs())?, denominator.pow(exponent.unsigned_abs())?);
+                match exponent < 0 {
+                    true => Some((denominator, numerator)),
+                    false => Some((numerator, denominator)),
+                }
+            }
+            Negate(negate) => {
+                let (numerator, denominator) = negate.0.as_rational_function(variable)?;
+                Some((-numerator, denominator))
+            }
+            Invert(invert) => {
+                let (numerator, denominator) = invert.0.as_rational_function(variable)?;
+                Some((denominator, numerator))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Polynomial {
+    /// Creates a polynomial from its coefficients, ordered from the constant term upwards
+    pub fn new(variable: &str, coefficients: Vec<Expression>) -> Self {
+        let mut polynomial = Self { variable: variable.to_string(), coefficients };
+        polynomial.trim();
+        polynomial
+    }
+
+    pub fn zero(variable: &str) -> Self {
+        Self::new(variable, vec![])
+    }
+
+    pub fn constant(variable: &str, value: Expression) -> Self {
+        Self::new(variable, vec![value])
+    }
+
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    /// Coefficients ordered from the constant term upwards
+    pub fn coefficients(&self) -> &[Expression] {
+        &self.coefficients
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// Raises the polynomial to a power by repeated multiplication, or returns `None` past `MAX_DEGREE`
+    fn pow(&self, exponent: u128) -> Option<Self> {
+        let exponent = usize::try_from(exponent).ok()?;
+        if self.degree().max(1).checked_mul(exponent)? > MAX_DEGREE {
+            return None;
+        }
+        Some((0..exponent).fold(Self::constant(&self.variable, num!(1)), |product, _| product * self.clone()))
+    }
+
+    /// Highest power with a non-zero coefficient. The zero polynomial has degree 0.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// Coefficient of `variable ^ n`
+    pub fn coefficient(&self, n: usize) -> Expression {
+        self.coefficients.get(n).cloned().unwrap_or(num!(0))
+    }
+
+    pub fn leading_coefficient(&self) -> Expression {
+        self.coefficient(self.degree())
+    }
+
+    /// The highest degree term, `leading_coefficient * variable ^ degree`
+    pub fn leading_term(&self) -> Expression {
+        Self::term(self.leading_coefficient(), &self.variable, self.degree())
+    }
+
+    /// Converts back into an expression, with terms ordered from the highest power down
+    pub fn to_expression(&self) -> Expression {
+        let mut terms: Vec<Expression> = self.coefficients.iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, coefficient)| !is_zero(coefficient))
+            .map(|(power, coefficient)| Self::term(coefficient.clone(), &self.variable, power))
+            .collect();
+
+        match terms.len() {
+            0 => num!(0),
+            1 => terms.remove(0),
+            _ => Expression::Add(crate::expression::Add(terms)),
+        }
+    }
+
+    fn term(coefficient: Expression, variable: &str, power: usize) -> Expression {
+        let power = match power {
+            0 => return coefficient,
+            1 => var!(variable),
+            n => pow!(var!(variable), num!(n)),
+        };
+        match coefficient {
+            coefficient if coefficient == num!(1) => power,
+            coefficient if coefficient == num!(-1) => neg!(power),
+            coefficient => mul!(coefficient, power),
+        }
+    }
+
+    fn trim(&mut self) {
+        for coefficient in self.coefficients.iter_mut() {
+            *coefficient = coefficient.simplify();
+        }
+        while self.coefficients.last().is_some_and(is_zero) {
+            self.coefficients.pop();
+        }
+    }
+}
+
+fn is_zero(expression: &Expression) -> bool {
+    *expression == num!(0)
+}
+
+impl From<Polynomial> for Expression {
+    fn from(polynomial: Polynomial) -> Self {
+        polynomial.to_expression()
+    }
+}
+
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_expression())
+    }
+}
+
+impl Add for Polynomial {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let length = self.coefficients.len().max(rhs.coefficients.len());
+        let coefficients = (0..length)
+            .map(|n| self.coefficient(n) + rhs.coefficient(n))
+            .collect();
+        Self::new(&self.variable, coefficients)
+    }
+}
+
+impl Sub for Polynomial {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for Polynomial {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self.is_zero() || rhs.is_zero() {
+            return Self::zero(&self.variable);
+        }
+        let mut products: Vec<Vec<Expression>> = vec![vec![]; self.degree() + rhs.degree() + 1];
+        for (i, left) in self.coefficients.iter().enumerate() {
+            for (j, right) in rhs.coefficients.iter().enumerate() {
+                products[i + j].push(left.clone() * right.clone());
+            }
+        }
+        let coefficients = products.into_iter()
+            .map(|terms| Expression::Add(crate::expression::Add(terms)))
+            .collect();
+        Self::new(&self.variable, coefficients)
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        let coefficients = self.coefficients.into_iter().map(|coefficient| neg!(coefficient)).collect();
+        Self::new(&self.variable, coefficients)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use crate::{inv, num, pow, sin, var};
+    use super::*;
+
+    #[test]
+    fn test_coefficients() {
+        let polynomial = Expression::from_str("x^2 + 2*x + 1").unwrap().as_polynomial("x").unwrap();
+        assert_eq!(polynomial.coefficients(), &[num!(1), num!(2), num!(1)]);
+        assert_eq!(polynomial.degree(), 2);
+        assert_eq!(polynomial.leading_term(), pow!(var!("x"), num!(2)));
+
+        let polynomial = (pow!(var!("x") - num!(1), num!(3)) - pow!(var!("x"), num!(3))).as_polynomial("x").unwrap();
+        assert_eq!(polynomial.coefficients(), &[num!(-1), num!(3), num!(-3)]);
+        assert_eq!(polynomial.coefficient(5), num!(0));
+    }
+
+    #[test]
+    fn test_symbolic_coefficients() {
+        let expression = var!("a") * pow!(var!("x"), num!(2)) + var!("b") * var!("x") + var!("c");
+        let polynomial = expression.as_polynomial("x").unwrap();
+        assert_eq!(polynomial.coefficients(), &[var!("c"), var!("b"), var!("a")]);
+        assert_eq!(polynomial.leading_term(), mul!(var!("a"), pow!(var!("x"), num!(2))));
+        assert_eq!(expression.as_polynomial("a").unwrap().degree(), 1);
+        assert_eq!(sin!(var!("a")).as_polynomial("x").unwrap().coefficients(), &[sin!(var!("a"))]);
+
+        let variables = HashMap::from([("a", 2.0), ("b", -3.0), ("c", 0.5), ("x", 1.5)].map(|(k, v)| (k.to_string(), v)));
+        assert_eq!(
+            polynomial.to_expression().solve(Some(&variables)),
+            expression.solve(Some(&variables))
+        );
+    }
+
+    #[test]
+    fn test_not_polynomial() {
+        assert_eq!(sin!(var!("x")).as_polynomial("x"), None);
+        assert_eq!(inv!(var!("x")).as_polynomial("x"), None);
+        assert_eq!(pow!(var!("x"), num!(0.5)).as_polynomial("x"), None);
+        assert_eq!(pow!(var!("x"), var!("n")).as_polynomial("x"), None);
+        assert_eq!(pow!(var!("x"), num!(1_000_000_000)).as_polynomial("x"), None);
+        assert_eq!(pow!(var!("x") + num!(1), num!(-1_000_000_000)).as_rational_function("x"), None);
+        assert_eq!(pow!(inv!(var!("x")), num!(1_000_000_000)).as_rational_function("x"), None);
+    }
+
+    #[test]
+    fn test_rational_function() {
+        let (numerator, denominator) = Expression::from_str("1/x + 2/(x - 1)").unwrap().as_rational_function("x").unwrap();
+        assert_eq!(numerator.coefficients(), &[num!(-1), num!(3)]);
+        assert_eq!(denominator.coefficients(), &[num!(0), num!(-1), num!(1)]);
+
+        let (numerator, denominator) = pow!(var!("x") + num!(1), num!(-2)).as_rational_function("x").unwrap();
+        assert_eq!(numerator.coefficients(), &[num!(1)]);
+        assert_eq!(denominator.coefficients(), &[num!(1), num!(2), num!(1)]);
+        assert_eq!(sin!(var!("x")).as_rational_function("x"), None);
+    }
+
+    #[test]
+    fn test_zero() {
+        let polynomial = (var!("x") - var!("x")).as_polynomial("x").unwrap();
+        assert!(polynomial.is_zero());
+        assert_eq!(polynomial.to_expression(), num!(0));
+    }
+}
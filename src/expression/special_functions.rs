@@ -0,0 +1,77 @@
+use crate::expression::Expression;
+
+impl Expression {
+    /// Whether the expression always has an integer value, so that rounding it changes nothing
+    pub(crate) fn is_integer_valued(&self) -> bool {
+        use Expression::*;
+        match self {
+            Number(number) => number.is_integer(),
+            Floor(_) | Ceil(_) | Round(_) | Sign(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Simplifies min or max by flattening nested calls of the same kind, folding the numbers into one
+/// and dropping repeated arguments. A single remaining argument is returned as is.
+pub(crate) fn simplify_extremum(args: &[Expression], maximum: bool) -> Expression {
+    let mut flattened = Vec::new();
+    for arg in args.iter().map(|arg| arg.simplify()) {
+        match arg {
+            Expression::Max(inner) if maximum => flattened.extend(inner),
+            Expression::Min(inner) if !maximum => flattened.extend(inner),
+            arg => flattened.push(arg),
+        }
+    }
+    let (numbers, mut rest): (Vec<Expression>, Vec<Expression>) = flattened.into_iter()
+        .partition(|arg| matches!(arg, Expression::Number(_)));
+    let extremum = match maximum {
+        true => numbers.into_iter().max(),
+        false => numbers.into_iter().min(),
+    };
+    rest.extend(extremum);
+    rest.sort();
+    rest.dedup();
+    match (rest.len(), maximum) {
+        (1, _) => rest.remove(0),
+        (_, true) => Expression::Max(rest),
+        (_, false) => Expression::Min(rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::{ceil, factorial, floor, gamma, max, min, modulo, num, round, sign, var};
+
+    #[test]
+    fn test_constant_arguments() {
+        assert_eq!(factorial!(num!(5)).simplify(), num!(120));
+        assert_eq!(gamma!(num!(5)).simplify(), num!(24));
+        assert_eq!(gamma!(num!(1, 2)).simplify(), gamma!(num!(1, 2)));
+        assert_eq!(floor!(num!(-7, 2)).simplify(), num!(-4));
+        assert_eq!(ceil!(num!(7, 2)).simplify(), num!(4));
+        assert_eq!(round!(num!(5, 2)).simplify(), num!(3));
+        assert_eq!(sign!(num!(-3) * var!("x") * num!(0)).simplify(), num!(0));
+        assert_eq!(modulo!(num!(-7), num!(3)).simplify(), num!(2));
+        assert_eq!(floor!(ceil!(var!("x"))).simplify(), ceil!(var!("x")));
+    }
+
+    #[test]
+    fn test_extremum() {
+        assert_eq!(min!(num!(3), var!("x"), num!(1, 2), var!("x")).simplify(), min!(num!(1, 2), var!("x")));
+        assert_eq!(max!(num!(3), max!(var!("y"), num!(5)), var!("x")).simplify(), max!(num!(5), var!("x"), var!("y")));
+        assert_eq!(max!(num!(2), num!(7)).simplify(), num!(7));
+        assert_eq!(min!(var!("x")).simplify(), var!("x"));
+
+        let variables = HashMap::from([("x".to_string(), -2.5)]);
+        assert_eq!(min!(num!(3), var!("x"), num!(1)).solve(Some(&variables)), Ok(-2.5));
+        assert_eq!(max!(num!(3), var!("x"), num!(1)).solve(Some(&variables)), Ok(3.0));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(factorial!(num!(2000)).solve(None), Ok(f64::INFINITY));
+        assert_eq!(gamma!(var!("x")).solve(Some(&HashMap::from([("x".to_string(), 172.0)]))), Ok(f64::INFINITY));
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use crate::expression::Expression;
+
+impl Expression {
+    /// Replaces every occurrence of `variable` with `with`
+    pub fn substitute(&self, variable: &str, with: &Expression) -> Expression {
+        self.replace(&Expression::Variable(variable.to_string()), with)
+    }
+
+    /// Replaces all variables in `substitutions` at once,
+    /// so variables inside the substituted expressions are left alone
+    pub fn substitute_all(&self, substitutions: &HashMap<String, Expression>) -> Expression {
+        match self {
+            Expression::Variable(name) => substitutions.get(name).cloned().unwrap_or_else(|| self.clone()),
+            _ => self.map_children(|child| child.substitute_all(substitutions)),
+        }
+    }
+
+    /// Replaces every sub-tree that is structurally equal to `pattern` with `with`.
+    /// Matches are replaced from the outside in, and the replacement itself is not searched again.
+    pub fn replace(&self, pattern: &Expression, with: &Expression) -> Expression {
+        if self == pattern {
+            return with.clone();
+        }
+        self.map_children(|child| child.replace(pattern, with))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::{num, pow, sin, var};
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let expression = Expression::from_str("O + V * t").unwrap();
+        let substituted = expression.substitute("V", &(var!("d") / var!("t")));
+        assert_eq!(substituted.simplify(), (var!("O") + var!("d")).simplify());
+        assert_eq!(expression.substitute("x", &num!(1)), expression);
+
+        let expression = sin!(pow!(var!("x"), num!(2))) + var!("x");
+        let substituted = expression.substitute("x", &num!(2));
+        assert_eq!(substituted.solve(None).unwrap(), 4f64.sin() + 2.0);
+    }
+
+    #[test]
+    fn test_substitute_all() {
+        let expression = var!("x") * var!("y");
+        let substitutions = HashMap::from([
+            ("x".to_string(), var!("y")),
+            ("y".to_string(), var!("x")),
+        ]);
+        assert_eq!(expression.substitute_all(&substitutions), var!("y") * var!("x"));
+    }
+
+    #[test]
+    fn test_replace() {
+        let pattern = pow!(var!("x"), num!(2));
+        let expression = sin!(pattern.clone()) + pattern.clone() * var!("a");
+        assert_eq!(expression.replace(&pattern, &var!("u")), sin!(var!("u")) + var!("u") * var!("a"));
+
+        // The replacement is not searched again, so this terminates
+        assert_eq!(var!("x").replace(&var!("x"), &(var!("x") + num!(1))), var!("x") + num!(1));
+    }
+}
@@ -0,0 +1,35 @@
+//! Symbolic manipulation of mathematical expressions and equations.
+//!
+//! ```
+//! use std::str::FromStr;
+//! use math_toolkit::{Equation, Expression};
+//!
+//! let expression = Expression::from_str("x ^ 2").unwrap();
+//! assert_eq!(expression.derivative("x"), math_toolkit::mul!(math_toolkit::var!("x"), math_toolkit::num!(2)));
+//!
+//! let equation = Equation::from_str("P = O + V * t").unwrap();
+//! let t = equation.isolate_variable("t").unwrap();
+//! assert_eq!(t.left, math_toolkit::var!("t"));
+//! ```
+
+pub mod expression;
+pub mod equation;
+pub mod operation;
+pub mod units;
+mod utils;
+
+pub use equation::Equation;
+pub use equation::inequality::Inequality;
+pub use equation::interval::{Endpoint, Interval, IntervalSet};
+pub use equation::solution_set::{Root, SolutionSet};
+pub use expression::Expression;
+pub use expression::piecewise::{Comparison, Condition};
+pub use expression::constant::{Constant, NamedConstant};
+pub use expression::context::{Context, Definition};
+pub use expression::from_str::ParseOptions;
+pub use expression::error::{ExpressionError, IsolateError, ParseError, UnitError};
+pub use expression::number::Number;
+pub use expression::polynomial::Polynomial;
+pub use units::dimension::{BaseDimension, Dimension};
+pub use units::quantity::Quantity;
+pub use units::unit::Unit;
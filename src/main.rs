@@ -1,12 +1,12 @@
-pub mod expression;
-pub mod equation;
-pub mod operation;
-mod utils;
+use std::env;
+use std::str::FromStr;
+
+use math_toolkit::Expression;
 
 fn main() {
-    println!("Hello, world!");
+    let input = env::args().skip(1).collect::<Vec<_>>().join(" ");
+    match Expression::from_str(&input) {
+        Ok(expression) => println!("{}", expression.simplify()),
+        Err(err) => eprintln!("Could not parse expression: {err}"),
+    }
 }
-
-
-
-
@@ -24,7 +24,27 @@ pub enum Operation{
     ArcCos,
     Tan,
     ArcTan,
+    Sinh,
+    ArcSinh,
+    Cosh,
+    ArcCosh,
+    Tanh,
+    ArcTanh,
     Ln,
     Abs,
-    Negate
+    Gamma,
+    Polygamma,
+    Erf,
+    Erfc,
+    Factorial,
+    Floor,
+    Ceil,
+    Round,
+    Sign,
+    Min,
+    Max,
+    Mod,
+    Negate,
+    Function,
+    Piecewise,
 }
\ No newline at end of file
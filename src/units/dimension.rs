@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Div, Mul};
+
+use num_rational::Rational32;
+use num_traits::{CheckedMul, One, Zero};
+
+/// The seven SI base dimensions, in the order of their exponents in a `Dimension`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BaseDimension {
+    Length,
+    Mass,
+    Time,
+    Current,
+    Temperature,
+    Amount,
+    LuminousIntensity,
+}
+
+impl BaseDimension {
+    pub const ALL: [BaseDimension; 7] = [
+        BaseDimension::Length,
+        BaseDimension::Mass,
+        BaseDimension::Time,
+        BaseDimension::Current,
+        BaseDimension::Temperature,
+        BaseDimension::Amount,
+        BaseDimension::LuminousIntensity,
+    ];
+
+    /// Symbol of the SI base unit, like `kg` for mass
+    pub fn unit_symbol(self) -> &'static str {
+        match self {
+            BaseDimension::Length => "m",
+            BaseDimension::Mass => "kg",
+            BaseDimension::Time => "s",
+            BaseDimension::Current => "A",
+            BaseDimension::Temperature => "K",
+            BaseDimension::Amount => "mol",
+            BaseDimension::LuminousIntensity => "cd",
+        }
+    }
+}
+
+/// A product of powers of the base dimensions, like length / time ^ 2 for an acceleration.
+/// Exponents are rational, since roots of dimensional quantities appear in physical formulas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Dimension([Rational32; 7]);
+
+impl Dimension {
+    pub fn new(exponents: [Rational32; 7]) -> Self {
+        Self(exponents)
+    }
+
+    /// The dimension with integer exponents, ordered like `BaseDimension::ALL`
+    pub fn from_integers(exponents: [i32; 7]) -> Self {
+        Self(exponents.map(Rational32::from_integer))
+    }
+
+    pub fn dimensionless() -> Self {
+        Self::default()
+    }
+
+    /// A base dimension to the first power
+    pub fn of(base: BaseDimension) -> Self {
+        let mut exponents = [Rational32::zero(); 7];
+        exponents[base as usize] = Rational32::one();
+        Self(exponents)
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        self.0.iter().all(Zero::is_zero)
+    }
+
+    pub fn exponent(&self, base: BaseDimension) -> Rational32 {
+        self.0[base as usize]
+    }
+
+    /// Multiplies every exponent by `exponent`, or returns `None` when one of them no longer fits
+    pub fn pow(&self, exponent: Rational32) -> Option<Self> {
+        let mut exponents = self.0;
+        for power in &mut exponents {
+            *power = power.checked_mul(&exponent)?;
+        }
+        Some(Self(exponents))
+    }
+}
+
+impl Mul for Dimension {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl Div for Dimension {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Self(std::array::from_fn(|i| self.0[i] - rhs.0[i]))
+    }
+}
+
+/// Writes the dimension in SI base units, like `m kg s^-2`, or `1` when dimensionless
+impl Display for Dimension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_dimensionless() {
+            return write!(f, "1");
+        }
+        let factors: Vec<String> = BaseDimension::ALL.iter()
+            .filter(|base| !self.exponent(**base).is_zero())
+            .map(|base| match self.exponent(*base) {
+                exponent if exponent.is_one() => base.unit_symbol().to_string(),
+                exponent if exponent.is_integer() => format!("{}^{exponent}", base.unit_symbol()),
+                exponent => format!("{}^({exponent})", base.unit_symbol()),
+            })
+            .collect();
+        write!(f, "{}", factors.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let length = Dimension::of(BaseDimension::Length);
+        let time = Dimension::of(BaseDimension::Time);
+        let acceleration = length / (time * time);
+        assert_eq!(acceleration, Dimension::from_integers([1, 0, -2, 0, 0, 0, 0]));
+        assert_eq!(acceleration.to_string(), "m s^-2");
+        assert_eq!((length / length).to_string(), "1");
+        assert!((length / length).is_dimensionless());
+        assert_eq!(time.pow(Rational32::new(2, 5)).unwrap().to_string(), "s^(2/5)");
+        assert_eq!(time.pow(Rational32::from_integer(100_000)).unwrap().pow(Rational32::from_integer(100_000)), None);
+        assert_eq!(Dimension::from_integers([2, 1, -2, 0, 0, 0, 0]).to_string(), "m^2 kg s^-2");
+    }
+}
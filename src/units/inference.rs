@@ -0,0 +1,251 @@
+use num_rational::Rational32;
+
+use crate::expression::context::{Context, Definition};
+use crate::expression::error::UnitError;
+use crate::expression::number::Number;
+use crate::expression::{Condition, Expression};
+use crate::operation::Operation;
+use crate::units::dimension::Dimension;
+use crate::units::quantity::Quantity;
+use crate::units::unit::{exponent_ratio, Unit};
+use crate::num;
+
+impl Expression {
+    /// Infers the physical dimension of the expression from the units declared for its variables in the context.
+    /// Numbers and undeclared variables are dimensionless, constants have the dimension of their SI unit.
+    pub fn dimension(&self, context: &Context) -> Result<Dimension, UnitError> {
+        use Expression::*;
+        match self {
+            Number(_) => Ok(Dimension::dimensionless()),
+            Constant(constant) => Ok(constant.dimension()),
+            Variable(name) => match (context.unit(name), context.constant(name)) {
+                (Some(unit), _) => Ok(unit.dimension),
+                (None, Some(constant)) if context.variable(name).is_none() => Ok(constant.dimension()),
+                _ => Ok(Dimension::dimensionless()),
+            },
+            Add(add) => same_dimension(&add.0, context),
+            Multiply(multiply) => multiply.0.iter()
+                .try_fold(Dimension::dimensionless(), |product, factor| Ok(product * factor.dimension(context)?)),
+            Power(base, exponent) => {
+                let base = base.dimension(context)?;
+                dimensionless(exponent, Operation::PowerExponent, context)?;
+                if base.is_dimensionless() {
+                    return Ok(base);
+                }
+                let exponent = exponent.simplify();
+                match &exponent {
+                    Number(crate::expression::number::Number::Rational(ratio)) => match exponent_ratio(ratio) {
+                        Some(ratio) => base.pow(ratio).ok_or(UnitError::ExponentOverflow(base, ratio)),
+                        None => Err(UnitError::NonRationalExponent(exponent)),
+                    },
+                    _ => Err(UnitError::NonRationalExponent(exponent)),
+                }
+            }
+            Sqrt(a) => {
+                let base = a.dimension(context)?;
+                let half = Rational32::new(1, 2);
+                base.pow(half).ok_or(UnitError::ExponentOverflow(base, half))
+            }
+            Log(a, b) => {
+                dimensionless(a, Operation::LogPower, context)?;
+                dimensionless(b, Operation::LogBase, context)
+            }
+            Sin(a) => dimensionless(a, Operation::Sin, context),
+            ArcSin(a) => dimensionless(a, Operation::ArcSin, context),
+            Cos(a) => dimensionless(a, Operation::Cos, context),
+            ArcCos(a) => dimensionless(a, Operation::ArcCos, context),
+            Tan(a) => dimensionless(a, Operation::Tan, context),
+            ArcTan(a) => dimensionless(a, Operation::ArcTan, context),
+            Sinh(a) => dimensionless(a, Operation::Sinh, context),
+            ArcSinh(a) => dimensionless(a, Operation::ArcSinh, context),
+            Cosh(a) => dimensionless(a, Operation::Cosh, context),
+            ArcCosh(a) => dimensionless(a, Operation::ArcCosh, context),
+            Tanh(a) => dimensionless(a, Operation::Tanh, context),
+            ArcTanh(a) => dimensionless(a, Operation::ArcTanh, context),
+            Ln(a) => dimensionless(a, Operation::Ln, context),
+            Gamma(a) => dimensionless(a, Operation::Gamma, context),
+            Polygamma(n, a) => {
+                dimensionless(n, Operation::Polygamma, context)?;
+                dimensionless(a, Operation::Polygamma, context)
+            }
+            Erf(a) => dimensionless(a, Operation::Erf, context),
+            Erfc(a) => dimensionless(a, Operation::Erfc, context),
+            Factorial(a) => dimensionless(a, Operation::Factorial, context),
+            Abs(a) | Floor(a) | Ceil(a) | Round(a) => a.dimension(context),
+            Sign(a) => {
+                a.dimension(context)?;
+                Ok(Dimension::dimensionless())
+            }
+            Min(args) | Max(args) => same_dimension(args, context),
+            Mod(a, b) => same_dimension(&[(**a).clone(), (**b).clone()], context),
+            Negate(negate) => negate.0.dimension(context),
+            Invert(invert) => Ok(Dimension::dimensionless() / invert.0.dimension(context)?),
+            Function(function) => {
+                let args = function.args.iter().map(|arg| arg.dimension(context)).collect::<Result<Vec<_>, _>>()?;
+                match function.definition(context)? {
+                    Definition::Native { .. } => {
+                        match args.into_iter().find(|dimension| !dimension.is_dimensionless()) {
+                            Some(dimension) => Err(UnitError::DimensionalArgument(Operation::Function, dimension)),
+                            None => Ok(Dimension::dimensionless()),
+                        }
+                    }
+                    Definition::Expression { parameters, body } => {
+                        let dimension = context.enter(&function.name, || {
+                            let mut inner = context.clone();
+                            for (parameter, dimension) in parameters.iter().zip(args) {
+                                inner.declare_unit(parameter, Unit::coherent(dimension));
+                            }
+                            Ok(body.dimension(&inner))
+                        })?;
+                        dimension
+                    }
+                }
+            }
+            Piecewise(branches, otherwise) => {
+                let mut values = Vec::new();
+                for (condition, value) in branches {
+                    check_condition(condition, context)?;
+                    values.push(value.clone());
+                }
+                values.push((**otherwise).clone());
+                same_dimension(&values, context)
+            }
+        }
+    }
+
+    /// Evaluates the expression with each variable read in the unit declared for it,
+    /// giving the result in the coherent SI unit of its dimension
+    pub fn evaluate_quantity(&self, context: &Context) -> Result<Quantity, UnitError> {
+        let dimension = self.dimension(context)?;
+        let mut si = context.clone();
+        for (name, value) in si.variables.iter_mut() {
+            if let Some(unit) = context.unit(name) {
+                *value *= unit.scale.value();
+            }
+        }
+        Ok(Quantity::new(Number::float(self.evaluate(&si)?), Unit::coherent(dimension)))
+    }
+
+    /// Evaluates the expression like `evaluate_quantity` and converts the result into `unit`
+    pub fn evaluate_in(&self, context: &Context, unit: &Unit) -> Result<f64, UnitError> {
+        Ok(self.evaluate_quantity(context)?.convert_to(unit)?.value.value())
+    }
+}
+
+/// The common dimension of expressions that are added or compared, failing at the first mismatch.
+/// A literal zero fits every dimension, so that `v > 0` needs no unit on the zero.
+fn same_dimension(expressions: &[Expression], context: &Context) -> Result<Dimension, UnitError> {
+    let mut common: Option<Dimension> = None;
+    for expression in expressions.iter().filter(|expression| **expression != num!(0)) {
+        let dimension = expression.dimension(context)?;
+        match common {
+            Some(common) if common != dimension => return Err(UnitError::IncompatibleDimensions(common, dimension)),
+            _ => common = Some(dimension),
+        }
+    }
+    Ok(common.unwrap_or_default())
+}
+
+/// Checks that the argument of `operation` is a pure number, which the result is as well
+fn dimensionless(argument: &Expression, operation: Operation, context: &Context) -> Result<Dimension, UnitError> {
+    match argument.dimension(context)? {
+        dimension if dimension.is_dimensionless() => Ok(dimension),
+        dimension => Err(UnitError::DimensionalArgument(operation, dimension)),
+    }
+}
+
+fn check_condition(condition: &Condition, context: &Context) -> Result<(), UnitError> {
+    match condition {
+        Condition::Bool(_) => Ok(()),
+        Condition::Compare(a, _, b) => same_dimension(&[a.clone(), b.clone()], context).map(|_| ()),
+        Condition::And(conditions) | Condition::Or(conditions) => conditions.iter()
+            .try_for_each(|condition| check_condition(condition, context)),
+        Condition::Not(condition) => check_condition(condition, context),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::expression::from_str::ParseOptions;
+    use crate::units::dimension::BaseDimension;
+    use super::*;
+
+    fn context() -> Context {
+        let mut context = Context::new();
+        context.set_quantity("E_0", &Quantity::new(Number::integer(1), Unit::from_str("MJ").unwrap()));
+        context.set_quantity("rho_0", &Quantity::new(Number::float(1.2), Unit::from_str("kg/m^3").unwrap()));
+        context.set_quantity("t", &Quantity::new(Number::integer(50), Unit::symbol("ms").unwrap()));
+        context.set_quantity("v", &Quantity::new(Number::integer(36), Unit::from_str("km/h").unwrap()));
+        context.set_variable("A", 1.0);
+        context
+    }
+
+    fn dimension(input: &str) -> Result<Dimension, UnitError> {
+        Expression::from_str(input).unwrap().dimension(&context())
+    }
+
+    #[test]
+    fn test_dimension() {
+        let length = Dimension::of(BaseDimension::Length);
+        let time = Dimension::of(BaseDimension::Time);
+        assert_eq!(dimension("A * (E_0/rho_0)^(1/5) * t^(2/5)"), Ok(length));
+        assert_eq!(dimension("v * t + 3 * v * t"), Ok(length));
+        assert_eq!(dimension("sqrt(v^2) / v"), Ok(Dimension::dimensionless()));
+        assert_eq!(dimension("sin(v * t / (v * t)) + cos(2 pi)"), Ok(Dimension::dimensionless()));
+        assert_eq!(dimension("if(v > 0, t, 2 t)"), Ok(time));
+        assert_eq!(dimension("max(t, 2 t) + abs(t)"), Ok(time));
+        assert_eq!(dimension("τ * t + γ * t"), Ok(time));
+
+        assert_eq!(dimension("v + t"), Err(UnitError::IncompatibleDimensions(length / time, time)));
+        assert_eq!(dimension("sin(t)"), Err(UnitError::DimensionalArgument(Operation::Sin, time)));
+        assert_eq!(dimension("2^t"), Err(UnitError::DimensionalArgument(Operation::PowerExponent, time)));
+        assert!(matches!(dimension("t^A"), Err(UnitError::NonRationalExponent(_))));
+        assert!(matches!(dimension("(v^100000)^100000"), Err(UnitError::ExponentOverflow(..))));
+        assert!(matches!(dimension("if(v > t, 1, 2)"), Err(UnitError::IncompatibleDimensions(..))));
+    }
+
+    #[test]
+    fn test_functions() {
+        let mut context = context();
+        context.define("distance", &["speed", "time"], Expression::from_str("speed * time").unwrap());
+        context.define_native("hypot", 2, |args| args[0].hypot(args[1]));
+        let distance = Expression::from_str("distance(v, t)").unwrap();
+        assert_eq!(distance.dimension(&context), Ok(Dimension::of(BaseDimension::Length)));
+        assert!(matches!(Expression::from_str("hypot(v, v)").unwrap().dimension(&context), Err(UnitError::DimensionalArgument(..))));
+        assert!(matches!(Expression::from_str("g(t)").unwrap().dimension(&context), Err(UnitError::Evaluation(_))));
+    }
+
+    #[test]
+    fn test_constants() {
+        let options = ParseOptions { physical_constants: true, ..ParseOptions::default() };
+        let energy = Expression::parse_with("m c^2", options).unwrap();
+        let mut context = Context::new();
+        context.set_quantity("m", &Quantity::new(Number::integer(1), Unit::symbol("g").unwrap()));
+        assert_eq!(energy.dimension(&context), Unit::symbol("J").map(|unit| unit.dimension).ok_or(UnitError::UnknownUnit("J".to_string())));
+        let expected = 1e-3 * 299_792_458f64.powi(2) / 1e12;
+        assert!((energy.evaluate_in(&context, &Unit::symbol("TJ").unwrap()).unwrap() - expected).abs() < 1e-9);
+        assert!(matches!(Expression::parse_with("sin(h)", options).unwrap().dimension(&context), Err(UnitError::DimensionalArgument(..))));
+
+        let acceleration = Dimension::from_integers([1, 0, -2, 0, 0, 0, 0]);
+        context.define_constant("g_n", Number::from_str("9.80665").unwrap(), acceleration);
+        assert_eq!(context.parse("m g_n").unwrap().dimension(&context), Unit::symbol("N").map(|unit| unit.dimension).ok_or(UnitError::UnknownUnit("N".to_string())));
+        assert_eq!(Expression::from_str("g_n").unwrap().dimension(&context), Ok(acceleration));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let context = context();
+        let distance = Expression::from_str("v * t").unwrap();
+        assert!((distance.evaluate_in(&context, &Unit::symbol("cm").unwrap()).unwrap() - 50.0).abs() < 1e-9);
+        assert_eq!(distance.evaluate_quantity(&context).unwrap().unit.name, "m");
+
+        let radius = Expression::from_str("A * (E_0/rho_0)^(1/5) * t^(2/5)").unwrap();
+        let expected = (1e6_f64 / 1.2).powf(0.2) * 0.05_f64.powf(0.4);
+        assert!((radius.evaluate_in(&context, &Unit::symbol("m").unwrap()).unwrap() - expected).abs() < 1e-9);
+        assert_eq!(
+            radius.evaluate_in(&context, &Unit::symbol("s").unwrap()),
+            Err(UnitError::IncompatibleDimensions(Dimension::of(BaseDimension::Length), Dimension::of(BaseDimension::Time)))
+        );
+    }
+}
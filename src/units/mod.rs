@@ -0,0 +1,9 @@
+//! Physical units and dimensional analysis of expressions.
+//!
+//! Units are declared for variables in a `Context`, dimensions are inferred through every expression node,
+//! and evaluation converts the values of the variables into SI units and the result into a requested unit.
+
+pub mod dimension;
+mod inference;
+pub mod quantity;
+pub mod unit;
@@ -0,0 +1,78 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Div, Mul};
+
+use crate::expression::error::UnitError;
+use crate::expression::number::Number;
+use crate::units::dimension::Dimension;
+use crate::units::unit::Unit;
+
+/// A number measured in a unit, like 3 km
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: Number,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: impl Into<Number>, unit: Unit) -> Self {
+        Self { value: value.into(), unit }
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        self.unit.dimension
+    }
+
+    /// The same quantity in `unit`, exact when both the value and the conversion factor are
+    pub fn convert_to(&self, unit: &Unit) -> Result<Quantity, UnitError> {
+        let factor = self.unit.conversion_factor(unit)?;
+        Ok(Quantity::new(self.value.clone() * factor, unit.clone()))
+    }
+
+    /// The same quantity in the coherent SI unit of its dimension
+    pub fn to_si(&self) -> Quantity {
+        Quantity::new(self.value.clone() * self.unit.scale.clone(), Unit::coherent(self.dimension()))
+    }
+}
+
+impl Mul for Quantity {
+    type Output = Quantity;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Quantity::new(self.value * rhs.value, self.unit * rhs.unit)
+    }
+}
+
+impl Div for Quantity {
+    type Output = Quantity;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Quantity::new(self.value / rhs.value, self.unit / rhs.unit)
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.unit.name.as_str() {
+            "1" => write!(f, "{}", self.value),
+            unit => write!(f, "{} {unit}", self.value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let distance = Quantity::new(Number::integer(3), Unit::symbol("km").unwrap());
+        assert_eq!(distance.convert_to(&Unit::symbol("m").unwrap()).unwrap().value, Number::integer(3000));
+        assert_eq!(distance.to_si().to_string(), "3000 m");
+
+        let speed = distance / Quantity::new(Number::integer(10), Unit::symbol("min").unwrap());
+        assert_eq!(speed.to_string(), "3/10 km/min");
+        assert_eq!(speed.convert_to(&Unit::from_str("km/h").unwrap()).unwrap().value, Number::integer(18));
+        assert!(speed.convert_to(&Unit::symbol("s").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Div, Mul};
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use num_rational::{BigRational, Rational32};
+
+use crate::expression::error::UnitError;
+use crate::expression::number::Number;
+use crate::expression::Expression;
+use crate::units::dimension::Dimension;
+
+/// SI prefixes with the power of ten they stand for. `u` is accepted for micro as well as `µ` and `μ`.
+const PREFIXES: [(&str, i32); 20] = [
+    ("Q", 30), ("R", 27), ("Y", 24), ("Z", 21), ("E", 18), ("P", 15), ("T", 12), ("G", 9), ("M", 6), ("k", 3),
+    ("h", 2), ("da", 1), ("d", -1), ("c", -2), ("m", -3), ("µ", -6), ("μ", -6), ("u", -6), ("n", -9), ("p", -12),
+];
+
+/// A unit of measurement: a dimension with the factor that converts one of the unit into the coherent SI unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    /// How the unit is written, like `km/h`
+    pub name: String,
+    /// Value of one of the unit in SI base units, like 1000 for km
+    pub scale: Number,
+    pub dimension: Dimension,
+}
+
+impl Unit {
+    pub fn new(name: &str, scale: Number, dimension: Dimension) -> Self {
+        Self { name: name.to_string(), scale, dimension }
+    }
+
+    /// The SI unit of the dimension that needs no conversion factor, like `m s^-2`
+    pub fn coherent(dimension: Dimension) -> Self {
+        Self::new(&dimension.to_string(), Number::integer(1), dimension)
+    }
+
+    pub fn dimensionless() -> Self {
+        Self::coherent(Dimension::dimensionless())
+    }
+
+    /// A single unit symbol like `N` or `km`, where SI prefixes are allowed on the units that take them
+    pub fn symbol(symbol: &str) -> Option<Unit> {
+        if let Some((scale, dimension, _)) = named(symbol) {
+            return Some(Unit::new(symbol, scale, dimension));
+        }
+        PREFIXES.iter().find_map(|(prefix, power)| {
+            let (scale, dimension, prefixable) = named(symbol.strip_prefix(prefix)?)?;
+            let factor = Number::Rational(BigRational::from_integer(BigInt::from(10)).pow(*power));
+            prefixable.then(|| Unit::new(symbol, scale * factor, dimension))
+        })
+    }
+
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.dimension == other.dimension
+    }
+
+    /// The factor that converts a value in this unit into a value in `to`
+    pub fn conversion_factor(&self, to: &Unit) -> Result<Number, UnitError> {
+        if !self.is_compatible(to) {
+            return Err(UnitError::IncompatibleDimensions(self.dimension, to.dimension));
+        }
+        Ok(self.scale.clone() / to.scale.clone())
+    }
+
+    /// Raises the unit to a power, failing when an exponent of its dimension overflows
+    pub fn pow(&self, exponent: Rational32) -> Result<Unit, UnitError> {
+        let name = match self.name.contains([' ', '/']) {
+            true => format!("({})^{exponent}", self.name),
+            false => format!("{}^{exponent}", self.name),
+        };
+        let exponent_number = Number::rational(*exponent.numer(), *exponent.denom());
+        let dimension = self.dimension.pow(exponent).ok_or(UnitError::ExponentOverflow(self.dimension, exponent))?;
+        Ok(Unit::new(&name, self.scale.pow(&exponent_number), dimension))
+    }
+
+    /// Reads a unit from an expression of unit symbols, like `kg * m^2 / s^2`
+    fn from_expression(expression: &Expression) -> Result<Unit, UnitError> {
+        match expression {
+            Expression::Variable(symbol) => Unit::symbol(symbol).ok_or_else(|| UnitError::UnknownUnit(symbol.clone())),
+            Expression::Number(number) => Ok(Unit::new(&number.to_string(), number.clone(), Dimension::dimensionless())),
+            Expression::Multiply(multiply) => multiply.0.iter()
+                .try_fold(Unit::dimensionless(), |product, factor| Ok(product * Unit::from_expression(factor)?)),
+            Expression::Invert(invert) => Ok(Unit::dimensionless() / Unit::from_expression(&invert.0)?),
+            Expression::Power(base, exponent) => {
+                let exponent = exponent.simplify();
+                match &exponent {
+                    Expression::Number(Number::Rational(ratio)) => match exponent_ratio(ratio) {
+                        Some(ratio) => Unit::from_expression(base)?.pow(ratio),
+                        None => Err(UnitError::NonRationalExponent(exponent)),
+                    },
+                    _ => Err(UnitError::NonRationalExponent(exponent)),
+                }
+            }
+            expression => Err(UnitError::UnknownUnit(expression.to_string())),
+        }
+    }
+}
+
+/// The exponent as a small ratio, as used for dimensions
+pub(crate) fn exponent_ratio(exponent: &BigRational) -> Option<Rational32> {
+    Some(Rational32::new(i32::try_from(exponent.numer()).ok()?, i32::try_from(exponent.denom()).ok()?))
+}
+
+/// Scale and dimension of a unit symbol without prefix, and whether it takes SI prefixes
+fn named(symbol: &str) -> Option<(Number, Dimension, bool)> {
+    let exact = |value: &str| Number::from_str(value).ok();
+    //                         m  kg  s  A  K mol cd
+    let (scale, exponents, prefixable) = match symbol {
+        "m" => (exact("1")?, [1, 0, 0, 0, 0, 0, 0], true),
+        "g" => (exact("0.001")?, [0, 1, 0, 0, 0, 0, 0], true),
+        "s" => (exact("1")?, [0, 0, 1, 0, 0, 0, 0], true),
+        "A" => (exact("1")?, [0, 0, 0, 1, 0, 0, 0], true),
+        "K" => (exact("1")?, [0, 0, 0, 0, 1, 0, 0], true),
+        "mol" => (exact("1")?, [0, 0, 0, 0, 0, 1, 0], true),
+        "cd" => (exact("1")?, [0, 0, 0, 0, 0, 0, 1], true),
+        "Hz" => (exact("1")?, [0, 0, -1, 0, 0, 0, 0], true),
+        "N" => (exact("1")?, [1, 1, -2, 0, 0, 0, 0], true),
+        "Pa" => (exact("1")?, [-1, 1, -2, 0, 0, 0, 0], true),
+        "J" => (exact("1")?, [2, 1, -2, 0, 0, 0, 0], true),
+        "W" => (exact("1")?, [2, 1, -3, 0, 0, 0, 0], true),
+        "C" => (exact("1")?, [0, 0, 1, 1, 0, 0, 0], true),
+        "V" => (exact("1")?, [2, 1, -3, -1, 0, 0, 0], true),
+        "Ω" | "ohm" => (exact("1")?, [2, 1, -3, -2, 0, 0, 0], true),
+        "F" => (exact("1")?, [-2, -1, 4, 2, 0, 0, 0], true),
+        "T" => (exact("1")?, [0, 1, -2, -1, 0, 0, 0], true),
+        "L" => (exact("0.001")?, [3, 0, 0, 0, 0, 0, 0], true),
+        "eV" => (exact("1.602176634e-19")?, [2, 1, -2, 0, 0, 0, 0], true),
+        "min" => (exact("60")?, [0, 0, 1, 0, 0, 0, 0], false),
+        "h" => (exact("3600")?, [0, 0, 1, 0, 0, 0, 0], false),
+        "day" => (exact("86400")?, [0, 0, 1, 0, 0, 0, 0], false),
+        "au" => (exact("149597870700")?, [1, 0, 0, 0, 0, 0, 0], false),
+        _ => return None,
+    };
+    Some((scale, Dimension::from_integers(exponents), prefixable))
+}
+
+/// Parses units written like expressions of symbols, for example `km/h`, `m s^-2` or `kg*m^2/s^2`
+impl FromStr for Unit {
+    type Err = UnitError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let expression = Expression::from_str(input)?;
+        let unit = Unit::from_expression(&expression)?;
+        Ok(Unit { name: input.trim().to_string(), ..unit })
+    }
+}
+
+impl Mul for Unit {
+    type Output = Unit;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let name = match (self.name.as_str(), rhs.name.as_str()) {
+            ("1", name) | (name, "1") => name.to_string(),
+            (left, right) => format!("{left} {right}"),
+        };
+        Unit::new(&name, self.scale * rhs.scale, self.dimension * rhs.dimension)
+    }
+}
+
+impl Div for Unit {
+    type Output = Unit;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let name = match (self.name.as_str(), rhs.name.as_str()) {
+            (name, "1") => name.to_string(),
+            (left, right) if right.contains([' ', '/']) => format!("{left}/({right})"),
+            (left, right) => format!("{left}/{right}"),
+        };
+        Unit::new(&name, self.scale / rhs.scale, self.dimension / rhs.dimension)
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::dimension::BaseDimension;
+
+    #[test]
+    fn test_symbol() {
+        let kilometre = Unit::symbol("km").unwrap();
+        assert_eq!(kilometre.scale, Number::integer(1000));
+        assert_eq!(kilometre.dimension, Dimension::of(BaseDimension::Length));
+        assert_eq!(Unit::symbol("kg").unwrap().scale, Number::integer(1));
+        assert_eq!(Unit::symbol("µs").unwrap().scale, Number::rational(1, 1_000_000));
+        assert_eq!(Unit::symbol("min").unwrap().scale, Number::integer(60));
+        assert_eq!(Unit::symbol("kmin"), None);
+        assert_eq!(Unit::symbol("parsec"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let speed = Unit::from_str("km/h").unwrap();
+        assert_eq!(speed.scale, Number::rational(5, 18));
+        assert_eq!(speed.dimension.to_string(), "m s^-1");
+        assert_eq!(speed.to_string(), "km/h");
+        assert_eq!(Unit::from_str("km/min").unwrap().scale, Number::rational(50, 3));
+        assert_eq!(Unit::from_str("min kW").unwrap().dimension, Unit::symbol("J").unwrap().dimension);
+        assert_eq!(Unit::from_str("kg m^2 s^-2").unwrap().dimension, Unit::symbol("J").unwrap().dimension);
+        assert_eq!(Unit::from_str("kWh").unwrap_err(), UnitError::UnknownUnit("kWh".to_string()));
+        assert_eq!(Unit::from_str("kW h").unwrap().conversion_factor(&Unit::symbol("MJ").unwrap()), Ok(Number::rational(18, 5)));
+        assert!(matches!(Unit::from_str("m^x"), Err(UnitError::NonRationalExponent(_))));
+        assert!(matches!(Unit::from_str("(m^100000)^100000"), Err(UnitError::ExponentOverflow(..))));
+        assert!(matches!(Unit::from_str("m +"), Err(UnitError::Parse(_))));
+    }
+
+    #[test]
+    fn test_conversion() {
+        let metre = Unit::symbol("m").unwrap();
+        let inch_scale = Unit::new("in", Number::from_str("0.0254").unwrap(), metre.dimension);
+        assert_eq!(metre.conversion_factor(&Unit::symbol("cm").unwrap()), Ok(Number::integer(100)));
+        assert_eq!(inch_scale.conversion_factor(&Unit::symbol("mm").unwrap()), Ok(Number::rational(127, 5)));
+        assert_eq!(
+            metre.conversion_factor(&Unit::symbol("s").unwrap()),
+            Err(UnitError::IncompatibleDimensions(metre.dimension, Unit::symbol("s").unwrap().dimension))
+        );
+        assert_eq!((metre.clone() / Unit::symbol("s").unwrap().pow(Rational32::from_integer(2)).unwrap()).to_string(), "m/s^2");
+    }
+}
@@ -7,14 +7,14 @@ pub trait InsertOrAdd<T, V>{
     fn insert_or_add(&mut self, key: T, value: V);
 }
 
-impl<T: Hash + Eq, V: Add<Output=V> + Clone + Copy> InsertOrAdd<T, V> for HashMap<T, V>{
+impl<T: Hash + Eq, V: Add<Output=V> + Clone> InsertOrAdd<T, V> for HashMap<T, V>{
     fn insert_or_add(&mut self, key: T, value: V) {
-        self.entry(key).and_modify(|v| *v = *v + value).or_insert(value);
+        self.entry(key).and_modify(|v| *v = v.clone() + value.clone()).or_insert(value);
     }
 }
 
-impl<T: Hash + Eq, V: Add<Output=V> + Clone + Copy> InsertOrAdd<T, V> for FxHashMap<T, V>{
+impl<T: Hash + Eq, V: Add<Output=V> + Clone> InsertOrAdd<T, V> for FxHashMap<T, V>{
     fn insert_or_add(&mut self, key: T, value: V) {
-        self.entry(key).and_modify(|v| *v = *v + value).or_insert(value);
+        self.entry(key).and_modify(|v| *v = v.clone() + value.clone()).or_insert(value);
     }
 }
\ No newline at end of file
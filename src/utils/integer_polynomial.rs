@@ -0,0 +1,280 @@
+//! Exact arithmetic on polynomials with integer coefficients, ordered from the constant term upwards
+
+/// Divisors are only enumerated for numbers up to this size
+const MAX_DIVISOR_SEARCH: i128 = 1_000_000_000_000;
+
+/// Upper bound on the candidate factors tried by Kronecker's method for a single degree
+const MAX_KRONECKER_CANDIDATES: usize = 100_000;
+
+pub fn degree(coefficients: &[i128]) -> usize {
+    coefficients.len().saturating_sub(1)
+}
+
+/// Positive divisors of n in increasing order, or none at all when n is zero or too large to search
+pub fn divisors(n: i128) -> Vec<i128> {
+    let n = n.abs();
+    if n == 0 || n > MAX_DIVISOR_SEARCH {
+        return vec![];
+    }
+    let mut small = Vec::new();
+    let mut large = Vec::new();
+    let mut i = 1;
+    while i * i <= n {
+        if n % i == 0 {
+            small.push(i);
+            if i != n / i {
+                large.push(n / i);
+            }
+        }
+        i += 1;
+    }
+    small.into_iter().chain(large.into_iter().rev()).collect()
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Greatest common divisor of the coefficients, carrying the sign of the leading coefficient
+pub fn content(coefficients: &[i128]) -> i128 {
+    let content = coefficients.iter().fold(0, |content, coefficient| gcd(content, *coefficient));
+    match coefficients.last() {
+        Some(leading) if *leading < 0 => -content,
+        _ => content,
+    }
+}
+
+pub fn evaluate(coefficients: &[i128], x: i128) -> Option<i128> {
+    coefficients.iter().rev().try_fold(0i128, |value, coefficient| value.checked_mul(x)?.checked_add(*coefficient))
+}
+
+/// Exact division by q * x - p, or `None` when it leaves a remainder
+pub fn divide_by_linear(coefficients: &[i128], p: i128, q: i128) -> Option<Vec<i128>> {
+    let degree = degree(coefficients);
+    let mut quotient = vec![0; degree];
+    let mut carry = coefficients[degree];
+    for k in (1..=degree).rev() {
+        if carry % q != 0 {
+            return None;
+        }
+        quotient[k - 1] = carry / q;
+        carry = coefficients[k - 1].checked_add(p.checked_mul(quotient[k - 1])?)?;
+    }
+    (carry == 0).then_some(quotient)
+}
+
+/// Exact long division, or `None` when it leaves a remainder or leaves the integers
+pub fn divide(dividend: &[i128], divisor: &[i128]) -> Option<Vec<i128>> {
+    if divisor.len() > dividend.len() {
+        return None;
+    }
+    let leading = *divisor.last()?;
+    let mut remainder = dividend.to_vec();
+    let mut quotient = vec![0; dividend.len() - divisor.len() + 1];
+    for shift in (0..quotient.len()).rev() {
+        let top = remainder[shift + divisor.len() - 1];
+        if top % leading != 0 {
+            return None;
+        }
+        let factor = top / leading;
+        quotient[shift] = factor;
+        for (i, coefficient) in divisor.iter().enumerate() {
+            remainder[shift + i] = remainder[shift + i].checked_sub(factor.checked_mul(*coefficient)?)?;
+        }
+    }
+    remainder.iter().all(|coefficient| *coefficient == 0).then_some(quotient)
+}
+
+/// Divides every rational root p / q out of the polynomial, returning the roots found as (p, q) pairs
+pub fn extract_rational_roots(coefficients: &mut Vec<i128>) -> Vec<(i128, i128)> {
+    let mut roots = Vec::new();
+    'search: while coefficients.len() > 1 {
+        if coefficients[0] == 0 {
+            coefficients.remove(0);
+            roots.push((0, 1));
+            continue;
+        }
+        let leading = coefficients[coefficients.len() - 1];
+        for p in divisors(coefficients[0]) {
+            for q in divisors(leading) {
+                for p in [p, -p] {
+                    if gcd(p, q) != 1 {
+                        continue;
+                    }
+                    if let Some(quotient) = divide_by_linear(coefficients, p, q) {
+                        *coefficients = quotient;
+                        roots.push((p, q));
+                        continue 'search;
+                    }
+                }
+            }
+        }
+        break;
+    }
+    roots
+}
+
+/// Factors the polynomial into its content and factors that are irreducible over the rationals,
+/// each with its multiplicity. Factors are only searched up to the bounds above,
+/// so very large coefficients can leave a reducible factor in the result.
+pub fn factor(coefficients: &[i128]) -> (i128, Vec<(Vec<i128>, usize)>) {
+    let content = content(coefficients);
+    if content == 0 {
+        return (0, vec![]);
+    }
+    let mut remaining: Vec<i128> = coefficients.iter().map(|coefficient| coefficient / content).collect();
+    let mut factors = Vec::new();
+
+    let mut degree_to_try = 1;
+    while 2 * degree_to_try <= degree(&remaining) || (degree_to_try == 1 && degree(&remaining) == 1 && remaining[0] == 0) {
+        let Some(factor) = find_factor(&remaining, degree_to_try) else {
+            degree_to_try += 1;
+            continue;
+        };
+        let mut multiplicity = 0;
+        while let Some(quotient) = divide(&remaining, &factor) {
+            remaining = quotient;
+            multiplicity += 1;
+        }
+        factors.push((factor, multiplicity));
+    }
+    if degree(&remaining) > 0 {
+        factors.push((remaining, 1));
+    }
+    (content, factors)
+}
+
+/// A primitive factor of exactly the given degree, with positive leading coefficient
+fn find_factor(coefficients: &[i128], degree: usize) -> Option<Vec<i128>> {
+    if degree == 1 {
+        let mut remaining = coefficients.to_vec();
+        let (p, q) = *extract_rational_roots(&mut remaining).first()?;
+        return Some(vec![-p, q]);
+    }
+    kronecker(coefficients, degree)
+}
+
+/// Kronecker's method: a factor g of f has g(x) dividing f(x) at every integer x,
+/// so g is found among the polynomials interpolating divisors of f at degree + 1 points
+fn kronecker(coefficients: &[i128], degree: usize) -> Option<Vec<i128>> {
+    let points: Vec<i128> = (0..).map(|i: i128| if i % 2 == 0 { i / 2 } else { -(i + 1) / 2 }).take(degree + 1).collect();
+    let mut choices: Vec<Vec<i128>> = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        let value = evaluate(coefficients, *point)?;
+        let divisors = divisors(value);
+        if divisors.is_empty() {
+            return None;
+        }
+        // A factor and its negation are equivalent, so fix the sign at the first point
+        choices.push(match i {
+            0 => divisors,
+            _ => divisors.iter().flat_map(|divisor| [*divisor, -divisor]).collect(),
+        });
+    }
+    let candidates = choices.iter().try_fold(1usize, |count, choice| count.checked_mul(choice.len()));
+    if candidates.is_none_or(|candidates| candidates > MAX_KRONECKER_CANDIDATES) {
+        return None;
+    }
+
+    let mut indices = vec![0; choices.len()];
+    loop {
+        let values: Vec<i128> = indices.iter().zip(&choices).map(|(index, choice)| choice[*index]).collect();
+        if let Some(candidate) = interpolate(&points, &values) {
+            if candidate.len() == degree + 1 {
+                let content = content(&candidate);
+                let candidate: Vec<i128> = candidate.iter().map(|coefficient| coefficient / content).collect();
+                if divide(coefficients, &candidate).is_some() {
+                    return Some(candidate);
+                }
+            }
+        }
+        // Advance to the next combination of divisors
+        let mut position = 0;
+        loop {
+            if position == indices.len() {
+                return None;
+            }
+            indices[position] += 1;
+            if indices[position] < choices[position].len() {
+                break;
+            }
+            indices[position] = 0;
+            position += 1;
+        }
+    }
+}
+
+/// The polynomial through the given points using Newton's divided differences,
+/// or `None` if it does not have integer coefficients
+fn interpolate(points: &[i128], values: &[i128]) -> Option<Vec<i128>> {
+    // Divided differences as fractions (numerator, denominator)
+    let mut differences: Vec<(i128, i128)> = values.iter().map(|value| (*value, 1)).collect();
+    for level in 1..points.len() {
+        for i in (level..points.len()).rev() {
+            let (a, b) = (differences[i], differences[i - 1]);
+            let numerator = a.0.checked_mul(b.1)?.checked_sub(b.0.checked_mul(a.1)?)?;
+            let denominator = a.1.checked_mul(b.1)?.checked_mul(points[i] - points[i - level])?;
+            differences[i] = reduce(numerator, denominator);
+        }
+    }
+    // Horner's scheme on the Newton form, coefficients as fractions
+    let mut polynomial: Vec<(i128, i128)> = vec![*differences.last()?];
+    for i in (0..points.len() - 1).rev() {
+        let mut next = vec![(0, 1); polynomial.len() + 1];
+        for (power, coefficient) in polynomial.iter().enumerate() {
+            next[power + 1] = add(next[power + 1], *coefficient)?;
+            next[power] = add(next[power], (coefficient.0.checked_mul(-points[i])?, coefficient.1))?;
+        }
+        next[0] = add(next[0], differences[i])?;
+        polynomial = next;
+    }
+    while polynomial.len() > 1 && polynomial.last()?.0 == 0 {
+        polynomial.pop();
+    }
+    polynomial.iter()
+        .map(|(numerator, denominator)| (numerator % denominator == 0).then(|| numerator / denominator))
+        .collect()
+}
+
+fn add(a: (i128, i128), b: (i128, i128)) -> Option<(i128, i128)> {
+    let numerator = a.0.checked_mul(b.1)?.checked_add(b.0.checked_mul(a.1)?)?;
+    Some(reduce(numerator, a.1.checked_mul(b.1)?))
+}
+
+fn reduce(numerator: i128, denominator: i128) -> (i128, i128) {
+    let divisor = gcd(numerator, denominator).max(1) * denominator.signum();
+    (numerator / divisor, denominator / divisor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rational_roots() {
+        // 2x^3 - 3x^2 - 3x + 2 = (2x - 1)(x + 1)(x - 2)
+        let mut coefficients = vec![2, -3, -3, 2];
+        let mut roots = extract_rational_roots(&mut coefficients);
+        roots.sort();
+        assert_eq!(roots, vec![(-1, 1), (1, 2), (2, 1)]);
+        assert_eq!(coefficients, vec![1]);
+    }
+
+    #[test]
+    fn test_factor() {
+        // 2x^2 - 2 = 2 (x - 1)(x + 1)
+        assert_eq!(factor(&[-2, 0, 2]), (2, vec![(vec![-1, 1], 1), (vec![1, 1], 1)]));
+        // x^4 - 2x^2 + 1 = (x - 1)^2 (x + 1)^2
+        assert_eq!(factor(&[1, 0, -2, 0, 1]), (1, vec![(vec![-1, 1], 2), (vec![1, 1], 2)]));
+        // x^4 + 4 = (x^2 - 2x + 2)(x^2 + 2x + 2)
+        assert_eq!(factor(&[4, 0, 0, 0, 1]), (1, vec![(vec![2, -2, 1], 1), (vec![2, 2, 1], 1)]));
+        // x^2 + 1 is irreducible
+        assert_eq!(factor(&[1, 0, 1]), (1, vec![(vec![1, 0, 1], 1)]));
+        // x^3 - x = x (x - 1)(x + 1)
+        assert_eq!(factor(&[0, -1, 0, 1]), (1, vec![(vec![0, 1], 1), (vec![-1, 1], 1), (vec![1, 1], 1)]));
+    }
+}
@@ -1 +1,3 @@
-pub mod insert_or_add;
\ No newline at end of file
+pub mod insert_or_add;
+pub mod integer_polynomial;
+pub mod special;
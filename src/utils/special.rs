@@ -0,0 +1,192 @@
+use std::f64::consts::PI;
+
+/// Coefficients of the Lanczos approximation with g = 7, good to about 15 significant digits
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+/// Gamma of larger integers overflows a float
+const MAX_FACTORIAL_ARGUMENT: f64 = 171.0;
+
+/// Gamma of larger arguments overflows a float
+const MAX_GAMMA_ARGUMENT: f64 = 171.624_376_956_302_7;
+
+/// Bernoulli numbers B_2, B_4, ..., B_20 for the asymptotic series of polygamma
+const BERNOULLI_NUMBERS: [f64; 10] = [
+    1.0 / 6.0,
+    -1.0 / 30.0,
+    1.0 / 42.0,
+    -1.0 / 30.0,
+    5.0 / 66.0,
+    -691.0 / 2730.0,
+    7.0 / 6.0,
+    -3617.0 / 510.0,
+    43867.0 / 798.0,
+    -174_611.0 / 330.0,
+];
+
+/// The recurrence shifts the argument of polygamma of order n above this plus n before the asymptotic series is used
+const POLYGAMMA_SERIES_START: f64 = 10.0;
+
+/// Below this the series for erf is used, above it the continued fraction for erfc
+const ERF_SERIES_LIMIT: f64 = 3.0;
+
+/// The gamma function, which is (n - 1)! for positive integers and has poles at 0, -1, -2, ...
+pub fn gamma(x: f64) -> f64 {
+    if x <= 0.0 && x.fract() == 0.0 {
+        return f64::NAN;
+    }
+    if x > MAX_GAMMA_ARGUMENT {
+        return f64::INFINITY;
+    }
+    if x.fract() == 0.0 && x <= MAX_FACTORIAL_ARGUMENT {
+        // Exact up to 22!, and closer than the approximation beyond that
+        return (1..x as u32).map(f64::from).product();
+    }
+    if x < 0.5 {
+        // Reflection formula: gamma(x) * gamma(1 - x) = pi / sin(pi * x)
+        return PI / ((PI * x).sin() * gamma(1.0 - x));
+    }
+    let x = x - 1.0;
+    let t = x + LANCZOS_G + 0.5;
+    let sum = LANCZOS_COEFFICIENTS[1..].iter().enumerate()
+        .fold(LANCZOS_COEFFICIENTS[0], |sum, (i, coefficient)| sum + coefficient / (x + i as f64 + 1.0));
+    // t ^ (x + 0.5) is split in two halves so that it does not overflow before the division by e ^ t
+    let half_power = t.powf(x / 2.0 + 0.25);
+    (2.0 * PI).sqrt() * half_power * (half_power * (-t).exp()) * sum
+}
+
+/// The polygamma function of order n, the (n + 1)-th derivative of ln(gamma(x)), which is the digamma function for n = 0.
+/// The order has to be an integer from 0 up to the largest whose factorial fits a float, otherwise the result is NaN.
+/// Like gamma the function has poles at 0, -1, -2, ...
+pub fn polygamma(n: f64, x: f64) -> f64 {
+    if !(0.0..=MAX_FACTORIAL_ARGUMENT - 1.0).contains(&n) || n.fract() != 0.0 || x.is_nan() || (x <= 0.0 && x.fract() == 0.0) {
+        return f64::NAN;
+    }
+    // (-1) ^ (n + 1), the sign of polygamma of order n for positive arguments
+    let sign = if n % 2.0 == 0.0 { -1.0 } else { 1.0 };
+    if x < 0.0 {
+        // Reflection formula: polygamma(n, x) = (-1) ^ n * polygamma(n, 1 - x) - pi * d^n/dx^n cot(pi * x)
+        return -sign * polygamma(n, 1.0 - x) - PI * cot_derivative(n as usize, x);
+    }
+    let factorial = gamma(n + 1.0);
+    // polygamma(n, x) = polygamma(n, x + 1) + (-1) ^ (n + 1) * n! / x ^ (n + 1)
+    let (mut x, mut shifted) = (x, 0.0);
+    while x < POLYGAMMA_SERIES_START + n {
+        shifted += sign * factorial / x.powf(n + 1.0);
+        x += 1.0;
+    }
+    let series = BERNOULLI_NUMBERS.iter().zip(1..).map(|(bernoulli, k)| {
+        let k = f64::from(k);
+        bernoulli * gamma(2.0 * k + n) / gamma(2.0 * k + 1.0) / x.powf(2.0 * k + n)
+    }).sum::<f64>();
+    let asymptotic = match n {
+        0.0 => x.ln() - 1.0 / (2.0 * x) - series,
+        _ => sign * (gamma(n) / x.powf(n) + factorial / (2.0 * x.powf(n + 1.0)) + series),
+    };
+    asymptotic + shifted
+}
+
+/// The n-th derivative of cot(pi * x), as a polynomial in c = cot(pi * x) built from d/dx c = -pi * (1 + c ^ 2)
+fn cot_derivative(n: usize, x: f64) -> f64 {
+    let mut coefficients = vec![0.0, 1.0];
+    for _ in 0..n {
+        let derivative: Vec<f64> = coefficients.iter().enumerate().skip(1).map(|(k, coefficient)| k as f64 * coefficient).collect();
+        coefficients = (0..derivative.len() + 2)
+            .map(|k| -PI * (derivative.get(k).unwrap_or(&0.0) + k.checked_sub(2).and_then(|k| derivative.get(k)).unwrap_or(&0.0)))
+            .collect();
+    }
+    let cot = 1.0 / (PI * x).tan();
+    coefficients.iter().rev().fold(0.0, |sum, coefficient| sum * cot + coefficient)
+}
+
+/// The error function 2 / sqrt(pi) * integral of e ^ (-t ^ 2) from 0 to x
+pub fn erf(x: f64) -> f64 {
+    if x < 0.0 {
+        return -erf(-x);
+    }
+    if x.is_nan() || x >= ERF_SERIES_LIMIT {
+        return 1.0 - erfc(x);
+    }
+    // erf(x) = 2 / sqrt(pi) * e ^ (-x ^ 2) * sum of 2^n x^(2n + 1) / (1 * 3 * ... * (2n + 1)), whose terms are all positive
+    let (mut term, mut sum) = (x, x);
+    for n in 1.. {
+        term *= 2.0 * x * x / (2 * n + 1) as f64;
+        sum += term;
+        if term <= sum * f64::EPSILON {
+            break;
+        }
+    }
+    2.0 / PI.sqrt() * (-x * x).exp() * sum
+}
+
+/// The complementary error function 1 - erf(x), accurate for large x where erf(x) rounds to 1
+pub fn erfc(x: f64) -> f64 {
+    if x < ERF_SERIES_LIMIT {
+        return 1.0 - erf(x);
+    }
+    // erfc(x) = e ^ (-x ^ 2) / sqrt(pi) / (x + (1/2) / (x + 1 / (x + (3/2) / (x + ...)))), evaluated from the tail
+    let fraction = (1..=60).rev().fold(x, |fraction, n| x + n as f64 / 2.0 / fraction);
+    (-x * x).exp() / PI.sqrt() / fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() <= 1e-13 * expected.abs().max(1e-300), "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_gamma() {
+        assert_close(gamma(5.0), 24.0);
+        assert_close(gamma(0.5), PI.sqrt());
+        assert_close(gamma(-1.5), 4.0 * PI.sqrt() / 3.0);
+        assert_close(gamma(4.5), 11.631_728_396_567_446);
+        assert_close(gamma(30.5), 4.822_696_933_490_909_5e31);
+        assert!(gamma(-2.0).is_nan());
+        assert!((gamma(171.5) / 9.483_367_566_824_795e307 - 1.0).abs() < 1e-11);
+        assert_eq!(gamma(172.0), f64::INFINITY);
+        assert_eq!(gamma(2000.0), f64::INFINITY);
+        assert_eq!(gamma(f64::INFINITY), f64::INFINITY);
+        assert_eq!(gamma(-2000.5), 0.0);
+    }
+
+    #[test]
+    fn test_polygamma() {
+        assert_close(polygamma(0.0, 1.0), -0.577_215_664_901_532_9);
+        assert_close(polygamma(0.0, 0.5), -1.963_510_026_021_423_5);
+        assert_close(polygamma(0.0, -0.5), 0.036_489_973_978_576_52);
+        assert_close(polygamma(0.0, 100.0), 4.600_161_852_738_087);
+        assert_close(polygamma(1.0, 1.0), PI * PI / 6.0);
+        assert_close(polygamma(2.0, 1.0), -2.404_113_806_319_188_5);
+        assert_close(polygamma(3.0, 0.25), 1_538.782_144_009_188);
+        assert_close(polygamma(1.0, -0.5), 8.934_802_200_544_68);
+        assert_close(polygamma(2.0, -1.25), 123.721_366_783_662);
+        assert!((polygamma(3.0, -999_999.5) / 194.818_182_068_005 - 1.0).abs() < 1e-9);
+        assert!(polygamma(0.0, -2.0).is_nan());
+        assert!(polygamma(1e12, 1.0).is_nan());
+        assert!(polygamma(0.5, 1.0).is_nan());
+    }
+
+    #[test]
+    fn test_erf() {
+        assert_eq!(erf(0.0), 0.0);
+        assert_close(erf(0.5), 0.520_499_877_813_046_5);
+        assert_close(erf(-1.0), -0.842_700_792_949_714_9);
+        assert_close(erfc(2.0), 0.004_677_734_981_047_265);
+        assert_close(erfc(5.0), 1.537_459_794_428_035_1e-12);
+        assert_close(erf(4.0) + erfc(4.0), 1.0);
+        assert!(erf(f64::NAN).is_nan());
+    }
+}